@@ -1,13 +1,17 @@
 use std::env::current_exe;
+use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{Parser, ValueEnum};
-use lumina_node::network::Network;
+use lumina_node::network::{Network, NetworkConfig};
 use serde_repr::Serialize_repr;
 
 use crate::native;
+use crate::query::{HeaderCommand, PeersParams, SampleParams};
 #[cfg(feature = "browser-node")]
 use crate::server;
+use crate::store::StoreCommand;
+use crate::{init, query, store};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize_repr)]
 #[repr(u8)]
@@ -21,8 +25,20 @@ pub(crate) enum ArgNetwork {
 
 #[derive(Debug, Parser)]
 pub(crate) enum CliArgs {
+    /// Create the config file and the node's identity
+    Init(init::Params),
     /// Run native node locally
     Node(native::Params),
+    /// Query the headers
+    #[command(subcommand)]
+    Header(HeaderCommand),
+    /// Sample the block at the given height
+    Sample(SampleParams),
+    /// List the peers the node connects to
+    Peers(PeersParams),
+    /// Inspect or reset the node's store
+    #[command(subcommand)]
+    Store(StoreCommand),
     /// Serve compiled wasm node to be run in the browser
     #[cfg(feature = "browser-node")]
     Browser(server::Params),
@@ -46,12 +62,32 @@ pub async fn run() -> Result<()> {
     let _guard = init_tracing();
 
     match args {
+        CliArgs::Init(args) => init::run(args).await,
         CliArgs::Node(args) => native::run(args).await,
+        CliArgs::Header(command) => query::run_header(command).await,
+        CliArgs::Sample(args) => query::run_sample(args).await,
+        CliArgs::Peers(args) => query::run_peers(args).await,
+        CliArgs::Store(command) => store::run(command).await,
         #[cfg(feature = "browser-node")]
         CliArgs::Browser(args) => server::run(args).await,
     }
 }
 
+/// Load the definition of a custom network from the TOML or JSON file.
+pub(crate) async fn load_network_config(path: &Path) -> Result<NetworkConfig> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read network config {}", path.display()))?;
+
+    let config = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content)?,
+        Some("json") => serde_json::from_str(&content)?,
+        _ => bail!("Network config must be a .toml or .json file"),
+    };
+
+    Ok(config)
+}
+
 fn init_tracing() -> tracing_appender::non_blocking::WorkerGuard {
     let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stdout());
 
@@ -0,0 +1,328 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use celestia_types::{VerificationOptions, DEFAULT_MAX_CLOCK_DRIFT};
+use clap::{Args, ValueEnum};
+use directories::ProjectDirs;
+use libp2p::Multiaddr;
+use lumina_node::blockstore::{DEFAULT_DISK_CAPACITY, DEFAULT_MEMORY_CAPACITY};
+use lumina_node::daser::DEFAULT_MAX_SAMPLES_NEEDED;
+use lumina_node::network::NetworkConfig;
+use lumina_node::syncer::{DEFAULT_HEAD_QUORUM, DEFAULT_STALL_TIMEOUT};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::common::{load_network_config, ArgNetwork};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const IDENTITY_FILE_NAME: &str = "identity.key";
+const CELESTIA_LOCAL_BRIDGE_RPC_ADDR: &str = "ws://localhost:26658";
+const MIB: u64 = 1024 * 1024;
+
+/// Configuration of the node, loaded from the TOML file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Network to connect.
+    #[serde(with = "network_name")]
+    pub(crate) network: ArgNetwork,
+    /// Path to the definition of a custom network, overrides `network`.
+    pub(crate) network_config: Option<PathBuf>,
+    /// Persistent header store path.
+    pub(crate) store: Option<PathBuf>,
+    /// Backend of the header store and blockstore.
+    pub(crate) store_backend: StoreBackend,
+    /// Path to the node's p2p identity keypair.
+    pub(crate) identity: Option<PathBuf>,
+    /// Listening addresses.
+    pub(crate) listen: Vec<Multiaddr>,
+    /// Bootnodes, overriding the ones of the network.
+    pub(crate) bootnodes: Vec<Multiaddr>,
+    /// RPC endpoint of the bridge node used to discover bootnodes in a private network.
+    pub(crate) rpc_url: String,
+    /// Address on which the metrics are served.
+    pub(crate) metrics: Option<SocketAddr>,
+    /// Data availability sampling settings.
+    pub(crate) das: DasConfig,
+    /// Header synchronization settings.
+    pub(crate) sync: SyncConfig,
+    /// Settings of the cache of the data fetched with bitswap.
+    pub(crate) cache: CacheConfig,
+}
+
+/// Data availability sampling settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DasConfig {
+    /// Maximum number of samples fetched for every block.
+    pub(crate) max_samples_per_block: usize,
+}
+
+/// Header synchronization settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SyncConfig {
+    /// Number of trusted peers that need to agree on the synchronized head.
+    pub(crate) head_quorum: usize,
+    /// For how many hours the stored head can be used to verify the network head, 0 for no limit.
+    ///
+    /// Disabled by default, as a fresh store starts from the genesis header which is already
+    /// outside of the trusting period.
+    pub(crate) trusting_period_hours: u64,
+    /// How many seconds ahead of the local clock the time of the network head can be.
+    pub(crate) max_clock_drift_secs: u64,
+    /// After how many seconds without a new head it's polled from the trusted peers.
+    pub(crate) stall_timeout_secs: u64,
+}
+
+/// Settings of the cache of the data fetched with bitswap.
+///
+/// Used by all the backends, the memory backend has no on-disk tier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CacheConfig {
+    /// Maximum size of the data cached in memory, in MiB.
+    pub(crate) memory_mib: u64,
+    /// Maximum size of the data cached on disk, in MiB.
+    pub(crate) disk_mib: u64,
+}
+
+/// Backend of the header store and blockstore.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StoreBackend {
+    /// Persistent store in the redb database file.
+    Redb,
+    /// Persistent store in the sled database.
+    #[default]
+    Sled,
+    /// Non-persistent store, kept in memory.
+    Memory,
+}
+
+/// Arguments common for all the commands, overriding the values from the config file.
+#[derive(Debug, Args)]
+pub(crate) struct ConfigArgs {
+    /// Path to the config file.
+    #[arg(short, long)]
+    pub(crate) config: Option<PathBuf>,
+
+    /// Network to connect.
+    #[arg(short, long, value_enum)]
+    pub(crate) network: Option<ArgNetwork>,
+
+    /// Path to the TOML or JSON definition of a custom network to connect.
+    #[arg(long, conflicts_with = "network")]
+    pub(crate) network_config: Option<PathBuf>,
+
+    /// Bootnode multiaddr, including peer id. Can be used multiple times.
+    #[arg(short, long = "bootnode")]
+    pub(crate) bootnodes: Vec<Multiaddr>,
+
+    /// Persistent header store path.
+    #[arg(short, long = "store")]
+    pub(crate) store: Option<PathBuf>,
+
+    /// Backend of the header store and blockstore.
+    #[arg(long, value_enum)]
+    pub(crate) store_backend: Option<StoreBackend>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            network: ArgNetwork::default(),
+            network_config: None,
+            store: None,
+            store_backend: StoreBackend::default(),
+            identity: None,
+            listen: Vec::new(),
+            bootnodes: Vec::new(),
+            rpc_url: CELESTIA_LOCAL_BRIDGE_RPC_ADDR.to_owned(),
+            metrics: None,
+            das: DasConfig::default(),
+            sync: SyncConfig::default(),
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+impl Default for DasConfig {
+    fn default() -> Self {
+        DasConfig {
+            max_samples_per_block: DEFAULT_MAX_SAMPLES_NEEDED,
+        }
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        SyncConfig {
+            head_quorum: DEFAULT_HEAD_QUORUM,
+            trusting_period_hours: 0,
+            max_clock_drift_secs: DEFAULT_MAX_CLOCK_DRIFT.as_secs(),
+            stall_timeout_secs: DEFAULT_STALL_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            memory_mib: DEFAULT_MEMORY_CAPACITY as u64 / MIB,
+            disk_mib: DEFAULT_DISK_CAPACITY / MIB,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Capacity of the memory tier, in bytes.
+    pub(crate) fn memory_capacity(&self) -> usize {
+        usize::try_from(self.memory_mib.saturating_mul(MIB)).unwrap_or(usize::MAX)
+    }
+
+    /// Capacity of the on-disk tier, in bytes.
+    pub(crate) fn disk_capacity(&self) -> u64 {
+        self.disk_mib.saturating_mul(MIB)
+    }
+}
+
+impl SyncConfig {
+    /// Options used to verify the network head against the stored one.
+    pub(crate) fn verification_options(&self) -> VerificationOptions {
+        let trusting_period = (self.trusting_period_hours != 0)
+            .then(|| Duration::from_secs(self.trusting_period_hours * 3600));
+
+        VerificationOptions {
+            trusting_period,
+            max_clock_drift: Duration::from_secs(self.max_clock_drift_secs),
+            ..Default::default()
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file, applying the overrides from the command line.
+    ///
+    /// If path to the config isn't provided, the default one is used if it exists.
+    pub(crate) async fn load(args: &ConfigArgs) -> Result<Config> {
+        let mut config = match &args.config {
+            Some(path) => Config::read(path).await?,
+            None => {
+                let path = default_config_path()?;
+                if path.exists() {
+                    Config::read(&path).await?
+                } else {
+                    Config::default()
+                }
+            }
+        };
+
+        if let Some(network) = args.network {
+            config.network = network;
+            config.network_config = None;
+        }
+        if let Some(path) = &args.network_config {
+            config.network_config = Some(path.clone());
+        }
+        if !args.bootnodes.is_empty() {
+            config.bootnodes = args.bootnodes.clone();
+        }
+        if let Some(path) = &args.store {
+            config.store = Some(path.clone());
+        }
+        if let Some(backend) = args.store_backend {
+            config.store_backend = backend;
+        }
+
+        Ok(config)
+    }
+
+    /// Read the config from the TOML file.
+    pub(crate) async fn read(path: &Path) -> Result<Config> {
+        let content = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+
+        toml::from_str(&content).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Write the config to the TOML file.
+    pub(crate) async fn write(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        fs::write(path, content)
+            .await
+            .with_context(|| format!("Failed to write config {}", path.display()))
+    }
+
+    /// Get the configuration of the network to connect.
+    pub(crate) async fn network(&self) -> Result<NetworkConfig> {
+        match &self.network_config {
+            Some(path) => load_network_config(path).await,
+            None => Ok(NetworkConfig::canonical(self.network.into())),
+        }
+    }
+
+    /// Get the path of the p2p identity keypair.
+    pub(crate) fn identity_path(&self) -> Result<PathBuf> {
+        match &self.identity {
+            Some(path) => Ok(path.clone()),
+            None => Ok(project_dirs()?.data_dir().join(IDENTITY_FILE_NAME)),
+        }
+    }
+
+    /// Get the path of the persistent store for the given network.
+    pub(crate) fn store_path(&self, network_id: &str) -> Result<PathBuf> {
+        match &self.store {
+            Some(path) => Ok(path.clone()),
+            None => default_store_path(self.store_backend, network_id),
+        }
+    }
+}
+
+/// Get the default path of the persistent store of the given backend.
+///
+/// Sled keeps the store in a directory, while redb uses a single file.
+pub(crate) fn default_store_path(backend: StoreBackend, network_id: &str) -> Result<PathBuf> {
+    let cache_dir = project_dirs()?.cache_dir().to_owned();
+
+    match backend {
+        StoreBackend::Redb => Ok(cache_dir.join(format!("{network_id}.redb"))),
+        StoreBackend::Sled | StoreBackend::Memory => Ok(cache_dir.join(network_id)),
+    }
+}
+
+pub(crate) fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("co", "eiger", "lumina").context("Couldn't find lumina's directories")
+}
+
+/// Get the path of the default config file.
+pub(crate) fn default_config_path() -> Result<PathBuf> {
+    Ok(project_dirs()?.config_dir().join(CONFIG_FILE_NAME))
+}
+
+/// (De)serialize the network by its command line name.
+mod network_name {
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    pub(super) fn serialize<S>(network: &ArgNetwork, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value = network
+            .to_possible_value()
+            .expect("no variants are skipped");
+        serializer.serialize_str(value.get_name())
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<ArgNetwork, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        ArgNetwork::from_str(&name, true).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use libp2p::identity::Keypair;
+use libp2p::PeerId;
+use tokio::fs;
+
+use crate::common::ArgNetwork;
+use crate::config::{default_config_path, Config, StoreBackend};
+
+#[derive(Debug, Args)]
+pub(crate) struct Params {
+    /// Path of the config file to create.
+    #[arg(short, long)]
+    pub(crate) config: Option<PathBuf>,
+
+    /// Network to connect.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub(crate) network: ArgNetwork,
+
+    /// Backend of the header store and blockstore.
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) store_backend: StoreBackend,
+
+    /// Overwrite the existing config file.
+    #[arg(long)]
+    pub(crate) force: bool,
+}
+
+pub(crate) async fn run(args: Params) -> Result<()> {
+    let config_path = match args.config {
+        Some(path) => path,
+        None => default_config_path()?,
+    };
+
+    if config_path.exists() && !args.force {
+        bail!(
+            "Config {} already exists, use --force to overwrite it",
+            config_path.display()
+        );
+    }
+
+    let mut config = Config {
+        network: args.network,
+        store_backend: args.store_backend,
+        ..Config::default()
+    };
+
+    let network = config.network().await?;
+    let identity_path = config.identity_path()?;
+    let store_path = config.store_path(&network.id)?;
+
+    config.identity = Some(identity_path.clone());
+    match args.store_backend {
+        StoreBackend::Sled => {
+            fs::create_dir_all(&store_path).await?;
+            config.store = Some(store_path);
+        }
+        StoreBackend::Redb => {
+            if let Some(dir) = store_path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            config.store = Some(store_path);
+        }
+        StoreBackend::Memory => {}
+    }
+
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    config.write(&config_path).await?;
+
+    let peer_id = match fs::read(&identity_path).await {
+        Ok(bytes) => {
+            let keypair = Keypair::from_protobuf_encoding(&bytes)
+                .with_context(|| format!("Invalid identity keypair {}", identity_path.display()))?;
+            PeerId::from(keypair.public())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let keypair = Keypair::generate_ed25519();
+            write_keypair(&identity_path, &keypair).await?;
+            PeerId::from(keypair.public())
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    println!("Config:   {}", config_path.display());
+    println!("Identity: {} ({peer_id})", identity_path.display());
+    if let Some(store) = &config.store {
+        println!("Store:    {}", store.display());
+    }
+
+    Ok(())
+}
+
+async fn write_keypair(path: &PathBuf, keypair: &Keypair) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let bytes = keypair.to_protobuf_encoding()?;
+    fs::write(path, bytes)
+        .await
+        .with_context(|| format!("Failed to write identity keypair {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+
+    Ok(())
+}
@@ -2,8 +2,13 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 mod common;
+mod config;
+mod init;
+mod metrics;
 mod native;
+mod query;
 #[cfg(feature = "browser-node")]
 mod server;
+mod store;
 
 pub use common::run;
@@ -0,0 +1,140 @@
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+use lumina_node::blockstore::EdsCache;
+use lumina_node::node::Node;
+use lumina_node::store::Store;
+use lumina_node::syncer::SyncingStatus;
+use tracing::info;
+
+/// Serve the node's metrics in the Prometheus text format, including the statistics of the
+/// `cache` used as the node's blockstore.
+pub(crate) async fn serve<S>(addr: SocketAddr, node: Arc<Node<S>>, cache: EdsCache) -> Result<()>
+where
+    S: Store + 'static,
+{
+    let app = Router::new()
+        .route("/metrics", get(serve_metrics::<S>))
+        .with_state((node, cache));
+
+    info!("serving metrics on {addr}");
+    Ok(axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?)
+}
+
+async fn serve_metrics<S>(
+    State((node, cache)): State<(Arc<Node<S>>, EdsCache)>,
+) -> Result<String, StatusCode>
+where
+    S: Store + 'static,
+{
+    let syncing_info = node
+        .syncer_info()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let peer_tracker_info = node.peer_tracker_info();
+    let header_ex_server_stats = node
+        .header_ex_server_stats()
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+        // writing to a string never fails
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        let _ = writeln!(out, "{name} {value}");
+    };
+
+    metric(
+        "lumina_local_head_height",
+        "gauge",
+        "Height of the latest synchronized header.",
+        syncing_info.local_head,
+    );
+    metric(
+        "lumina_subjective_head_height",
+        "gauge",
+        "Height of the latest verified header seen in the network.",
+        syncing_info.subjective_head,
+    );
+    metric(
+        "lumina_syncer_stalled",
+        "gauge",
+        "Whether the latest verified header wasn't updated for a while.",
+        (syncing_info.status == SyncingStatus::Stalled).into(),
+    );
+    metric(
+        "lumina_connected_peers",
+        "gauge",
+        "Number of the connected peers.",
+        peer_tracker_info.num_connected_peers,
+    );
+    metric(
+        "lumina_connected_trusted_peers",
+        "gauge",
+        "Number of the connected trusted peers.",
+        peer_tracker_info.num_connected_trusted_peers,
+    );
+
+    metric(
+        "lumina_header_ex_rate_limited_requests_total",
+        "counter",
+        "Number of the header-ex requests rejected for exceeding the peer's rate limit.",
+        header_ex_server_stats.rate_limited_requests,
+    );
+    metric(
+        "lumina_header_ex_concurrency_limited_requests_total",
+        "counter",
+        "Number of the header-ex requests rejected for exceeding the peer's in-flight limit.",
+        header_ex_server_stats.concurrency_limited_requests,
+    );
+    metric(
+        "lumina_header_ex_penalized_peers_total",
+        "counter",
+        "Number of the peers penalized for abusing the header-ex protocol.",
+        header_ex_server_stats.penalized_peers,
+    );
+
+    let cache_stats = cache.stats();
+
+    metric(
+        "lumina_eds_cache_memory_hits_total",
+        "counter",
+        "Number of the bitswap lookups served from the memory cache.",
+        cache_stats.memory_hits,
+    );
+    metric(
+        "lumina_eds_cache_disk_hits_total",
+        "counter",
+        "Number of the bitswap lookups served from the on-disk cache.",
+        cache_stats.disk_hits,
+    );
+    metric(
+        "lumina_eds_cache_misses_total",
+        "counter",
+        "Number of the bitswap lookups not found in the cache.",
+        cache_stats.misses,
+    );
+    metric(
+        "lumina_eds_cache_memory_bytes",
+        "gauge",
+        "Size of the data in the memory cache.",
+        cache_stats.memory_size,
+    );
+    metric(
+        "lumina_eds_cache_disk_bytes",
+        "gauge",
+        "Size of the data in the on-disk cache.",
+        cache_stats.disk_size,
+    );
+
+    Ok(out)
+}
@@ -1,106 +1,298 @@
 use std::env;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
+use blockstore::Blockstore;
 use celestia_rpc::prelude::*;
 use celestia_rpc::Client;
 use clap::Parser;
-use directories::ProjectDirs;
 use libp2p::{identity, multiaddr::Protocol, Multiaddr};
-use lumina_node::blockstore::SledBlockstore;
-use lumina_node::network::{canonical_network_bootnodes, network_genesis, network_id, Network};
+use lumina_node::blockstore::EdsCache;
+use lumina_node::network::{network_id, Network, NetworkConfig};
 use lumina_node::node::{Node, NodeConfig};
-use lumina_node::store::{SledStore, Store};
+use lumina_node::store::{InMemoryStore, RedbStore, SledStore, Store};
 use sled::Db;
 use tokio::fs;
 use tokio::task::spawn_blocking;
 use tokio::time::sleep;
-use tracing::info;
-use tracing::warn;
+use tracing::{info, warn};
 
-use crate::common::ArgNetwork;
-
-const CELESTIA_LOCAL_BRIDGE_RPC_ADDR: &str = "ws://localhost:26658";
+use crate::config::{project_dirs, Config, ConfigArgs, StoreBackend};
+use crate::metrics;
 
 #[derive(Debug, Parser)]
 pub(crate) struct Params {
-    /// Network to connect.
-    #[arg(short, long, value_enum, default_value_t)]
-    pub(crate) network: ArgNetwork,
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
 
     /// Listening addresses. Can be used multiple times.
     #[arg(short, long = "listen")]
     pub(crate) listen_addrs: Vec<Multiaddr>,
 
-    /// Bootnode multiaddr, including peer id. Can be used multiple times.
-    #[arg(short, long = "bootnode")]
-    pub(crate) bootnodes: Vec<Multiaddr>,
+    /// RPC endpoint of the bridge node used to discover bootnodes in a private network.
+    #[arg(long)]
+    pub(crate) rpc_url: Option<String>,
+
+    /// Address on which the metrics are served.
+    #[arg(long)]
+    pub(crate) metrics: Option<SocketAddr>,
+
+    /// Maximum number of samples fetched for every block.
+    #[arg(long)]
+    pub(crate) das_max_samples_per_block: Option<usize>,
+
+    /// Number of trusted peers that need to agree on the synchronized head.
+    #[arg(long)]
+    pub(crate) sync_head_quorum: Option<usize>,
+
+    /// For how many hours the stored head can be used to verify the network head, 0 for no limit.
+    #[arg(long)]
+    pub(crate) sync_trusting_period_hours: Option<u64>,
 
-    /// Persistent header store path.
-    #[arg(short, long = "store")]
-    pub(crate) store: Option<PathBuf>,
+    /// How many seconds ahead of the local clock the time of the network head can be.
+    #[arg(long)]
+    pub(crate) sync_max_clock_drift_secs: Option<u64>,
+
+    /// After how many seconds without a new head it's polled from the trusted peers.
+    #[arg(long)]
+    pub(crate) sync_stall_timeout_secs: Option<u64>,
+}
+
+/// Header store and blockstore of the node, opened with the configured backend.
+pub(crate) enum Stores {
+    Redb {
+        path: PathBuf,
+        store: RedbStore,
+        blockstore: EdsCache,
+    },
+    Sled {
+        path: PathBuf,
+        db: Db,
+        store: SledStore,
+        blockstore: EdsCache,
+    },
+    Memory {
+        store: InMemoryStore,
+        blockstore: EdsCache,
+    },
 }
 
 pub(crate) async fn run(args: Params) -> Result<()> {
-    let network = args.network.into();
-    let p2p_local_keypair = identity::Keypair::generate_ed25519();
+    let mut config = Config::load(&args.config).await?;
 
-    let p2p_bootnodes = if args.bootnodes.is_empty() {
-        match network {
-            Network::Private => fetch_bridge_multiaddrs(CELESTIA_LOCAL_BRIDGE_RPC_ADDR).await?,
-            network => canonical_network_bootnodes(network).collect(),
-        }
-    } else {
-        args.bootnodes
-    };
+    if !args.listen_addrs.is_empty() {
+        config.listen = args.listen_addrs;
+    }
+    if let Some(rpc_url) = args.rpc_url {
+        config.rpc_url = rpc_url;
+    }
+    if let Some(addr) = args.metrics {
+        config.metrics = Some(addr);
+    }
+    if let Some(max_samples) = args.das_max_samples_per_block {
+        config.das.max_samples_per_block = max_samples;
+    }
+    if let Some(head_quorum) = args.sync_head_quorum {
+        config.sync.head_quorum = head_quorum;
+    }
+    if let Some(trusting_period_hours) = args.sync_trusting_period_hours {
+        config.sync.trusting_period_hours = trusting_period_hours;
+    }
+    if let Some(max_clock_drift_secs) = args.sync_max_clock_drift_secs {
+        config.sync.max_clock_drift_secs = max_clock_drift_secs;
+    }
+    if let Some(stall_timeout_secs) = args.sync_stall_timeout_secs {
+        config.sync.stall_timeout_secs = stall_timeout_secs;
+    }
+    if config.sync.head_quorum == 0 {
+        bail!("Head quorum must be at least 1");
+    }
+    if config.sync.stall_timeout_secs == 0 {
+        bail!("Stall timeout must be at least 1 second");
+    }
 
-    let network_id = network_id(network).to_owned();
-    let genesis_hash = network_genesis(network);
+    let network = config.network().await?;
 
     info!("Initializing store");
-    let db = open_db(args.store, &network_id).await?;
-    let store = SledStore::new(db.clone()).await?;
-    let blockstore = SledBlockstore::new(db).await?;
+    match open_stores(&config, &network.id).await? {
+        Stores::Redb {
+            store, blockstore, ..
+        } => run_node(&config, network, blockstore, store).await,
+        Stores::Sled {
+            store, blockstore, ..
+        } => run_node(&config, network, blockstore, store).await,
+        Stores::Memory { store, blockstore } => run_node(&config, network, blockstore, store).await,
+    }
+}
 
+async fn run_node<S>(
+    config: &Config,
+    network: NetworkConfig,
+    blockstore: EdsCache,
+    store: S,
+) -> Result<()>
+where
+    S: Store + 'static,
+{
     match store.head_height().await {
         Ok(height) => info!("Initialised store with head height: {height}"),
         Err(_) => info!("Initialised new store"),
     }
 
-    let node = Node::new(NodeConfig {
-        network_id,
-        genesis_hash,
+    let cache = blockstore.clone();
+    let node = start_node(config, network, config.listen.clone(), blockstore, store).await?;
+    let node = Arc::new(node);
+
+    if let Some(addr) = config.metrics {
+        let node = node.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr, node, cache).await {
+                warn!("Metrics server failed: {e}");
+            }
+        });
+    }
+
+    node.wait_connected_trusted().await?;
+
+    // We have nothing else to do, but we want to keep main alive
+    loop {
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Start the node with the configured network, identity and the given stores.
+pub(crate) async fn start_node<B, S>(
+    config: &Config,
+    network: NetworkConfig,
+    p2p_listen_on: Vec<Multiaddr>,
+    blockstore: B,
+    store: S,
+) -> Result<Node<S>>
+where
+    B: Blockstore + 'static,
+    S: Store + 'static,
+{
+    let p2p_local_keypair = match load_keypair(&config.identity_path()?).await? {
+        Some(keypair) => keypair,
+        None => identity::Keypair::generate_ed25519(),
+    };
+
+    let p2p_bootnodes = if !config.bootnodes.is_empty() {
+        config.bootnodes.clone()
+    } else if network.bootnodes.is_empty() && network.id == network_id(Network::Private) {
+        fetch_bridge_multiaddrs(&config.rpc_url).await?
+    } else {
+        network.bootnodes
+    };
+
+    Node::new(NodeConfig {
+        network_id: network.id,
+        genesis_hash: network.genesis_hash,
+        p2p_protocol_versions: network.protocol_versions,
         p2p_local_keypair,
         p2p_bootnodes,
-        p2p_listen_on: args.listen_addrs,
+        p2p_listen_on,
+        das_max_samples_per_block: config.das.max_samples_per_block,
+        syncer_head_quorum: config.sync.head_quorum,
+        syncer_verification_options: config.sync.verification_options(),
+        syncer_stall_timeout: Duration::from_secs(config.sync.stall_timeout_secs),
         blockstore,
         store,
     })
     .await
-    .context("Failed to start node")?;
+    .context("Failed to start node")
+}
 
-    node.wait_connected_trusted().await?;
+/// Open the header store and blockstore of the given network with the configured backend.
+pub(crate) async fn open_stores(config: &Config, network_id: &str) -> Result<Stores> {
+    match config.store_backend {
+        StoreBackend::Redb => {
+            let path = config.store_path(network_id)?;
+            let store = open_redb_store(&path, config.store.is_none()).await?;
+            let blockstore = EdsCache::with_redb(
+                store.raw_db(),
+                config.cache.memory_capacity(),
+                config.cache.disk_capacity(),
+            )
+            .await?;
 
-    // We have nothing else to do, but we want to keep main alive
-    loop {
-        sleep(Duration::from_secs(1)).await;
+            Ok(Stores::Redb {
+                path,
+                store,
+                blockstore,
+            })
+        }
+        StoreBackend::Sled => {
+            let path = config.store_path(network_id)?;
+            let db = open_db(path.clone(), config.store.is_none()).await?;
+            let store = SledStore::new(db.clone()).await?;
+            let blockstore = EdsCache::with_sled(
+                db.clone(),
+                config.cache.memory_capacity(),
+                config.cache.disk_capacity(),
+            )
+            .await?;
+
+            Ok(Stores::Sled {
+                path,
+                db,
+                store,
+                blockstore,
+            })
+        }
+        StoreBackend::Memory => Ok(Stores::Memory {
+            store: InMemoryStore::new(),
+            blockstore: EdsCache::in_memory(config.cache.memory_capacity()),
+        }),
     }
 }
 
-async fn open_db(path: Option<PathBuf>, network_id: &str) -> Result<Db> {
-    if let Some(path) = path {
-        let db = spawn_blocking(|| sled::open(path)).await??;
-        return Ok(db);
+async fn open_redb_store(path: &Path, is_default_path: bool) -> Result<RedbStore> {
+    if is_default_path {
+        migrate_old_cache_dir().await?;
     }
 
-    let cache_dir =
-        ProjectDirs::from("co", "eiger", "lumina").context("Couldn't find lumina's cache dir")?;
-    let mut cache_dir = cache_dir.cache_dir().to_owned();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
 
-    // TODO: remove it in 2 months or after a few releases
-    // If we find an old ('celestia') cache dir, move it to the new one.
-    if let Some(old_cache_dir) = ProjectDirs::from("co", "eiger", "celestia") {
+    RedbStore::open(path).await.with_context(|| {
+        format!(
+            "Failed to open the store at {}, is another node using it?",
+            path.display()
+        )
+    })
+}
+
+pub(crate) async fn open_db(path: PathBuf, is_default_path: bool) -> Result<Db> {
+    if is_default_path {
+        migrate_old_cache_dir().await?;
+    }
+
+    let db = spawn_blocking({
+        let path = path.clone();
+        move || sled::open(path)
+    })
+    .await?
+    .with_context(|| {
+        format!(
+            "Failed to open the store at {}, is another node using it?",
+            path.display()
+        )
+    })?;
+
+    Ok(db)
+}
+
+// TODO: remove it in 2 months or after a few releases
+// If we find an old ('celestia') cache dir, move it to the new one.
+async fn migrate_old_cache_dir() -> Result<()> {
+    let cache_dir = project_dirs()?.cache_dir().to_owned();
+
+    if let Some(old_cache_dir) = directories::ProjectDirs::from("co", "eiger", "celestia") {
         let old_cache_dir = old_cache_dir.cache_dir();
         if old_cache_dir.exists() && !cache_dir.exists() {
             warn!(
@@ -112,11 +304,21 @@ async fn open_db(path: Option<PathBuf>, network_id: &str) -> Result<Db> {
         }
     }
 
-    cache_dir.push(network_id);
-    // TODO: should we create there also a subdirectory for the 'db'
-    // in case we want to put there some other stuff too?
-    let db = spawn_blocking(|| sled::open(cache_dir)).await??;
-    Ok(db)
+    Ok(())
+}
+
+/// Load the node's identity keypair, if it was created.
+async fn load_keypair(path: &Path) -> Result<Option<identity::Keypair>> {
+    let bytes = match fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let keypair = identity::Keypair::from_protobuf_encoding(&bytes)
+        .with_context(|| format!("Invalid identity keypair {}", path.display()))?;
+
+    Ok(Some(keypair))
 }
 
 /// Get the address of the local bridge node
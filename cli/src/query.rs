@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use blockstore::Blockstore;
+use clap::{Args, Subcommand};
+use futures::future::join_all;
+use lumina_node::blockstore::InMemoryBlockstore;
+use lumina_node::network::NetworkConfig;
+use lumina_node::node::{Node, NodeError};
+use lumina_node::p2p::P2pError;
+use lumina_node::store::{InMemoryStore, Store};
+use rand::Rng;
+use tokio::time::{sleep, timeout};
+
+use crate::config::{Config, ConfigArgs};
+use crate::native::{open_stores, start_node, Stores};
+
+/// How long to wait for connecting to the network.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum HeaderCommand {
+    /// Print the header at the given height as JSON.
+    Get(HeaderGetParams),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct HeaderGetParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Height of the header.
+    pub(crate) height: u64,
+
+    /// Request the header from the network instead of reading the local store.
+    #[arg(long)]
+    pub(crate) remote: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SampleParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Height of the block to sample. The header must be already synchronized.
+    pub(crate) height: u64,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct PeersParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Seconds to wait for discovering the peers after connecting.
+    #[arg(long, default_value_t = 5)]
+    pub(crate) wait: u64,
+}
+
+pub(crate) async fn run_header(command: HeaderCommand) -> Result<()> {
+    let HeaderCommand::Get(args) = command;
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    let header = if args.remote {
+        let node = start_node(
+            &config,
+            network,
+            vec![],
+            InMemoryBlockstore::new(),
+            InMemoryStore::new(),
+        )
+        .await?;
+        wait_connected_trusted(&node).await?;
+
+        node.request_header_by_height(args.height).await?
+    } else {
+        match open_stores(&config, &network.id).await? {
+            Stores::Redb { store, .. } => store.get_by_height(args.height).await?,
+            Stores::Sled { store, .. } => store.get_by_height(args.height).await?,
+            Stores::Memory { .. } => bail!("Memory store is always empty, use --remote"),
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&header)?);
+
+    Ok(())
+}
+
+pub(crate) async fn run_sample(args: SampleParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    match open_stores(&config, &network.id).await? {
+        Stores::Redb {
+            store, blockstore, ..
+        } => sample_synced(&config, network, args.height, blockstore, store).await,
+        Stores::Sled {
+            store, blockstore, ..
+        } => sample_synced(&config, network, args.height, blockstore, store).await,
+        Stores::Memory { .. } => {
+            bail!("Sampling requires the header to be synchronized in a persistent store")
+        }
+    }
+}
+
+async fn sample_synced<B, S>(
+    config: &Config,
+    network: NetworkConfig,
+    height: u64,
+    blockstore: B,
+    store: S,
+) -> Result<()>
+where
+    B: Blockstore + 'static,
+    S: Store + 'static,
+{
+    if !store.has_at(height).await {
+        bail!("Header at height {height} is not synchronized yet");
+    }
+
+    let node = start_node(config, network, vec![], blockstore, store).await?;
+    sample(config, &node, height).await
+}
+
+async fn sample<S>(config: &Config, node: &Node<S>, height: u64) -> Result<()>
+where
+    S: Store,
+{
+    let header = node.get_header_by_height(height).await?;
+    let indexes = random_indexes(header.dah.square_width(), config.das.max_samples_per_block);
+
+    wait_connected_trusted(node).await?;
+
+    let results = join_all(indexes.iter().map(|&(row, column)| async move {
+        let res = node.request_sample(row, column, height).await;
+        (row, column, res)
+    }))
+    .await;
+
+    let mut verified = 0;
+
+    for (row, column, res) in results {
+        match res {
+            Ok(_) => {
+                verified += 1;
+                println!("row {row:>3}, column {column:>3}: verified");
+            }
+            Err(NodeError::P2p(P2pError::BitswapQueryTimeout)) => {
+                println!("row {row:>3}, column {column:>3}: not received");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let available = verified == indexes.len();
+    println!(
+        "Block at height {height} is {}: {verified}/{} samples verified",
+        if available {
+            "available"
+        } else {
+            "not available"
+        },
+        indexes.len()
+    );
+
+    Ok(())
+}
+
+pub(crate) async fn run_peers(args: PeersParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    let node = start_node(
+        &config,
+        network,
+        vec![],
+        InMemoryBlockstore::new(),
+        InMemoryStore::new(),
+    )
+    .await?;
+
+    println!("Local peer id: {}", node.local_peer_id());
+
+    wait_connected_trusted(&node).await?;
+    sleep(Duration::from_secs(args.wait)).await;
+
+    let info = node.peer_tracker_info();
+    println!(
+        "Connected peers: {} ({} trusted)",
+        info.num_connected_peers, info.num_connected_trusted_peers
+    );
+
+    for peer_id in node.connected_peers().await? {
+        println!("{peer_id}");
+    }
+
+    Ok(())
+}
+
+async fn wait_connected_trusted<S>(node: &Node<S>) -> Result<()>
+where
+    S: Store,
+{
+    match timeout(CONNECT_TIMEOUT, node.wait_connected_trusted()).await {
+        Ok(res) => Ok(res?),
+        Err(_) => bail!("Couldn't connect to any trusted peer"),
+    }
+}
+
+fn random_indexes(square_width: u16, max_samples: usize) -> HashSet<(u16, u16)> {
+    let samples_in_block = usize::from(square_width).pow(2);
+
+    if samples_in_block <= max_samples {
+        return (0..square_width)
+            .flat_map(|row| (0..square_width).map(move |col| (row, col)))
+            .collect();
+    }
+
+    let mut indexes = HashSet::with_capacity(max_samples);
+    let mut rng = rand::thread_rng();
+
+    while indexes.len() < max_samples {
+        let row = rng.gen_range(0..square_width);
+        let col = rng.gen_range(0..square_width);
+        indexes.insert((row, col));
+    }
+
+    indexes
+}
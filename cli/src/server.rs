@@ -1,4 +1,5 @@
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
 use anyhow::Result;
 use axum::extract::{Path, State};
@@ -10,18 +11,19 @@ use celestia_types::hash::Hash;
 use clap::Args;
 use libp2p::multiaddr::Protocol;
 use libp2p::Multiaddr;
-use lumina_node::network::{canonical_network_bootnodes, network_genesis};
+use lumina_node::network::NetworkConfig;
 use rust_embed::RustEmbed;
 use serde::Serialize;
 use tracing::info;
 
-use crate::common::ArgNetwork;
+use crate::common::{load_network_config, ArgNetwork};
 
 const SERVER_DEFAULT_BIND_ADDR: &str = "127.0.0.1:9876";
 
 #[derive(Debug, Clone, Serialize)]
 struct WasmNodeArgs {
     pub network: ArgNetwork,
+    pub network_config: Option<NetworkConfig>,
     pub bootnodes: Vec<Multiaddr>,
     pub genesis_hash: Option<Hash>,
 }
@@ -40,6 +42,10 @@ pub(crate) struct Params {
     #[arg(short, long, value_enum, default_value_t)]
     pub(crate) network: ArgNetwork,
 
+    /// Path to the TOML or JSON definition of a custom network to connect.
+    #[arg(long, conflicts_with = "network")]
+    pub(crate) network_config: Option<PathBuf>,
+
     /// Listening addresses. Can be used multiple times.
     #[arg(short, long = "listen", default_value = SERVER_DEFAULT_BIND_ADDR)]
     pub(crate) listen_addr: SocketAddr,
@@ -50,10 +56,18 @@ pub(crate) struct Params {
 }
 
 pub(crate) async fn run(args: Params) -> Result<()> {
-    let network = args.network.into();
-    let genesis_hash = network_genesis(network);
+    let network_config = match args.network_config {
+        Some(path) => Some(load_network_config(&path).await?),
+        None => None,
+    };
+    let network = network_config
+        .clone()
+        .unwrap_or_else(|| NetworkConfig::canonical(args.network.into()));
+
     let bootnodes = if args.bootnodes.is_empty() {
-        canonical_network_bootnodes(network)
+        network
+            .bootnodes
+            .into_iter()
             .filter(|addr| addr.iter().any(|proto| proto == Protocol::WebTransport))
             .collect()
     } else {
@@ -62,8 +76,9 @@ pub(crate) async fn run(args: Params) -> Result<()> {
 
     let state = WasmNodeArgs {
         network: args.network,
+        network_config,
         bootnodes,
-        genesis_hash,
+        genesis_hash: network.genesis_hash,
     };
 
     let app = Router::new()
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::ops::Bound;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use blockstore::Blockstore;
+use cid::Cid;
+use clap::{Args, Subcommand};
+use lumina_node::blockstore::EdsCache;
+use lumina_node::store::archive;
+use lumina_node::store::{RedbStore, SledStore, Store, StoreError};
+use tokio::fs;
+use tokio::io::{BufReader, BufWriter};
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+use crate::config::{default_store_path, Config, ConfigArgs, StoreBackend};
+use crate::native::{open_db, open_stores, Stores};
+
+/// Number of headers copied at once during the migration.
+const MIGRATION_BATCH_SIZE: usize = 512;
+/// Names of the trees in which the sled stores keep the blocks, the `SledBlockstore` used by
+/// the previous versions and the on-disk tier of the [`EdsCache`].
+const SLED_BLOCKS_TREE_IDS: [&[u8]; 2] = [b"BLOCKSTORE.BLOCKS", b"EDS_CACHE.DATA"];
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum StoreCommand {
+    /// Print information about the store.
+    Info(InfoParams),
+    /// Remove all the data from the store.
+    Reset(ResetParams),
+    /// Copy headers, sampling metadata and blocks from a sled store into a redb store.
+    Migrate(MigrateParams),
+    /// Export headers and their sampling metadata to a portable archive.
+    Export(ExportParams),
+    /// Verify and append headers from a portable archive to the store.
+    Import(ImportParams),
+    /// Check the consistency of the store, optionally repairing it.
+    Fsck(FsckParams),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct InfoParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ResetParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Confirm removing the data.
+    #[arg(long)]
+    pub(crate) yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MigrateParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Path of the sled store to migrate from.
+    ///
+    /// Defaults to the configured store if it uses sled, or to the default sled store path.
+    #[arg(long)]
+    pub(crate) from: Option<PathBuf>,
+
+    /// Path of the redb store to migrate to.
+    ///
+    /// Defaults to the configured store if it uses redb, or to the default redb store path.
+    #[arg(long)]
+    pub(crate) to: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExportParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Path of the archive to create.
+    pub(crate) file: PathBuf,
+
+    /// Height of the first exported header. Defaults to 1.
+    #[arg(long)]
+    pub(crate) from: Option<u64>,
+
+    /// Height of the last exported header. Defaults to the store's head.
+    #[arg(long)]
+    pub(crate) to: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ImportParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Path of the archive to import.
+    pub(crate) file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct FsckParams {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+
+    /// Truncate the store to the last consistent height and fix the lowest unsampled height.
+    #[arg(long)]
+    pub(crate) repair: bool,
+}
+
+pub(crate) async fn run(command: StoreCommand) -> Result<()> {
+    match command {
+        StoreCommand::Info(args) => info(args).await,
+        StoreCommand::Reset(args) => reset(args).await,
+        StoreCommand::Migrate(args) => migrate(args).await,
+        StoreCommand::Export(args) => export(args).await,
+        StoreCommand::Import(args) => import(args).await,
+        StoreCommand::Fsck(args) => fsck(args).await,
+    }
+}
+
+async fn info(args: InfoParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    println!("Network:         {}", network.id);
+
+    match open_stores(&config, &network.id).await? {
+        Stores::Redb { path, store, .. } => {
+            println!("Backend:         redb");
+            println!("Path:            {}", path.display());
+            println!(
+                "Size on disk:    {} bytes",
+                fs::metadata(&path).await?.len()
+            );
+            print_heights(&store).await?;
+        }
+        Stores::Sled {
+            path, db, store, ..
+        } => {
+            println!("Backend:         sled");
+            println!("Path:            {}", path.display());
+            println!("Size on disk:    {} bytes", db.size_on_disk()?);
+            print_heights(&store).await?;
+        }
+        Stores::Memory { store, .. } => {
+            println!("Backend:         memory");
+            print_heights(&store).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn print_heights<S>(store: &S) -> Result<()>
+where
+    S: Store,
+{
+    match store.head_height().await {
+        Ok(height) => println!("Head height:     {height}"),
+        Err(StoreError::NotFound) => println!("Head height:     empty"),
+        Err(e) => return Err(e.into()),
+    }
+
+    let next_unsampled = store.next_unsampled_height().await?;
+    println!("Sampled heights: {}", next_unsampled.saturating_sub(1));
+
+    Ok(())
+}
+
+async fn reset(args: ResetParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    if config.store_backend == StoreBackend::Memory {
+        bail!("Memory store is not persisted, nothing to reset");
+    }
+
+    let path = config.store_path(&network.id)?;
+
+    if !path.exists() {
+        println!("Store {} doesn't exist", path.display());
+        return Ok(());
+    }
+
+    if !args.yes {
+        bail!(
+            "This will remove all the data in {}, use --yes to confirm",
+            path.display()
+        );
+    }
+
+    if path.is_dir() {
+        fs::remove_dir_all(&path).await?;
+    } else {
+        fs::remove_file(&path).await?;
+    }
+    println!("Removed {}", path.display());
+
+    Ok(())
+}
+
+async fn migrate(args: MigrateParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    let from = match args.from {
+        Some(path) => path,
+        None if config.store_backend == StoreBackend::Sled => config.store_path(&network.id)?,
+        None => default_store_path(StoreBackend::Sled, &network.id)?,
+    };
+    let to = match args.to {
+        Some(path) => path,
+        None if config.store_backend == StoreBackend::Redb => config.store_path(&network.id)?,
+        None => default_store_path(StoreBackend::Redb, &network.id)?,
+    };
+
+    if !from.exists() {
+        bail!("Sled store {} doesn't exist", from.display());
+    }
+
+    let db = open_db(from.clone(), false).await?;
+    let sled_store = SledStore::new(db.clone()).await?;
+
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let redb_store = RedbStore::open(&to).await?;
+
+    if redb_store.head_height().await.is_ok() {
+        bail!("Redb store {} is not empty", to.display());
+    }
+
+    let redb_blockstore =
+        EdsCache::with_redb(redb_store.raw_db(), 0, config.cache.disk_capacity()).await?;
+
+    println!("Migrating {} -> {}", from.display(), to.display());
+
+    let head_height = match sled_store.head_height().await {
+        Ok(height) => height,
+        Err(StoreError::NotFound) => 0,
+        Err(e) => return Err(e.into()),
+    };
+
+    for start in (1..=head_height).step_by(MIGRATION_BATCH_SIZE) {
+        let end = (start + MIGRATION_BATCH_SIZE as u64 - 1).min(head_height);
+        let headers = sled_store.get_range(start..=end).await?;
+        redb_store.append_unchecked(headers).await?;
+    }
+    println!("Headers:         {head_height}");
+
+    let mut sampled = 0;
+    for height in 1..=head_height {
+        if let Some(metadata) = sled_store.get_sampling_metadata(height).await? {
+            redb_store
+                .update_sampling_metadata(height, metadata.accepted, metadata.samples())
+                .await?;
+            sampled += 1;
+        }
+    }
+    println!("Sampled heights: {sampled}");
+
+    let mut cids = HashSet::new();
+    for tree_id in SLED_BLOCKS_TREE_IDS {
+        for entry in db.open_tree(tree_id)?.iter() {
+            let (cid, data) = entry?;
+            let cid = Cid::try_from(cid.as_ref())?;
+            redb_blockstore.put_keyed(&cid, &data).await?;
+            cids.insert(cid);
+        }
+    }
+
+    // Blocks not fitting in the on-disk cache are evicted
+    let mut evicted = 0;
+    for cid in &cids {
+        if !redb_blockstore.has(cid).await? {
+            evicted += 1;
+        }
+    }
+    println!(
+        "Blocks:          {} ({evicted} evicted, over the cache capacity)",
+        cids.len()
+    );
+
+    println!("Set `store_backend = \"redb\"` in the config to use the migrated store");
+
+    Ok(())
+}
+
+async fn export(args: ExportParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    let range = (
+        args.from.map_or(Bound::Unbounded, Bound::Included),
+        args.to.map_or(Bound::Unbounded, Bound::Included),
+    );
+
+    let file = fs::File::create(&args.file)
+        .await
+        .with_context(|| format!("Failed to create archive {}", args.file.display()))?;
+    let writer = BufWriter::new(file).compat_write();
+
+    let exported = match open_stores(&config, &network.id).await? {
+        Stores::Redb { store, .. } => archive::export(&store, range, writer).await?,
+        Stores::Sled { store, .. } => archive::export(&store, range, writer).await?,
+        Stores::Memory { .. } => bail!("Memory store is not persisted, nothing to export"),
+    };
+
+    println!("Exported {exported} headers to {}", args.file.display());
+
+    Ok(())
+}
+
+async fn import(args: ImportParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    let file = fs::File::open(&args.file)
+        .await
+        .with_context(|| format!("Failed to open archive {}", args.file.display()))?;
+    let reader = BufReader::new(file).compat();
+
+    let imported = match open_stores(&config, &network.id).await? {
+        Stores::Redb { store, .. } => archive::import(&store, reader, network.genesis_hash).await?,
+        Stores::Sled { store, .. } => archive::import(&store, reader, network.genesis_hash).await?,
+        Stores::Memory { .. } => bail!("Memory store is not persisted, nothing to import to"),
+    };
+
+    println!("Imported {imported} headers from {}", args.file.display());
+
+    Ok(())
+}
+
+async fn fsck(args: FsckParams) -> Result<()> {
+    let config = Config::load(&args.config).await?;
+    let network = config.network().await?;
+
+    match open_stores(&config, &network.id).await? {
+        Stores::Redb { store, .. } => check_and_repair(&store, args.repair).await,
+        Stores::Sled { store, .. } => check_and_repair(&store, args.repair).await,
+        Stores::Memory { .. } => bail!("Memory store is not persisted, nothing to check"),
+    }
+}
+
+async fn check_and_repair<S>(store: &S, repair: bool) -> Result<()>
+where
+    S: Store,
+{
+    let report = store.check_integrity().await?;
+
+    println!("Head height:            {}", report.head_height);
+    println!("Last consistent height: {}", report.last_consistent_height);
+    println!("Next unsampled height:  {}", report.next_unsampled_height);
+
+    if report.is_consistent() {
+        println!("Store is consistent");
+        return Ok(());
+    }
+
+    for issue in &report.issues {
+        println!("Inconsistency: {issue}");
+    }
+
+    if !repair {
+        bail!(
+            "Found {} inconsistencies, use --repair to truncate the store to height {}",
+            report.issues.len(),
+            report.last_consistent_height
+        );
+    }
+
+    store.truncate(report.last_consistent_height).await?;
+
+    let report = store.check_integrity().await?;
+    if !report.is_consistent() {
+        bail!("Store is still inconsistent after the repair, reset it with `lumina store reset`");
+    }
+
+    println!(
+        "Repaired, store truncated to height {}",
+        report.last_consistent_height
+    );
+
+    Ok(())
+}
@@ -8,7 +8,9 @@ async function fetch_config() {
 
   console.log("Received config:", json);
 
-  let config = NodeConfig.default(json.network);
+  let config = json.network_config
+    ? NodeConfig.custom(json.network_config)
+    : NodeConfig.default(json.network);
   if (json.bootnodes.length !== 0) {
     config.bootnodes = json.bootnodes;
   }
@@ -24,7 +26,14 @@ async function show_stats(node) {
     return;
   }
   const info = await node.syncer_info();
-  document.getElementById("syncer").innerText = `${info.local_head}/${info.subjective_head}`;
+  document.getElementById("syncer").innerText = `${info.local_head}/${info.subjective_head} (${info.status})`;
+  document.getElementById("stored-headers").innerText = info.stored_headers
+    .map(range => `[${range.start}, ${range.end}]`)
+    .join(", ");
+
+  const rate = info.headers_per_second != null ? `${info.headers_per_second.toFixed(1)} headers/s` : "unknown";
+  const eta = info.eta != null ? `${info.eta.secs}s` : "unknown";
+  document.getElementById("sync-rate").innerText = `${rate}, catching up in: ${eta}`;
 
   let peers_ul = document.createElement('ul');
   (await node.connected_peers()).forEach(peer => {
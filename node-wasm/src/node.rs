@@ -1,19 +1,26 @@
 //! A browser compatible wrappers for the [`lumina-node`].
 
+use std::io;
 use std::result::Result as StdResult;
 
-use celestia_types::{hash::Hash, ExtendedHeader};
-use js_sys::Array;
+use celestia_types::{hash::Hash, ExtendedHeader, VerificationOptions};
+use futures::{StreamExt, TryStreamExt};
+use js_sys::{Array, Uint8Array};
 use libp2p::identity::Keypair;
 use libp2p::multiaddr::Protocol;
-use lumina_node::blockstore::IndexedDbBlockstore;
-use lumina_node::network::{canonical_network_bootnodes, network_genesis, network_id};
+use lumina_node::blockstore::{EdsCache, DEFAULT_DISK_CAPACITY, DEFAULT_MEMORY_CAPACITY};
+use lumina_node::daser::DEFAULT_MAX_SAMPLES_NEEDED;
+use lumina_node::network::{
+    canonical_network_bootnodes, network_genesis, network_id, NetworkConfig, ProtocolVersions,
+};
 use lumina_node::node::{Node, NodeConfig};
 use lumina_node::store::{IndexedDbStore, Store};
+use lumina_node::syncer::{DEFAULT_HEAD_QUORUM, DEFAULT_STALL_TIMEOUT};
 use serde::Serialize;
 use serde_wasm_bindgen::{from_value, to_value};
 use tracing::info;
 use wasm_bindgen::prelude::*;
+use web_sys::ReadableStream;
 
 use crate::utils::js_value_from_display;
 use crate::utils::JsContext;
@@ -30,12 +37,16 @@ struct WasmNode(Node<IndexedDbStore>);
 pub struct WasmNodeConfig {
     /// A network to connect to.
     pub network: Network,
+    /// An id of a custom network. If set, it is used instead of the `network`'s id.
+    #[wasm_bindgen(getter_with_clone)]
+    pub network_id: Option<String>,
     /// Hash of the genesis block in the network.
     #[wasm_bindgen(getter_with_clone)]
     pub genesis_hash: Option<String>,
     /// A list of bootstrap peers to connect to.
     #[wasm_bindgen(getter_with_clone)]
     pub bootnodes: Vec<String>,
+    protocol_versions: ProtocolVersions,
 }
 
 #[wasm_bindgen(js_class = Node)]
@@ -220,6 +231,25 @@ impl WasmNode {
 
         Ok(to_value(&metadata)?)
     }
+
+    /// Import headers and their sampling metadata from the archive created with
+    /// `lumina store export`, e.g. to seed a fresh node.
+    ///
+    /// Headers are verified before they are appended to the store.
+    /// Returns the number of imported headers.
+    pub async fn import_headers(&self, stream: ReadableStream) -> Result<u64> {
+        let reader = wasm_streams::ReadableStream::from_raw(stream)
+            .into_stream()
+            .map(|chunk| {
+                chunk
+                    .and_then(|chunk| chunk.dyn_into::<Uint8Array>())
+                    .map(|chunk| chunk.to_vec())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e:?}")))
+            })
+            .into_async_read();
+
+        Ok(self.0.import_headers(reader).await?)
+    }
 }
 
 #[wasm_bindgen(js_class = NodeConfig)]
@@ -228,22 +258,53 @@ impl WasmNodeConfig {
     pub fn default(network: Network) -> WasmNodeConfig {
         WasmNodeConfig {
             network,
+            network_id: None,
             genesis_hash: network_genesis(network.into()).map(|h| h.to_string()),
             bootnodes: canonical_network_bootnodes(network.into())
                 .filter(|addr| addr.iter().any(|proto| proto == Protocol::WebTransport))
                 .map(|addr| addr.to_string())
                 .collect::<Vec<_>>(),
+            protocol_versions: ProtocolVersions::default(),
         }
     }
 
-    async fn into_node_config(self) -> Result<NodeConfig<IndexedDbBlockstore, IndexedDbStore>> {
-        let network_id = network_id(self.network.into());
+    /// Get the configuration for a custom network.
+    ///
+    /// `network_config` is an object with the network's `id` and optional `genesis_hash`,
+    /// `bootnodes` and `protocol_versions`, in the same format as accepted by `lumina-cli`.
+    pub fn custom(network_config: JsValue) -> Result<WasmNodeConfig> {
+        let config = from_value::<NetworkConfig>(network_config)
+            .js_context("Parsing network config failed")?;
+
+        Ok(WasmNodeConfig {
+            network: Network::Private,
+            network_id: Some(config.id),
+            genesis_hash: config.genesis_hash.map(|h| h.to_string()),
+            bootnodes: config
+                .bootnodes
+                .into_iter()
+                .filter(|addr| addr.iter().any(|proto| proto == Protocol::WebTransport))
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>(),
+            protocol_versions: config.protocol_versions,
+        })
+    }
+
+    async fn into_node_config(self) -> Result<NodeConfig<EdsCache, IndexedDbStore>> {
+        let network_id = self
+            .network_id
+            .unwrap_or_else(|| network_id(self.network.into()).to_owned());
+        let network_id = network_id.as_str();
         let store = IndexedDbStore::new(network_id)
             .await
             .js_context("Failed to open the store")?;
-        let blockstore = IndexedDbBlockstore::new(&format!("{network_id}-blockstore"))
-            .await
-            .js_context("Failed to open the blockstore")?;
+        let blockstore = EdsCache::with_indexed_db(
+            &format!("{network_id}-eds-cache"),
+            DEFAULT_MEMORY_CAPACITY,
+            DEFAULT_DISK_CAPACITY,
+        )
+        .await
+        .js_context("Failed to open the blockstore")?;
 
         let p2p_local_keypair = Keypair::generate_ed25519();
 
@@ -257,9 +318,14 @@ impl WasmNodeConfig {
         Ok(NodeConfig {
             network_id: network_id.to_string(),
             genesis_hash,
+            p2p_protocol_versions: self.protocol_versions,
             p2p_bootnodes,
             p2p_local_keypair,
             p2p_listen_on: vec![],
+            das_max_samples_per_block: DEFAULT_MAX_SAMPLES_NEEDED,
+            syncer_head_quorum: DEFAULT_HEAD_QUORUM,
+            syncer_verification_options: VerificationOptions::default(),
+            syncer_stall_timeout: DEFAULT_STALL_TIMEOUT,
             blockstore,
             store,
         })
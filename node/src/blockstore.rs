@@ -2,6 +2,10 @@
 
 use crate::p2p::MAX_MH_SIZE;
 
+pub use eds_cache::{EdsCache, EdsCacheStats, DEFAULT_DISK_CAPACITY, DEFAULT_MEMORY_CAPACITY};
+
+mod eds_cache;
+
 /// An [`InMemoryBlockstore`] with maximum multihash size used by lumina.
 ///
 /// [`InMemoryBlockstore`]: blockstore::InMemoryBlockstore
@@ -13,6 +17,12 @@ pub type InMemoryBlockstore = blockstore::InMemoryBlockstore<MAX_MH_SIZE>;
 /// [`SledBlockstore`]: blockstore::SledBlockstore
 pub type SledBlockstore = blockstore::SledBlockstore;
 
+#[cfg(not(target_arch = "wasm32"))]
+/// A [`RedbBlockstore`].
+///
+/// [`RedbBlockstore`]: blockstore::RedbBlockstore
+pub type RedbBlockstore = blockstore::RedbBlockstore;
+
 #[cfg(target_arch = "wasm32")]
 /// An [`IndexedDbBlockstore`].
 ///
@@ -0,0 +1,546 @@
+//! Size bounded cache of the shwap data, e.g. [`Row`]s, [`Sample`]s and [`NamespacedData`].
+//!
+//! Bitswap looks up the requested CIDs in the [`Blockstore`] before querying the network, so
+//! using an [`EdsCache`] as the node's blockstore serves the repeated requests for the recent
+//! heights locally, without letting the blockstore grow forever.
+//!
+//! The cache consists of two tiers. The memory tier keeps the most recently used entries, while
+//! the optional on-disk tier keeps more of them in a [`redb`], [`sled`] or IndexedDB database.
+//! Each tier evicts its least recently used entries once the size of the data it holds would
+//! exceed its capacity.
+//!
+//! [`Row`]: celestia_types::row::Row
+//! [`Sample`]: celestia_types::sample::Sample
+//! [`NamespacedData`]: celestia_types::namespaced_data::NamespacedData
+//! [`redb`]: https://docs.rs/redb
+//! [`sled`]: https://docs.rs/sled
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use blockstore::{Blockstore, Result};
+use cid::CidGeneric;
+use lru::LruCache;
+
+#[cfg(target_arch = "wasm32")]
+use self::indexed_db_tier::IndexedDbTier;
+#[cfg(not(target_arch = "wasm32"))]
+use self::redb_tier::RedbTier;
+#[cfg(not(target_arch = "wasm32"))]
+use self::sled_tier::SledTier;
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db_tier;
+mod recency_index;
+#[cfg(not(target_arch = "wasm32"))]
+mod redb_tier;
+#[cfg(not(target_arch = "wasm32"))]
+mod sled_tier;
+
+/// Default capacity of the memory tier of the [`EdsCache`], in bytes.
+pub const DEFAULT_MEMORY_CAPACITY: usize = 64 * 1024 * 1024;
+
+/// Default capacity of the on-disk tier of the [`EdsCache`], in bytes.
+pub const DEFAULT_DISK_CAPACITY: u64 = 1024 * 1024 * 1024;
+
+/// Number of the on-disk tier hits after which their recency is persisted.
+const TOUCH_BATCH_SIZE: usize = 64;
+
+/// Hit and miss counters and the sizes of the [`EdsCache`] tiers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EdsCacheStats {
+    /// Number of lookups served from the memory tier.
+    pub memory_hits: u64,
+    /// Number of lookups served from the on-disk tier.
+    pub disk_hits: u64,
+    /// Number of lookups which weren't found in the cache.
+    pub misses: u64,
+    /// Size of the data held in the memory tier, in bytes.
+    pub memory_size: u64,
+    /// Size of the data held in the on-disk tier, in bytes.
+    pub disk_size: u64,
+}
+
+/// A [`Blockstore`] caching the data in the memory and on-disk tiers with LRU eviction.
+///
+/// Cloning the cache is cheap and the clones share the data, so a clone can be kept for
+/// reading the [`EdsCacheStats`] after the cache is given to the node.
+#[derive(Debug, Clone)]
+pub struct EdsCache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    memory: Mutex<MemoryTier>,
+    disk: Option<DiskTier>,
+    memory_hits: AtomicU64,
+    disk_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EdsCache {
+    /// Create a cache keeping up to `memory_capacity` bytes of data in memory only.
+    pub fn in_memory(memory_capacity: usize) -> Self {
+        EdsCache::new(memory_capacity, None)
+    }
+
+    /// Create a cache keeping up to `memory_capacity` bytes of data in memory and up to
+    /// `disk_capacity` bytes in the [`redb::Database`].
+    ///
+    /// The database can be shared with the other stores, e.g. [`RedbStore`]. Entries persisted
+    /// by the previous runs are kept, evicting the oldest ones if `disk_capacity` was lowered.
+    ///
+    /// [`RedbStore`]: crate::store::RedbStore
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_redb(
+        db: Arc<redb::Database>,
+        memory_capacity: usize,
+        disk_capacity: u64,
+    ) -> Result<Self> {
+        let disk = RedbTier::new(db, disk_capacity).await?;
+        Ok(EdsCache::new(memory_capacity, Some(DiskTier::Redb(disk))))
+    }
+
+    /// Create a cache keeping up to `memory_capacity` bytes of data in memory and up to
+    /// `disk_capacity` bytes in the [`sled::Db`].
+    ///
+    /// The database can be shared with the other stores, e.g. [`SledStore`]. Entries persisted
+    /// by the previous runs are kept, evicting the oldest ones if `disk_capacity` was lowered.
+    ///
+    /// [`SledStore`]: crate::store::SledStore
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_sled(
+        db: sled::Db,
+        memory_capacity: usize,
+        disk_capacity: u64,
+    ) -> Result<Self> {
+        let disk = SledTier::new(db, disk_capacity).await?;
+        Ok(EdsCache::new(memory_capacity, Some(DiskTier::Sled(disk))))
+    }
+
+    /// Create a cache keeping up to `memory_capacity` bytes of data in memory and up to
+    /// `disk_capacity` bytes in the IndexedDB database with the given name.
+    ///
+    /// Entries persisted by the previous runs are kept, evicting the oldest ones if
+    /// `disk_capacity` was lowered.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn with_indexed_db(
+        name: &str,
+        memory_capacity: usize,
+        disk_capacity: u64,
+    ) -> Result<Self> {
+        let disk = IndexedDbTier::new(name, disk_capacity).await?;
+        Ok(EdsCache::new(
+            memory_capacity,
+            Some(DiskTier::IndexedDb(disk)),
+        ))
+    }
+
+    fn new(memory_capacity: usize, disk: Option<DiskTier>) -> Self {
+        EdsCache {
+            inner: Arc::new(Inner {
+                memory: Mutex::new(MemoryTier::new(memory_capacity)),
+                disk,
+                memory_hits: AtomicU64::new(0),
+                disk_hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the hit and miss counters and the current sizes of the tiers.
+    pub fn stats(&self) -> EdsCacheStats {
+        EdsCacheStats {
+            memory_hits: self.inner.memory_hits.load(Ordering::Relaxed),
+            disk_hits: self.inner.disk_hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            memory_size: self.inner.memory.lock().expect("lock failed").size as u64,
+            disk_size: self.inner.disk.as_ref().map_or(0, DiskTier::size),
+        }
+    }
+}
+
+impl Blockstore for EdsCache {
+    async fn get<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<Option<Vec<u8>>> {
+        let cid = cid.to_bytes();
+
+        let cached = self.inner.memory.lock().expect("lock failed").get(&cid);
+        if let Some(data) = cached {
+            self.inner.memory_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(data));
+        }
+
+        if let Some(disk) = &self.inner.disk {
+            if let Some(data) = disk.get(cid.clone()).await? {
+                self.inner.disk_hits.fetch_add(1, Ordering::Relaxed);
+                self.inner
+                    .memory
+                    .lock()
+                    .expect("lock failed")
+                    .put(cid, data.clone());
+                return Ok(Some(data));
+            }
+        }
+
+        self.inner.misses.fetch_add(1, Ordering::Relaxed);
+        Ok(None)
+    }
+
+    async fn put_keyed<const S: usize>(&self, cid: &CidGeneric<S>, data: &[u8]) -> Result<()> {
+        let cid = cid.to_bytes();
+
+        if let Some(disk) = &self.inner.disk {
+            disk.put(cid.clone(), data.to_vec()).await?;
+        }
+
+        self.inner
+            .memory
+            .lock()
+            .expect("lock failed")
+            .put(cid, data.to_vec());
+
+        Ok(())
+    }
+
+    async fn has<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<bool> {
+        let cid = cid.to_bytes();
+
+        if self
+            .inner
+            .memory
+            .lock()
+            .expect("lock failed")
+            .contains(&cid)
+        {
+            return Ok(true);
+        }
+
+        if let Some(disk) = &self.inner.disk {
+            return disk.has(cid).await;
+        }
+
+        Ok(false)
+    }
+}
+
+#[derive(Debug)]
+struct MemoryTier {
+    entries: LruCache<Vec<u8>, Vec<u8>>,
+    size: usize,
+    capacity: usize,
+}
+
+impl MemoryTier {
+    fn new(capacity: usize) -> Self {
+        MemoryTier {
+            entries: LruCache::unbounded(),
+            size: 0,
+            capacity,
+        }
+    }
+
+    fn get(&mut self, cid: &[u8]) -> Option<Vec<u8>> {
+        self.entries.get(cid).cloned()
+    }
+
+    fn contains(&self, cid: &[u8]) -> bool {
+        self.entries.contains(cid)
+    }
+
+    fn put(&mut self, cid: Vec<u8>, data: Vec<u8>) {
+        // Data under the same CID is always the same
+        if self.entries.contains(&cid) {
+            self.entries.promote(&cid);
+            return;
+        }
+
+        if data.len() > self.capacity {
+            return;
+        }
+
+        self.size += data.len();
+        self.entries.put(cid, data);
+
+        while self.size > self.capacity {
+            let Some((_, evicted)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.size -= evicted.len();
+        }
+    }
+}
+
+/// On-disk tier of the cache, in the database of the node's stores.
+#[derive(Debug)]
+enum DiskTier {
+    #[cfg(not(target_arch = "wasm32"))]
+    Redb(RedbTier),
+    #[cfg(not(target_arch = "wasm32"))]
+    Sled(SledTier),
+    #[cfg(target_arch = "wasm32")]
+    IndexedDb(IndexedDbTier),
+}
+
+impl DiskTier {
+    fn size(&self) -> u64 {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Redb(tier) => tier.size(),
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Sled(tier) => tier.size(),
+            #[cfg(target_arch = "wasm32")]
+            DiskTier::IndexedDb(tier) => tier.size(),
+        }
+    }
+
+    async fn get(&self, cid: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Redb(tier) => tier.get(cid).await,
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Sled(tier) => tier.get(cid).await,
+            #[cfg(target_arch = "wasm32")]
+            DiskTier::IndexedDb(tier) => tier.get(cid).await,
+        }
+    }
+
+    async fn has(&self, cid: Vec<u8>) -> Result<bool> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Redb(tier) => tier.has(cid).await,
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Sled(tier) => tier.has(cid).await,
+            #[cfg(target_arch = "wasm32")]
+            DiskTier::IndexedDb(tier) => tier.has(cid).await,
+        }
+    }
+
+    async fn put(&self, cid: Vec<u8>, data: Vec<u8>) -> Result<()> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Redb(tier) => tier.put(cid, data).await,
+            #[cfg(not(target_arch = "wasm32"))]
+            DiskTier::Sled(tier) => tier.put(cid, data).await,
+            #[cfg(target_arch = "wasm32")]
+            DiskTier::IndexedDb(tier) => tier.put(cid, data).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::async_test;
+    use cid::multihash::Multihash;
+
+    fn cid(n: u8) -> CidGeneric<64> {
+        let hash = Multihash::wrap(0x12, &[n; 32]).unwrap();
+        CidGeneric::new_v1(0x55, hash)
+    }
+
+    #[async_test]
+    async fn memory_tier_evicts_least_recently_used() {
+        let cache = EdsCache::in_memory(8);
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        // Make the first entry the most recently used one
+        assert_eq!(cache.get(&cid(1)).await.unwrap().unwrap(), b"1111");
+
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+        assert!(cache.get(&cid(2)).await.unwrap().is_none());
+
+        assert_eq!(
+            cache.stats(),
+            EdsCacheStats {
+                memory_hits: 1,
+                disk_hits: 0,
+                misses: 1,
+                memory_size: 8,
+                disk_size: 0,
+            }
+        );
+    }
+
+    #[async_test]
+    async fn oversized_data_is_not_cached() {
+        let cache = EdsCache::in_memory(4);
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"22222").await.unwrap();
+
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert_eq!(cache.stats().memory_size, 4);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn in_memory_db() -> Arc<redb::Database> {
+        let db = redb::Database::builder()
+            .create_with_backend(redb::backends::InMemoryBackend::new())
+            .unwrap();
+        Arc::new(db)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn disk_tier_serves_data_evicted_from_memory() {
+        let cache = EdsCache::with_redb(in_memory_db(), 4, 8).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+
+        assert_eq!(cache.get(&cid(1)).await.unwrap().unwrap(), b"1111");
+        // Promoted back to the memory tier
+        assert_eq!(cache.get(&cid(1)).await.unwrap().unwrap(), b"1111");
+        assert!(cache.get(&cid(3)).await.unwrap().is_none());
+
+        assert_eq!(
+            cache.stats(),
+            EdsCacheStats {
+                memory_hits: 1,
+                disk_hits: 1,
+                misses: 1,
+                memory_size: 4,
+                disk_size: 8,
+            }
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn disk_tier_evicts_least_recently_used() {
+        let cache = EdsCache::with_redb(in_memory_db(), 0, 8).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        assert!(cache.get(&cid(1)).await.unwrap().is_some());
+
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+        assert_eq!(cache.stats().disk_size, 8);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn disk_tier_persists_recency_in_batches() {
+        let db = in_memory_db();
+        let cache = EdsCache::with_redb(db.clone(), 0, 8).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+
+        for _ in 0..TOUCH_BATCH_SIZE {
+            assert!(cache.get(&cid(1)).await.unwrap().is_some());
+        }
+
+        // Another cache over the same database sees the first entry as the most recently used
+        let other = EdsCache::with_redb(db, 0, 4).await.unwrap();
+
+        assert!(other.has(&cid(1)).await.unwrap());
+        assert!(!other.has(&cid(2)).await.unwrap());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn disk_tier_persists() {
+        let db_dir = tempfile::TempDir::with_prefix("lumina.eds_cache.test").unwrap();
+        let path = db_dir.path().join("db");
+
+        let db = Arc::new(redb::Database::create(&path).unwrap());
+        let cache = EdsCache::with_redb(db, 0, 12).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+        assert!(cache.get(&cid(1)).await.unwrap().is_some());
+        drop(cache);
+
+        // Reopen with a lower capacity
+        let db = Arc::new(redb::Database::create(&path).unwrap());
+        let cache = EdsCache::with_redb(db, 0, 8).await.unwrap();
+
+        assert_eq!(cache.stats().disk_size, 8);
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn open_sled_db(path: std::path::PathBuf) -> sled::Db {
+        tokio::task::spawn_blocking(move || sled::open(path).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn sled_disk_tier_evicts_least_recently_used() {
+        let db_dir = tempfile::TempDir::with_prefix("lumina.eds_cache.test").unwrap();
+        let db = open_sled_db(db_dir.path().join("db")).await;
+        let cache = EdsCache::with_sled(db, 0, 8).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        assert!(cache.get(&cid(1)).await.unwrap().is_some());
+
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+        assert_eq!(cache.stats().disk_size, 8);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn sled_disk_tier_persists() {
+        let db_dir = tempfile::TempDir::with_prefix("lumina.eds_cache.test").unwrap();
+        let path = db_dir.path().join("db");
+
+        let db = open_sled_db(path.clone()).await;
+        let cache = EdsCache::with_sled(db, 0, 12).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+        assert!(cache.get(&cid(1)).await.unwrap().is_some());
+        drop(cache);
+
+        // Reopen with a lower capacity
+        let db = open_sled_db(path).await;
+        let cache = EdsCache::with_sled(db, 0, 8).await.unwrap();
+
+        assert_eq!(cache.stats().disk_size, 8);
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[async_test]
+    async fn indexed_db_disk_tier_evicts_least_recently_used() {
+        let name = "lumina.eds_cache.test";
+        rexie::Rexie::delete(name).await.unwrap();
+        let cache = EdsCache::with_indexed_db(name, 0, 8).await.unwrap();
+
+        cache.put_keyed(&cid(1), b"1111").await.unwrap();
+        cache.put_keyed(&cid(2), b"2222").await.unwrap();
+        assert!(cache.get(&cid(1)).await.unwrap().is_some());
+
+        cache.put_keyed(&cid(3), b"3333").await.unwrap();
+
+        assert!(cache.has(&cid(1)).await.unwrap());
+        assert!(!cache.has(&cid(2)).await.unwrap());
+        assert!(cache.has(&cid(3)).await.unwrap());
+        assert_eq!(cache.stats().disk_size, 8);
+    }
+}
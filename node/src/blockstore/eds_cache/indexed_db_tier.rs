@@ -0,0 +1,221 @@
+//! On-disk tier of the [`EdsCache`] kept in an IndexedDB database.
+//!
+//! [`EdsCache`]: super::EdsCache
+
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use blockstore::{Error, Result};
+use js_sys::Uint8Array;
+use rexie::{KeyRange, ObjectStore, Rexie, TransactionMode};
+use send_wrapper::SendWrapper;
+use wasm_bindgen::{JsCast, JsValue};
+
+use super::recency_index::{decode_entry, encode_entry, RecencyIndex};
+use super::TOUCH_BATCH_SIZE;
+
+/// indexeddb version, needs to be incremented on every schema schange
+const DB_VERSION: u32 = 1;
+
+/// CID -> data of the entry.
+const DATA_STORE_NAME: &str = "data";
+/// CID -> (sequence number of the last use, size) of the entry.
+const ENTRIES_STORE_NAME: &str = "entries";
+
+#[derive(Debug)]
+pub(super) struct IndexedDbTier {
+    // SendWrapper usage is safe in wasm because we're running on a single thread
+    db: SendWrapper<Rexie>,
+    capacity: u64,
+    index: tokio::sync::Mutex<RecencyIndex>,
+    /// Mirror of the size of the index, for the stats.
+    size: AtomicU64,
+    /// CIDs of the entries used since their recency was last persisted, in the order of use.
+    /// Not persisted when the tier is dropped, as it can't block for the transaction.
+    pending_touches: Mutex<Vec<Vec<u8>>>,
+}
+
+impl IndexedDbTier {
+    pub(super) async fn new(name: &str, capacity: u64) -> Result<Self> {
+        let db = Rexie::builder(name)
+            .version(DB_VERSION)
+            .add_object_store(ObjectStore::new(DATA_STORE_NAME))
+            .add_object_store(ObjectStore::new(ENTRIES_STORE_NAME))
+            .build()
+            .await?;
+
+        let tx = db.transaction(&[ENTRIES_STORE_NAME], TransactionMode::ReadOnly)?;
+        let persisted = tx
+            .store(ENTRIES_STORE_NAME)?
+            .get_all(None, None, None, None)
+            .await?;
+        tx.done().await?;
+
+        let mut index = RecencyIndex::default();
+
+        for (cid, entry) in persisted {
+            let (seq, len) = decode_entry(&to_bytes(&entry)?)
+                .ok_or_else(|| Error::StoredDataError("invalid eds cache entry".to_string()))?;
+            index.load(to_bytes(&cid)?, seq, len);
+        }
+
+        let tier = IndexedDbTier {
+            db: SendWrapper::new(db),
+            capacity,
+            size: AtomicU64::new(0),
+            index: tokio::sync::Mutex::new(index),
+            pending_touches: Mutex::new(Vec::new()),
+        };
+
+        let mut index = tier.index.lock().await;
+        let evicted = index.evict(capacity, 0);
+        tier.write(&evicted, &[], None).await?;
+        tier.size.store(index.size(), Ordering::Relaxed);
+        drop(index);
+
+        Ok(tier)
+    }
+
+    /// Returns the total size of the entries.
+    pub(super) fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub(super) async fn get(&self, cid: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let tx = self
+            .db
+            .transaction(&[DATA_STORE_NAME], TransactionMode::ReadOnly)?;
+        let data = tx
+            .store(DATA_STORE_NAME)?
+            .get(&Uint8Array::from(&cid[..]))
+            .await?;
+
+        if data.is_undefined() {
+            return Ok(None);
+        }
+
+        let data = to_bytes(&data)?;
+
+        // Don't write the entry on every hit, persist their recency in batches
+        let touches = {
+            let mut pending_touches = self.pending_touches.lock().expect("lock failed");
+            pending_touches.push(cid);
+
+            if pending_touches.len() < TOUCH_BATCH_SIZE {
+                return Ok(Some(data));
+            }
+
+            mem::take(&mut *pending_touches)
+        };
+
+        let mut index = self.index.lock().await;
+        let touched = touch_all(&mut index, &touches);
+        self.write(&[], &touched, None).await?;
+
+        Ok(Some(data))
+    }
+
+    pub(super) async fn has(&self, cid: Vec<u8>) -> Result<bool> {
+        let tx = self
+            .db
+            .transaction(&[DATA_STORE_NAME], TransactionMode::ReadOnly)?;
+        let key_range = KeyRange::only(&Uint8Array::from(&cid[..]))?;
+        let count = tx.store(DATA_STORE_NAME)?.count(Some(&key_range)).await?;
+
+        Ok(count > 0)
+    }
+
+    pub(super) async fn put(&self, cid: Vec<u8>, data: Vec<u8>) -> Result<()> {
+        let len = data.len() as u64;
+
+        if len > self.capacity {
+            return Ok(());
+        }
+
+        let touches = mem::take(&mut *self.pending_touches.lock().expect("lock failed"));
+        let mut index = self.index.lock().await;
+
+        // Evict according to the recent uses
+        let mut touched = touch_all(&mut index, &touches);
+
+        if index.contains(&cid) {
+            touched.extend(touch_all(&mut index, &[cid]));
+            return self.write(&[], &touched, None).await;
+        }
+
+        let evicted = index.evict(self.capacity, len);
+        let seq = index.insert(cid.clone(), len);
+        touched.push((cid.clone(), seq, len));
+        self.size.store(index.size(), Ordering::Relaxed);
+
+        self.write(&evicted, &touched, Some((&cid, &data))).await
+    }
+
+    /// Removes the evicted entries, writes the recency of the touched ones and the data of
+    /// the inserted one in a single transaction.
+    async fn write(
+        &self,
+        evicted: &[Vec<u8>],
+        touched: &[(Vec<u8>, u64, u64)],
+        inserted: Option<(&[u8], &[u8])>,
+    ) -> Result<()> {
+        if evicted.is_empty() && touched.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.db.transaction(
+            &[DATA_STORE_NAME, ENTRIES_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let data_store = tx.store(DATA_STORE_NAME)?;
+        let entries_store = tx.store(ENTRIES_STORE_NAME)?;
+
+        for cid in evicted {
+            let cid = Uint8Array::from(&cid[..]);
+            data_store.delete(&cid).await?;
+            entries_store.delete(&cid).await?;
+        }
+
+        for (cid, seq, len) in touched {
+            let entry = Uint8Array::from(&encode_entry(*seq, *len)[..]);
+            entries_store
+                .put(&entry, Some(&Uint8Array::from(&cid[..])))
+                .await?;
+        }
+
+        if let Some((cid, data)) = inserted {
+            data_store
+                .put(&Uint8Array::from(data), Some(&Uint8Array::from(cid)))
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Marks the entries as used, in order, returning their new recency.
+fn touch_all(index: &mut RecencyIndex, cids: &[Vec<u8>]) -> Vec<(Vec<u8>, u64, u64)> {
+    cids.iter()
+        .filter_map(|cid| {
+            let (seq, len) = index.touch(cid)?;
+            Some((cid.clone(), seq, len))
+        })
+        .collect()
+}
+
+fn to_bytes(value: &JsValue) -> Result<Vec<u8>> {
+    let arr = value.dyn_ref::<Uint8Array>().ok_or_else(|| {
+        Error::StoredDataError(format!(
+            "expected 'Uint8Array', got '{}'",
+            value
+                .js_typeof()
+                .as_string()
+                .expect("typeof must be a string")
+        ))
+    })?;
+
+    Ok(arr.to_vec())
+}
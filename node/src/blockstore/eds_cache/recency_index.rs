@@ -0,0 +1,105 @@
+//! Recency of the entries of the on-disk tiers whose databases can't be ordered by it.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Sequence numbers of the last uses and sizes of the persisted entries.
+///
+/// The index is loaded from the persisted entries when the tier is opened, and changed before
+/// the database, which keeps the entries in sync with it.
+#[derive(Debug, Default)]
+pub(super) struct RecencyIndex {
+    /// CID -> (sequence number of the last use, size) of the entry.
+    entries: HashMap<Vec<u8>, (u64, u64)>,
+    /// Sequence number of the last use -> CID of the entry.
+    recency: BTreeMap<u64, Vec<u8>>,
+    /// Total size of the entries.
+    size: u64,
+    next_seq: u64,
+}
+
+impl RecencyIndex {
+    /// Adds an entry persisted by the previous runs.
+    pub(super) fn load(&mut self, cid: Vec<u8>, seq: u64, len: u64) {
+        if let Some((old_seq, old_len)) = self.entries.insert(cid.clone(), (seq, len)) {
+            self.recency.remove(&old_seq);
+            self.size -= old_len;
+        }
+
+        self.recency.insert(seq, cid);
+        self.size += len;
+        self.next_seq = self.next_seq.max(seq + 1);
+    }
+
+    /// Returns the total size of the entries.
+    pub(super) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(super) fn contains(&self, cid: &[u8]) -> bool {
+        self.entries.contains_key(cid)
+    }
+
+    /// Marks the entry as the most recently used one.
+    ///
+    /// Returns the new sequence number and the size of the entry, if it exists.
+    pub(super) fn touch(&mut self, cid: &[u8]) -> Option<(u64, u64)> {
+        let (seq, len) = self.entries.get_mut(cid)?;
+        let cid = self
+            .recency
+            .remove(seq)
+            .expect("entries in sync with recency");
+
+        *seq = self.next_seq;
+        self.recency.insert(self.next_seq, cid);
+        self.next_seq += 1;
+
+        Some((*seq, *len))
+    }
+
+    /// Adds a new entry as the most recently used one, returning its sequence number.
+    pub(super) fn insert(&mut self, cid: Vec<u8>, len: u64) -> u64 {
+        let seq = self.next_seq;
+
+        self.next_seq += 1;
+        self.size += len;
+        self.recency.insert(seq, cid.clone());
+        self.entries.insert(cid, (seq, len));
+
+        seq
+    }
+
+    /// Removes the least recently used entries until `reserved` more bytes fit within `capacity`.
+    ///
+    /// Returns the CIDs of the removed entries.
+    pub(super) fn evict(&mut self, capacity: u64, reserved: u64) -> Vec<Vec<u8>> {
+        let mut evicted = Vec::new();
+
+        while self.size + reserved > capacity {
+            let Some((_, cid)) = self.recency.pop_first() else {
+                break;
+            };
+
+            if let Some((_, len)) = self.entries.remove(&cid) {
+                self.size -= len;
+            }
+            evicted.push(cid);
+        }
+
+        evicted
+    }
+}
+
+/// Encodes the sequence number of the last use and the size of the entry.
+pub(super) fn encode_entry(seq: u64, len: u64) -> [u8; 16] {
+    let mut buf = [0; 16];
+    buf[..8].copy_from_slice(&seq.to_be_bytes());
+    buf[8..].copy_from_slice(&len.to_be_bytes());
+    buf
+}
+
+/// Decodes the sequence number of the last use and the size of the entry.
+pub(super) fn decode_entry(buf: &[u8]) -> Option<(u64, u64)> {
+    let seq = buf.get(..8)?.try_into().ok()?;
+    let len = buf.get(8..16)?.try_into().ok()?;
+    Some((u64::from_be_bytes(seq), u64::from_be_bytes(len)))
+}
@@ -0,0 +1,269 @@
+//! On-disk tier of the [`EdsCache`] kept in a [`redb`] database.
+//!
+//! [`EdsCache`]: super::EdsCache
+
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use blockstore::Result;
+use redb::{
+    Database, Durability, ReadTransaction, ReadableTable, TableDefinition, WriteTransaction,
+};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+use super::TOUCH_BATCH_SIZE;
+
+/// CID -> data of the entry.
+const DATA_TABLE: TableDefinition<'static, &[u8], &[u8]> = TableDefinition::new("EDS_CACHE.DATA");
+/// CID -> (sequence number of the last use, size) of the entry.
+const ENTRIES_TABLE: TableDefinition<'static, &[u8], (u64, u64)> =
+    TableDefinition::new("EDS_CACHE.ENTRIES");
+/// Sequence number of the last use -> CID of the entry.
+const RECENCY_TABLE: TableDefinition<'static, u64, &[u8]> =
+    TableDefinition::new("EDS_CACHE.RECENCY");
+/// (total size of the entries, next sequence number).
+const STATE_TABLE: TableDefinition<'static, (), (u64, u64)> =
+    TableDefinition::new("EDS_CACHE.STATE");
+
+#[derive(Debug)]
+pub(super) struct RedbTier {
+    db: Arc<Database>,
+    capacity: u64,
+    /// Mirror of the size persisted in the database, for the stats.
+    size: AtomicU64,
+    /// CIDs of the entries used since their recency was last persisted, in the order of use.
+    pending_touches: Mutex<Vec<Vec<u8>>>,
+}
+
+impl RedbTier {
+    pub(super) async fn new(db: Arc<Database>, capacity: u64) -> Result<Self> {
+        let size = write_tx(db.clone(), Durability::Immediate, move |tx| {
+            // Make sure the tables exist for the read transactions
+            tx.open_table(DATA_TABLE)?;
+            evict(tx, capacity, 0)
+        })
+        .await?;
+
+        Ok(RedbTier {
+            db,
+            capacity,
+            size: AtomicU64::new(size),
+            pending_touches: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Returns the total size of the entries.
+    pub(super) fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub(super) async fn get(&self, cid: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let data = read_tx(self.db.clone(), {
+            let cid = cid.clone();
+            move |tx| {
+                Ok(tx
+                    .open_table(DATA_TABLE)?
+                    .get(&cid[..])?
+                    .map(|guard| guard.value().to_owned()))
+            }
+        })
+        .await?;
+
+        if data.is_none() {
+            return Ok(None);
+        }
+
+        // Don't open a write transaction for every hit, persist their recency in batches
+        let touches = {
+            let mut pending_touches = self.pending_touches.lock().expect("lock failed");
+            pending_touches.push(cid);
+
+            if pending_touches.len() < TOUCH_BATCH_SIZE {
+                return Ok(data);
+            }
+
+            mem::take(&mut *pending_touches)
+        };
+
+        // Losing the recency updates on a crash is fine, don't wait for them to be persisted
+        write_tx(self.db.clone(), Durability::None, move |tx| {
+            touch_all(tx, &touches)
+        })
+        .await?;
+
+        Ok(data)
+    }
+
+    pub(super) async fn has(&self, cid: Vec<u8>) -> Result<bool> {
+        read_tx(self.db.clone(), move |tx| {
+            Ok(tx.open_table(DATA_TABLE)?.get(&cid[..])?.is_some())
+        })
+        .await
+    }
+
+    pub(super) async fn put(&self, cid: Vec<u8>, data: Vec<u8>) -> Result<()> {
+        let len = data.len() as u64;
+        let capacity = self.capacity;
+
+        if len > capacity {
+            return Ok(());
+        }
+
+        let touches = mem::take(&mut *self.pending_touches.lock().expect("lock failed"));
+
+        let size = write_tx(self.db.clone(), Durability::Immediate, move |tx| {
+            // Evict according to the recent uses
+            touch_all(tx, &touches)?;
+
+            if tx.open_table(ENTRIES_TABLE)?.get(&cid[..])?.is_some() {
+                touch(tx, &cid)?;
+                return Ok(read_state(tx)?.0);
+            }
+
+            let size = evict(tx, capacity, len)?;
+            let (_, seq) = read_state(tx)?;
+
+            tx.open_table(DATA_TABLE)?.insert(&cid[..], &data[..])?;
+            tx.open_table(ENTRIES_TABLE)?.insert(&cid[..], (seq, len))?;
+            tx.open_table(RECENCY_TABLE)?.insert(seq, &cid[..])?;
+            tx.open_table(STATE_TABLE)?
+                .insert((), (size + len, seq + 1))?;
+
+            Ok(size + len)
+        })
+        .await?;
+
+        self.size.store(size, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+impl Drop for RedbTier {
+    fn drop(&mut self) {
+        let touches = mem::take(self.pending_touches.get_mut().expect("lock failed"));
+
+        if touches.is_empty() {
+            return;
+        }
+
+        // Don't lose the recency updates on a clean shutdown
+        if let Err(e) = persist_touches(&self.db, &touches) {
+            warn!("Failed to persist the recency of the cached entries: {e}");
+        }
+    }
+}
+
+/// Persist the recency of the used entries, blocking the current thread.
+fn persist_touches(db: &Database, cids: &[Vec<u8>]) -> Result<()> {
+    let mut tx = db.begin_write()?;
+    tx.set_durability(Durability::None);
+    touch_all(&tx, cids)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Execute a read transaction.
+async fn read_tx<F, T>(db: Arc<Database>, f: F) -> Result<T>
+where
+    F: FnOnce(&mut ReadTransaction) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_blocking(move || {
+        let mut tx = db.begin_read()?;
+        f(&mut tx)
+    })
+    .await?
+}
+
+/// Execute a write transaction.
+///
+/// If closure returns an error the transaction is aborted, otherwise commited.
+async fn write_tx<F, T>(db: Arc<Database>, durability: Durability, f: F) -> Result<T>
+where
+    F: FnOnce(&mut WriteTransaction) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_blocking(move || {
+        let mut tx = db.begin_write()?;
+        tx.set_durability(durability);
+        let res = f(&mut tx);
+
+        if res.is_ok() {
+            tx.commit()?;
+        } else {
+            tx.abort()?;
+        }
+
+        res
+    })
+    .await?
+}
+
+/// Returns the total size of the entries and the next sequence number.
+fn read_state(tx: &WriteTransaction) -> Result<(u64, u64)> {
+    let state = tx
+        .open_table(STATE_TABLE)?
+        .get(())?
+        .map(|guard| guard.value());
+
+    Ok(state.unwrap_or((0, 0)))
+}
+
+/// Marks the entry as the most recently used one.
+fn touch(tx: &WriteTransaction, cid: &[u8]) -> Result<()> {
+    let mut entries_table = tx.open_table(ENTRIES_TABLE)?;
+    let Some((seq, len)) = entries_table.get(cid)?.map(|guard| guard.value()) else {
+        return Ok(());
+    };
+
+    let (size, next_seq) = read_state(tx)?;
+    let mut recency_table = tx.open_table(RECENCY_TABLE)?;
+
+    recency_table.remove(seq)?;
+    recency_table.insert(next_seq, cid)?;
+    entries_table.insert(cid, (next_seq, len))?;
+    tx.open_table(STATE_TABLE)?
+        .insert((), (size, next_seq + 1))?;
+
+    Ok(())
+}
+
+/// Marks the entries as used, in order.
+fn touch_all(tx: &WriteTransaction, cids: &[Vec<u8>]) -> Result<()> {
+    for cid in cids {
+        touch(tx, cid)?;
+    }
+
+    Ok(())
+}
+
+/// Evicts the least recently used entries until `reserved` more bytes fit within `capacity`.
+///
+/// Returns the total size of the remaining entries.
+fn evict(tx: &WriteTransaction, capacity: u64, reserved: u64) -> Result<u64> {
+    let (mut size, next_seq) = read_state(tx)?;
+    let mut data_table = tx.open_table(DATA_TABLE)?;
+    let mut entries_table = tx.open_table(ENTRIES_TABLE)?;
+    let mut recency_table = tx.open_table(RECENCY_TABLE)?;
+
+    while size + reserved > capacity {
+        let Some(cid) = recency_table
+            .pop_first()?
+            .map(|(_, cid)| cid.value().to_owned())
+        else {
+            break;
+        };
+
+        if let Some((_, len)) = entries_table.remove(&cid[..])?.map(|guard| guard.value()) {
+            size -= len;
+        }
+        data_table.remove(&cid[..])?;
+    }
+
+    tx.open_table(STATE_TABLE)?.insert((), (size, next_seq))?;
+
+    Ok(size)
+}
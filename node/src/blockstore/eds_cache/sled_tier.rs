@@ -0,0 +1,196 @@
+//! On-disk tier of the [`EdsCache`] kept in a [`sled`] database.
+//!
+//! [`EdsCache`]: super::EdsCache
+
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use blockstore::{Error, Result};
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use sled::{Db, Transactional, Tree};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+
+use super::recency_index::{decode_entry, encode_entry, RecencyIndex};
+use super::TOUCH_BATCH_SIZE;
+
+/// CID -> data of the entry.
+const DATA_TREE_ID: &[u8] = b"EDS_CACHE.DATA";
+/// CID -> (sequence number of the last use, size) of the entry.
+const ENTRIES_TREE_ID: &[u8] = b"EDS_CACHE.ENTRIES";
+
+#[derive(Debug)]
+pub(super) struct SledTier {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    data: Tree,
+    entries: Tree,
+    capacity: u64,
+    index: Mutex<RecencyIndex>,
+    /// Mirror of the size of the index, for the stats.
+    size: AtomicU64,
+    /// CIDs of the entries used since their recency was last persisted, in the order of use.
+    pending_touches: Mutex<Vec<Vec<u8>>>,
+}
+
+impl SledTier {
+    pub(super) async fn new(db: Db, capacity: u64) -> Result<Self> {
+        spawn_blocking(move || {
+            let data = db.open_tree(DATA_TREE_ID)?;
+            let entries = db.open_tree(ENTRIES_TREE_ID)?;
+            let mut index = RecencyIndex::default();
+
+            for entry in entries.iter() {
+                let (cid, entry) = entry?;
+                let (seq, len) = decode_entry(&entry)
+                    .ok_or_else(|| Error::StoredDataError("invalid eds cache entry".to_string()))?;
+                index.load(cid.to_vec(), seq, len);
+            }
+
+            let evicted = index.evict(capacity, 0);
+            (&data, &entries)
+                .transaction(|(data, entries)| {
+                    for cid in &evicted {
+                        data.remove(&cid[..])?;
+                        entries.remove(&cid[..])?;
+                    }
+                    Ok::<_, ConflictableTransactionError>(())
+                })
+                .map_err(transaction_error)?;
+
+            Ok(SledTier {
+                inner: Arc::new(Inner {
+                    data,
+                    entries,
+                    capacity,
+                    size: AtomicU64::new(index.size()),
+                    index: Mutex::new(index),
+                    pending_touches: Mutex::new(Vec::new()),
+                }),
+            })
+        })
+        .await?
+    }
+
+    /// Returns the total size of the entries.
+    pub(super) fn size(&self) -> u64 {
+        self.inner.size.load(Ordering::Relaxed)
+    }
+
+    pub(super) async fn get(&self, cid: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let Some(data) = inner.data.get(&cid)? else {
+                return Ok(None);
+            };
+
+            // Don't write the entry on every hit, persist their recency in batches
+            let touches = {
+                let mut pending_touches = inner.pending_touches.lock().expect("lock failed");
+                pending_touches.push(cid);
+
+                if pending_touches.len() < TOUCH_BATCH_SIZE {
+                    return Ok(Some(data.to_vec()));
+                }
+
+                mem::take(&mut *pending_touches)
+            };
+
+            let mut index = inner.index.lock().expect("lock failed");
+            inner.touch_all(&mut index, &touches)?;
+
+            Ok(Some(data.to_vec()))
+        })
+        .await?
+    }
+
+    pub(super) async fn has(&self, cid: Vec<u8>) -> Result<bool> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || Ok(inner.data.contains_key(cid)?)).await?
+    }
+
+    pub(super) async fn put(&self, cid: Vec<u8>, data: Vec<u8>) -> Result<()> {
+        let inner = self.inner.clone();
+        let len = data.len() as u64;
+
+        if len > inner.capacity {
+            return Ok(());
+        }
+
+        spawn_blocking(move || {
+            let touches = mem::take(&mut *inner.pending_touches.lock().expect("lock failed"));
+            let mut index = inner.index.lock().expect("lock failed");
+
+            // Evict according to the recent uses
+            inner.touch_all(&mut index, &touches)?;
+
+            if index.contains(&cid) {
+                return inner.touch_all(&mut index, &[cid]);
+            }
+
+            let evicted = index.evict(inner.capacity, len);
+            let seq = index.insert(cid.clone(), len);
+            inner.size.store(index.size(), Ordering::Relaxed);
+
+            (&inner.data, &inner.entries)
+                .transaction(|(data_tree, entries_tree)| {
+                    for cid in &evicted {
+                        data_tree.remove(&cid[..])?;
+                        entries_tree.remove(&cid[..])?;
+                    }
+                    data_tree.insert(&cid[..], &data[..])?;
+                    entries_tree.insert(&cid[..], &encode_entry(seq, len)[..])?;
+                    Ok::<_, ConflictableTransactionError>(())
+                })
+                .map_err(transaction_error)
+        })
+        .await?
+    }
+}
+
+impl Inner {
+    /// Marks the entries as used, in order, and persists their recency.
+    fn touch_all(&self, index: &mut RecencyIndex, cids: &[Vec<u8>]) -> Result<()> {
+        let touched: Vec<_> = cids
+            .iter()
+            .filter_map(|cid| Some((cid, index.touch(cid)?)))
+            .collect();
+
+        if touched.is_empty() {
+            return Ok(());
+        }
+
+        self.entries
+            .transaction(|entries| {
+                for (cid, (seq, len)) in &touched {
+                    entries.insert(&cid[..], &encode_entry(*seq, *len)[..])?;
+                }
+                Ok::<_, ConflictableTransactionError>(())
+            })
+            .map_err(transaction_error)
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        let touches = mem::take(self.pending_touches.get_mut().expect("lock failed"));
+        let mut index = mem::take(self.index.get_mut().expect("lock failed"));
+
+        // Don't lose the recency updates on a clean shutdown
+        if let Err(e) = self.touch_all(&mut index, &touches) {
+            warn!("Failed to persist the recency of the cached entries: {e}");
+        }
+    }
+}
+
+fn transaction_error(e: TransactionError) -> Error {
+    match e {
+        TransactionError::Abort(e) | TransactionError::Storage(e) => e.into(),
+    }
+}
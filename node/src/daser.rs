@@ -6,12 +6,20 @@
 //! get verified successfuly, then block is marked as accepted. Otherwise, if [`Daser`] doesn't
 //! receive valid samples, block is marked as not accepted and data sampling continues.
 //!
+//! To spread the verification across both row and column roots, [`Daser`] alternates the axis
+//! along which it requires the samples of a block to be proven, starting from a random one.
+//! Shwap sample requests can't specify the axis, so the samples proven along the other one are
+//! ignored at first. Go nodes prove samples only along the rows, so if no peer serves the
+//! required axis in time, any is accepted. The axis each sample was actually proven along is
+//! recorded in the [`SamplingMetadata`] together with its CID.
+//!
 //! [`Sample`]: celestia_types::sample::Sample
+//! [`SamplingMetadata`]: crate::store::SamplingMetadata
 
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
@@ -26,7 +34,8 @@ use crate::p2p::shwap::convert_cid;
 use crate::p2p::{P2p, P2pError};
 use crate::store::{Store, StoreError};
 
-const MAX_SAMPLES_NEEDED: usize = 16;
+/// Default maximum number of samples fetched for every block.
+pub const DEFAULT_MAX_SAMPLES_NEEDED: usize = 16;
 
 type Result<T, E = DaserError> = std::result::Result<T, E>;
 
@@ -56,6 +65,10 @@ where
     pub p2p: Arc<P2p>,
     /// Headers storage.
     pub store: Arc<S>,
+    /// Maximum number of samples fetched for every block.
+    ///
+    /// Blocks with fewer shares are sampled entirely.
+    pub max_samples_needed: usize,
 }
 
 impl Daser {
@@ -109,7 +122,7 @@ where
             cancellation_token,
             p2p: args.p2p,
             store: args.store,
-            max_samples_needed: MAX_SAMPLES_NEEDED,
+            max_samples_needed: args.max_samples_needed,
         })
     }
 
@@ -131,33 +144,42 @@ where
         self.store.wait_height(height).await?;
 
         let header = self.store.get_by_height(height).await?;
-        let (sampled_cids, accepted) = self.sample_block(&header).await?;
+        let (samples, accepted) = self.sample_block(&header).await?;
 
         self.store
-            .update_sampling_metadata(height, accepted, sampled_cids)
+            .update_sampling_metadata(height, accepted, samples)
             .await?;
 
         Ok(())
     }
 
-    async fn sample_block(&mut self, header: &ExtendedHeader) -> Result<(Vec<Cid>, bool)> {
+    async fn sample_block(
+        &mut self,
+        header: &ExtendedHeader,
+    ) -> Result<(Vec<(Cid, AxisType)>, bool)> {
         let now = Instant::now();
         let indexes = random_indexes(header.dah.square_width(), self.max_samples_needed);
+        let proof_axes = alternating_axes(rand::random());
         let mut futs = FuturesUnordered::new();
 
-        for (row_index, column_index) in indexes {
-            let fut = self
-                .p2p
-                .get_sample(row_index, column_index, header.height().value());
+        for ((row_index, column_index), proof_axis) in indexes.into_iter().zip(proof_axes) {
+            let fut = self.p2p.get_sample_with_proof_axis(
+                row_index,
+                column_index,
+                proof_axis,
+                header.height().value(),
+            );
             futs.push(fut);
         }
 
-        let mut cids: Vec<Cid> = Vec::new();
+        let mut samples: Vec<(Cid, AxisType)> = Vec::new();
         let mut accepted = true;
 
         while let Some(res) = futs.next().await {
             match res {
-                Ok(sample) => cids.push(convert_cid(&sample.id.into())?),
+                Ok(sample) => {
+                    samples.push((convert_cid(&sample.id.into())?, sample.proof_type));
+                }
                 // Validation is done at Bitswap level, through `ShwapMultihasher`.
                 // If the sample is not valid, it will never be delivered to us
                 // as the data of the CID. Because of that, the only signal
@@ -167,17 +189,35 @@ where
             }
         }
 
+        let col_proofs = samples
+            .iter()
+            .filter(|(_, axis)| *axis == AxisType::Col)
+            .count();
+
         debug!(
-            "Data sampling of {} is {}. Took {:?}",
+            "Data sampling of {} is {} ({} row and {} column proofs). Took {:?}",
             header.height(),
             if accepted { "accepted" } else { "rejected" },
+            samples.len() - col_proofs,
+            col_proofs,
             now.elapsed()
         );
 
-        Ok((cids, accepted))
+        Ok((samples, accepted))
     }
 }
 
+/// Returns an endless sequence of axes alternating between rows and columns.
+fn alternating_axes(start_with_row: bool) -> impl Iterator<Item = AxisType> {
+    let (first, second) = if start_with_row {
+        (AxisType::Row, AxisType::Col)
+    } else {
+        (AxisType::Col, AxisType::Row)
+    };
+
+    [first, second].into_iter().cycle()
+}
+
 fn random_indexes(square_width: u16, max_samples_needed: usize) -> HashSet<(u16, u16)> {
     let samples_in_block = usize::from(square_width).pow(2);
 
@@ -209,7 +249,7 @@ mod tests {
     use celestia_tendermint_proto::Protobuf;
     use celestia_types::sample::{Sample, SampleId};
     use celestia_types::test_utils::{generate_eds, ExtendedHeaderGenerator};
-    use celestia_types::{AxisType, DataAvailabilityHeader, ExtendedDataSquare};
+    use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
 
     #[async_test]
     async fn received_valid_samples() {
@@ -219,6 +259,7 @@ mod tests {
         let _daser = Daser::start(DaserArgs {
             p2p: Arc::new(mock),
             store: store.clone(),
+            max_samples_needed: DEFAULT_MAX_SAMPLES_NEEDED,
         })
         .unwrap();
 
@@ -240,6 +281,7 @@ mod tests {
         let _daser = Daser::start(DaserArgs {
             p2p: Arc::new(mock),
             store: store.clone(),
+            max_samples_needed: DEFAULT_MAX_SAMPLES_NEEDED,
         })
         .unwrap();
 
@@ -267,9 +309,13 @@ mod tests {
         store.append_single(header).await.unwrap();
 
         let mut cids = Vec::new();
+        let mut proof_axes = Vec::new();
+        let mut required_axes = Vec::new();
 
-        for i in 0..(square_width * square_width).min(MAX_SAMPLES_NEEDED) {
+        for i in 0..(square_width * square_width).min(DEFAULT_MAX_SAMPLES_NEEDED) {
             let (cid, respond_to) = handle.expect_get_shwap_cid().await;
+            let required_axis = handle.required_proof_axis(&cid).unwrap();
+            required_axes.push(required_axis);
 
             // Simulate invalid sample by triggering BitswapQueryTimeout
             if simulate_invalid_sampling && i == 2 {
@@ -280,22 +326,40 @@ mod tests {
             let sample_id: SampleId = cid.try_into().unwrap();
             assert_eq!(sample_id.block_height(), height);
 
-            let sample = gen_sample_of_cid(sample_id, &eds, store).await;
+            // Serve some samples proven along rows, like Go nodes once any axis is accepted
+            let proof_axis = if i % 3 == 0 {
+                AxisType::Row
+            } else {
+                required_axis
+            };
+
+            let sample = gen_sample_of_cid(sample_id, proof_axis, &eds, store).await;
             let sample_bytes = sample.encode_vec().unwrap();
 
             respond_to.send(Ok(sample_bytes)).unwrap();
             cids.push(cid);
+            proof_axes.push((cid, proof_axis));
         }
 
         handle.expect_no_cmd().await;
 
+        // Required axes are spread evenly across rows and columns
+        let required_rows = required_axes
+            .iter()
+            .filter(|axis| **axis == AxisType::Row)
+            .count();
+        let required_cols = required_axes.len() - required_rows;
+        assert!(required_rows.abs_diff(required_cols) <= 1);
+
         let sampling_metadata = store.get_sampling_metadata(height).await.unwrap().unwrap();
         assert_eq!(sampling_metadata.accepted, !simulate_invalid_sampling);
         assert_eq!(sampling_metadata.cids_sampled, cids);
+        assert_eq!(sampling_metadata.proof_axes, proof_axes);
     }
 
     async fn gen_sample_of_cid(
         sample_id: SampleId,
+        proof_axis: AxisType,
         eds: &ExtendedDataSquare,
         store: &InMemoryStore,
     ) -> Sample {
@@ -304,7 +368,7 @@ mod tests {
         Sample::new(
             sample_id.row_index(),
             sample_id.column_index(),
-            AxisType::Row,
+            proof_axis,
             eds,
             header.height().value(),
         )
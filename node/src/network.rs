@@ -21,6 +21,75 @@ pub enum Network {
     Private,
 }
 
+/// Configuration of a Celestia network.
+///
+/// Besides the canonical networks, created with [`NetworkConfig::canonical`], it
+/// allows connecting to any custom network, e.g. a devnet with its own chain id.
+/// It can be deserialized from any serde format, like TOML or JSON:
+///
+/// ```toml
+/// id = "my-devnet"
+/// genesis_hash = "6BE39EFD10BA412A9DB5288488303F5DD32CF386707A5BEF33617F4C43301872"
+/// bootnodes = ["/ip4/10.0.0.1/tcp/2121/p2p/12D3KooWSqZaLcn5Guypo2mrHr297YPJnV8KMEMXNjs3qAS8msw8"]
+///
+/// [protocol_versions]
+/// header_ex = "v0.0.3"
+/// header_sub = "v0.0.1"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NetworkConfig {
+    /// Chain id of the network.
+    pub id: String,
+    /// Hash of the genesis block in the network.
+    #[serde(default)]
+    pub genesis_hash: Option<Hash>,
+    /// Bootstrap nodes of the network.
+    #[serde(default)]
+    pub bootnodes: Vec<Multiaddr>,
+    /// Versions of the protocols used in the network.
+    #[serde(default)]
+    pub protocol_versions: ProtocolVersions,
+}
+
+impl NetworkConfig {
+    /// Get the configuration of the given canonical network.
+    pub fn canonical(network: Network) -> Self {
+        NetworkConfig {
+            id: network_id(network).to_owned(),
+            genesis_hash: network_genesis(network),
+            bootnodes: canonical_network_bootnodes(network).collect(),
+            protocol_versions: ProtocolVersions::default(),
+        }
+    }
+}
+
+impl From<Network> for NetworkConfig {
+    fn from(network: Network) -> Self {
+        NetworkConfig::canonical(network)
+    }
+}
+
+/// Versions of the Celestia p2p protocols.
+///
+/// Defaults to the versions used by the canonical networks.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProtocolVersions {
+    /// Version of the header-ex protocol.
+    pub header_ex: String,
+    /// Version of the header-sub gossipsub topic.
+    pub header_sub: String,
+}
+
+impl Default for ProtocolVersions {
+    fn default() -> Self {
+        ProtocolVersions {
+            header_ex: "v0.0.3".to_owned(),
+            header_sub: "v0.0.1".to_owned(),
+        }
+    }
+}
+
 /// Unknown network provided.
 #[derive(Debug, Error)]
 #[error("unknown network {0}")]
@@ -133,4 +202,44 @@ mod tests {
         let private = canonical_network_bootnodes(Network::Private);
         assert_eq!(private.count(), 0);
     }
+
+    #[test]
+    fn test_network_config_deserialization() {
+        let config: NetworkConfig = serde_json::from_str(
+            r#"{
+                "id": "my-devnet",
+                "genesis_hash": "6BE39EFD10BA412A9DB5288488303F5DD32CF386707A5BEF33617F4C43301872",
+                "bootnodes": ["/ip4/10.0.0.1/tcp/2121/p2p/12D3KooWSqZaLcn5Guypo2mrHr297YPJnV8KMEMXNjs3qAS8msw8"],
+                "protocol_versions": { "header_ex": "v0.0.4" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.id, "my-devnet");
+        assert_eq!(config.genesis_hash, network_genesis(Network::Mainnet));
+        assert_eq!(config.bootnodes.len(), 1);
+        assert_eq!(config.protocol_versions.header_ex, "v0.0.4");
+        assert_eq!(
+            config.protocol_versions.header_sub,
+            ProtocolVersions::default().header_sub
+        );
+
+        // only id is required
+        let config: NetworkConfig = serde_json::from_str(r#"{ "id": "my-devnet" }"#).unwrap();
+        assert_eq!(config.genesis_hash, None);
+        assert!(config.bootnodes.is_empty());
+        assert_eq!(config.protocol_versions, ProtocolVersions::default());
+    }
+
+    #[test]
+    fn test_canonical_network_config() {
+        let config = NetworkConfig::canonical(Network::Mocha);
+
+        assert_eq!(config.id, "mocha-4");
+        assert_eq!(config.genesis_hash, network_genesis(Network::Mocha));
+        assert_eq!(
+            config.bootnodes,
+            canonical_network_bootnodes(Network::Mocha).collect::<Vec<_>>()
+        );
+    }
 }
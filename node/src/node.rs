@@ -6,14 +6,17 @@
 
 use std::ops::RangeBounds;
 use std::sync::Arc;
+use std::time::Duration;
 
 use blockstore::Blockstore;
+use celestia_types::column::Column;
 use celestia_types::hash::Hash;
-use celestia_types::namespaced_data::NamespacedData;
+use celestia_types::namespaced_data::{NamespacedData, NamespacedRows};
 use celestia_types::nmt::Namespace;
 use celestia_types::row::Row;
 use celestia_types::sample::Sample;
-use celestia_types::ExtendedHeader;
+use celestia_types::{ExtendedHeader, VerificationOptions};
+use futures::io::{AsyncRead, AsyncWrite};
 use libp2p::identity::Keypair;
 use libp2p::swarm::NetworkInfo;
 use libp2p::{Multiaddr, PeerId};
@@ -23,9 +26,11 @@ use tracing::warn;
 
 use crate::daser::{Daser, DaserArgs, DaserError};
 use crate::executor::spawn;
-use crate::p2p::{P2p, P2pArgs, P2pError};
+use crate::network::ProtocolVersions;
+use crate::p2p::{HeaderExServerStats, P2p, P2pArgs, P2pError};
 use crate::peer_tracker::PeerTrackerInfo;
-use crate::store::{SamplingMetadata, Store, StoreError};
+use crate::store::archive::{self, ArchiveError};
+use crate::store::{ForkEvidence, SamplingMetadata, Store, StoreError};
 use crate::syncer::{Syncer, SyncerArgs, SyncerError, SyncingInfo};
 
 type Result<T, E = NodeError> = std::result::Result<T, E>;
@@ -48,6 +53,10 @@ pub enum NodeError {
     /// An error propagated from the [`Daser`] module.
     #[error(transparent)]
     Daser(#[from] DaserError),
+
+    /// An error propagated from the [`archive`] module.
+    #[error(transparent)]
+    Archive(#[from] ArchiveError),
 }
 
 /// Node conifguration.
@@ -60,13 +69,32 @@ where
     pub network_id: String,
     /// The hash of the genesis block in network.
     pub genesis_hash: Option<Hash>,
+    /// Versions of the p2p protocols used in the network.
+    pub p2p_protocol_versions: ProtocolVersions,
     /// The keypair to be used as [`Node`]s identity.
     pub p2p_local_keypair: Keypair,
     /// List of bootstrap nodes to connect to and trust.
     pub p2p_bootnodes: Vec<Multiaddr>,
     /// List of the addresses where [`Node`] will listen for incoming connections.
     pub p2p_listen_on: Vec<Multiaddr>,
+    /// Maximum number of samples fetched for every block by the [`Daser`].
+    pub das_max_samples_per_block: usize,
+    /// Number of trusted peers that need to agree on the head the [`Syncer`] synchronizes to.
+    pub syncer_head_quorum: usize,
+    /// Options used by the [`Syncer`] to verify the network head against the stored one.
+    ///
+    /// The trusting period is opt-in and not checked by default. A fresh store is initialized
+    /// with the genesis header, which is usually outside of the trusting period, so it should
+    /// be set only if the store holds a recent header.
+    pub syncer_verification_options: VerificationOptions,
+    /// Time without a new head after which the [`Syncer`] polls it from the trusted peers.
+    pub syncer_stall_timeout: Duration,
     /// The blockstore for bitswap.
+    ///
+    /// Bitswap serves the requests for the data it already holds, so an [`EdsCache`] can be
+    /// used to keep the recently fetched rows and samples without growing forever.
+    ///
+    /// [`EdsCache`]: crate::blockstore::EdsCache
     pub blockstore: B,
     /// The store for headers.
     pub store: S,
@@ -81,6 +109,7 @@ where
     store: Arc<S>,
     syncer: Arc<Syncer<S>>,
     _daser: Arc<Daser>,
+    genesis_hash: Option<Hash>,
     tasks_cancellation_token: CancellationToken,
 }
 
@@ -97,6 +126,7 @@ where
 
         let p2p = Arc::new(P2p::start(P2pArgs {
             network_id: config.network_id,
+            protocol_versions: config.p2p_protocol_versions,
             local_keypair: config.p2p_local_keypair,
             bootnodes: config.p2p_bootnodes,
             listen_on: config.p2p_listen_on,
@@ -108,11 +138,15 @@ where
             genesis_hash: config.genesis_hash,
             store: store.clone(),
             p2p: p2p.clone(),
+            head_quorum: config.syncer_head_quorum,
+            verification_options: config.syncer_verification_options,
+            stall_timeout: config.syncer_stall_timeout,
         })?);
 
         let daser = Arc::new(Daser::start(DaserArgs {
             p2p: p2p.clone(),
             store: store.clone(),
+            max_samples_needed: config.das_max_samples_per_block,
         })?);
 
         // spawn the task that will stop the services when the fraud is detected
@@ -141,6 +175,7 @@ where
             store,
             syncer,
             _daser: daser,
+            genesis_hash: config.genesis_hash,
             tasks_cancellation_token,
         })
     }
@@ -172,6 +207,11 @@ where
         Ok(self.p2p.network_info().await?)
     }
 
+    /// Get the statistics of the header-ex requests rejected by this node.
+    pub async fn header_ex_server_stats(&self) -> Result<HeaderExServerStats> {
+        Ok(self.p2p.header_ex_server_stats().await?)
+    }
+
     /// Get all the multiaddresses on which the node listens.
     pub async fn listeners(&self) -> Result<Vec<Multiaddr>> {
         Ok(self.p2p.listeners().await?)
@@ -223,6 +263,19 @@ where
         Ok(self.p2p.get_row(row_index, block_height).await?)
     }
 
+    /// Request a verified [`Column`] from the network.
+    ///
+    /// Columns are an extension of the shwap protocol which isn't supported by
+    /// the celestia-node (Go) peers, only other Lumina nodes can serve them.
+    ///
+    /// # Errors
+    ///
+    /// On failure to receive a verified [`Column`] within a certain time, the
+    /// `NodeError::P2p(P2pError::BitswapQueryTimeout)` error will be returned.
+    pub async fn request_column(&self, column_index: u16, block_height: u64) -> Result<Column> {
+        Ok(self.p2p.get_column(column_index, block_height).await?)
+    }
+
     /// Request a verified [`Sample`] from the network.
     ///
     /// # Errors
@@ -259,6 +312,54 @@ where
             .await?)
     }
 
+    /// Request verified [`NamespacedData`] of the `namespace` from all the rows of the block.
+    ///
+    /// The header of the block needs to be synchronized already, rows are verified against
+    /// its [`DataAvailabilityHeader`]. Rows which could contain the namespace, but don't,
+    /// are returned with the proofs of its absence.
+    ///
+    /// # Errors
+    ///
+    /// On failure to receive any of the verified rows within a certain time, the
+    /// `NodeError::P2p(P2pError::BitswapQueryTimeout)` error will be returned.
+    ///
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    pub async fn request_namespace_range(
+        &self,
+        block_height: u64,
+        namespace: Namespace,
+    ) -> Result<NamespacedRows> {
+        let header = self.store.get_by_height(block_height).await?;
+
+        Ok(self
+            .p2p
+            .get_namespaced_rows(namespace, &header.dah, block_height)
+            .await?)
+    }
+
+    /// Request a verified proof that the `namespace` has no shares in the block.
+    ///
+    /// The proof consists of the absence proofs of the rows which roots' namespace ranges
+    /// contain the `namespace`, see [`NamespacedRows::verify_absence`]. Returns `None` if
+    /// the namespace is present in the block.
+    ///
+    /// # Errors
+    ///
+    /// On failure to receive any of the verified rows within a certain time, the
+    /// `NodeError::P2p(P2pError::BitswapQueryTimeout)` error will be returned.
+    pub async fn request_namespace_absence(
+        &self,
+        block_height: u64,
+        namespace: Namespace,
+    ) -> Result<Option<NamespacedRows>> {
+        // Rows are already verified together against the header
+        let rows = self
+            .request_namespace_range(block_height, namespace)
+            .await?;
+
+        Ok(rows.is_absent().then_some(rows))
+    }
+
     /// Get current header syncing info.
     pub async fn syncer_info(&self) -> Result<SyncingInfo> {
         Ok(self.syncer.info().await?)
@@ -301,6 +402,13 @@ where
         Ok(self.store.get_range(range).await?)
     }
 
+    /// Get the evidence of the forks found in the network, in the order they were found.
+    ///
+    /// A found fork stops the synchronization and sampling, same as a valid fraud proof.
+    pub async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        Ok(self.store.get_fork_evidence().await?)
+    }
+
     /// Get data sampling metadata of an already sampled height.
     ///
     /// Returns `Ok(None)` if metadata for the given height does not exists.
@@ -311,6 +419,31 @@ where
             Err(e) => Err(e.into()),
         }
     }
+
+    /// Export synced headers from the given heights range, with their sampling metadata,
+    /// to the portable [`archive`].
+    ///
+    /// Returns the number of exported headers.
+    pub async fn export_headers<R, W>(&self, range: R, writer: W) -> Result<u64>
+    where
+        R: RangeBounds<u64> + Send,
+        W: AsyncWrite + Unpin,
+    {
+        Ok(archive::export(&*self.store, range, writer).await?)
+    }
+
+    /// Import headers from the portable [`archive`], verifying them before they
+    /// are appended to the store.
+    ///
+    /// If the store is empty, the archive must start with the network's genesis header.
+    ///
+    /// Returns the number of imported headers.
+    pub async fn import_headers<R>(&self, reader: R) -> Result<u64>
+    where
+        R: AsyncRead + Unpin,
+    {
+        Ok(archive::import(&*self.store, reader, self.genesis_hash).await?)
+    }
 }
 
 impl<S> Drop for Node<S>
@@ -16,6 +16,7 @@
 use std::collections::HashMap;
 use std::future::poll_fn;
 use std::io;
+use std::iter;
 use std::sync::Arc;
 use std::task::Poll;
 use std::time::Duration;
@@ -23,12 +24,15 @@ use std::time::Duration;
 use blockstore::Blockstore;
 use celestia_proto::p2p::pb::{header_request, HeaderRequest};
 use celestia_tendermint_proto::Protobuf;
-use celestia_types::namespaced_data::NamespacedData;
+use celestia_types::column::Column;
+use celestia_types::namespaced_data::{NamespacedData, NamespacedRows};
 use celestia_types::nmt::Namespace;
 use celestia_types::row::Row;
 use celestia_types::sample::Sample;
 use celestia_types::{fraud_proof::BadEncodingFraudProof, hash::Hash};
-use celestia_types::{ExtendedHeader, FraudProof, Height};
+use celestia_types::{
+    AxisType, DataAvailabilityHeader, ExtendedHeader, FraudProof, Height, VerificationOptions,
+};
 use cid::Cid;
 use futures::StreamExt;
 use instant::Instant;
@@ -50,25 +54,34 @@ use tokio::sync::{mpsc, oneshot, watch};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, instrument, trace, warn};
 
+mod bisection;
+mod fork_detector;
 mod header_ex;
 mod header_session;
 pub(crate) mod shwap;
 mod swarm;
 
 use crate::executor::{self, spawn, Interval};
-use crate::p2p::header_ex::{HeaderExBehaviour, HeaderExConfig};
+use crate::network::ProtocolVersions;
+use crate::p2p::fork_detector::{Conflict, ForkDetector};
+use crate::p2p::header_ex::utils::HeaderRequestExt;
+use crate::p2p::header_ex::{HeaderExBehaviour, HeaderExConfig, HeaderExEvent};
 use crate::p2p::header_session::HeaderSession;
-use crate::p2p::shwap::{namespaced_data_cid, row_cid, sample_cid, ShwapMultihasher};
+use crate::p2p::shwap::{
+    column_cid, namespaced_data_cid, row_cid, sample_cid, RequiredProofAxes, ShwapMultihasher,
+};
 use crate::p2p::swarm::new_swarm;
 use crate::peer_tracker::PeerTracker;
-use crate::peer_tracker::PeerTrackerInfo;
-use crate::store::Store;
+use crate::peer_tracker::{HeaderExThroughput, PeerTrackerInfo};
+use crate::store::{ForkEvidence, Store};
 use crate::utils::{
     celestia_protocol_id, fraudsub_ident_topic, gossipsub_ident_topic, MultiaddrExt,
     OneshotResultSender, OneshotResultSenderExt, OneshotSenderExt,
 };
 
-pub use crate::p2p::header_ex::HeaderExError;
+pub use crate::p2p::header_ex::{
+    HeadDisagreement, HeadQuorumResponse, HeaderExError, HeaderExServerStats,
+};
 
 // Minimal number of peers that we want to maintain connection to.
 // If we have fewer peers than that, we will try to reconnect / discover
@@ -88,10 +101,16 @@ pub(crate) const MAX_MH_SIZE: usize = 64;
 
 const GET_SAMPLE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Maximum time spent on waiting for a column, which may be served only by the Lumina peers.
+const GET_COLUMN_TIMEOUT: Duration = Duration::from_secs(30);
+
 // all fraud proofs for height bigger than head height by this threshold
 // will be ignored
 const FRAUD_PROOF_HEAD_HEIGHT_THRESHOLD: u64 = 20;
 
+/// For how long the peers serving conflicting headers are blocked.
+const FORK_PENALTY_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
 type Result<T, E = P2pError> = std::result::Result<T, E>;
 
 /// Representation of all the errors that can occur when interacting with [`P2p`].
@@ -152,6 +171,14 @@ pub enum P2pError {
     /// Bitswap query timed out.
     #[error("Bitswap query timed out")]
     BitswapQueryTimeout,
+
+    /// Header couldn't be verified against the trusted one.
+    #[error("Header verification failed: {0}")]
+    HeaderVerification(celestia_types::Error),
+
+    /// Shwap data couldn't be verified against the [`DataAvailabilityHeader`].
+    #[error("Shwap verification failed: {0}")]
+    ShwapVerification(celestia_types::Error),
 }
 
 impl From<oneshot::error::RecvError> for P2pError {
@@ -166,7 +193,9 @@ pub struct P2p {
     cmd_tx: mpsc::Sender<P2pCmd>,
     header_sub_watcher: watch::Receiver<Option<ExtendedHeader>>,
     peer_tracker_info_watcher: watch::Receiver<PeerTrackerInfo>,
+    peer_tracker: Arc<PeerTracker>,
     local_peer_id: PeerId,
+    required_proof_axes: RequiredProofAxes,
 }
 
 /// Arguments used to configure the [`P2p`].
@@ -177,6 +206,8 @@ where
 {
     /// An id of the network to connect to.
     pub network_id: String,
+    /// Versions of the protocols used in the network.
+    pub protocol_versions: ProtocolVersions,
     /// The keypair to be used as the identity.
     pub local_keypair: Keypair,
     /// List of bootstrap nodes to connect to and trust.
@@ -194,10 +225,17 @@ pub(crate) enum P2pCmd {
     NetworkInfo {
         respond_to: oneshot::Sender<NetworkInfo>,
     },
+    HeaderExServerStats {
+        respond_to: oneshot::Sender<HeaderExServerStats>,
+    },
     HeaderExRequest {
         request: HeaderRequest,
         respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
     },
+    HeaderExHeadRequest {
+        quorum: usize,
+        respond_to: OneshotResultSender<HeadQuorumResponse, P2pError>,
+    },
     Listeners {
         respond_to: oneshot::Sender<Vec<Multiaddr>>,
     },
@@ -236,8 +274,15 @@ impl P2p {
 
         let peer_tracker = Arc::new(PeerTracker::new());
         let peer_tracker_info_watcher = peer_tracker.info_watcher();
+        let required_proof_axes = RequiredProofAxes::default();
 
-        let mut worker = Worker::new(args, cmd_rx, header_sub_tx, peer_tracker)?;
+        let mut worker = Worker::new(
+            args,
+            cmd_rx,
+            header_sub_tx,
+            peer_tracker.clone(),
+            required_proof_axes.clone(),
+        )?;
 
         spawn(async move {
             worker.run().await;
@@ -247,7 +292,9 @@ impl P2p {
             cmd_tx,
             header_sub_watcher: header_sub_rx,
             peer_tracker_info_watcher,
+            peer_tracker,
             local_peer_id,
+            required_proof_axes,
         })
     }
 
@@ -257,12 +304,15 @@ impl P2p {
         let (cmd_tx, cmd_rx) = mpsc::channel(16);
         let (header_sub_tx, header_sub_rx) = watch::channel(None);
         let (peer_tracker_tx, peer_tracker_rx) = watch::channel(PeerTrackerInfo::default());
+        let required_proof_axes = RequiredProofAxes::default();
 
         let p2p = P2p {
             cmd_tx: cmd_tx.clone(),
             header_sub_watcher: header_sub_rx,
             peer_tracker_info_watcher: peer_tracker_rx,
+            peer_tracker: Arc::new(PeerTracker::new()),
             local_peer_id: PeerId::random(),
+            required_proof_axes: required_proof_axes.clone(),
         };
 
         let handle = crate::test_utils::MockP2pHandle {
@@ -270,6 +320,7 @@ impl P2p {
             cmd_rx,
             header_sub_tx,
             peer_tracker_tx,
+            required_proof_axes,
         };
 
         (p2p, handle)
@@ -308,6 +359,11 @@ impl P2p {
         self.peer_tracker_info_watcher.borrow()
     }
 
+    /// Returns the observed performance of the `header-ex` requests to the connected peers.
+    pub fn header_ex_throughput(&self) -> Option<HeaderExThroughput> {
+        self.peer_tracker.header_ex_throughput()
+    }
+
     /// Initializes `header-sub` protocol with a given `subjective_head`.
     pub async fn init_header_sub(&self, head: ExtendedHeader) -> Result<()> {
         self.send_command(P2pCmd::InitHeaderSub {
@@ -344,6 +400,16 @@ impl P2p {
         Ok(rx.await?)
     }
 
+    /// Get the statistics of the `header-ex` requests rejected by this node.
+    pub async fn header_ex_server_stats(&self) -> Result<HeaderExServerStats> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::HeaderExServerStats { respond_to: tx })
+            .await?;
+
+        Ok(rx.await?)
+    }
+
     /// Send a request on the `header-ex` protocol.
     pub async fn header_ex_request(&self, request: HeaderRequest) -> Result<Vec<ExtendedHeader>> {
         let (tx, rx) = oneshot::channel();
@@ -362,6 +428,23 @@ impl P2p {
         self.get_header_by_height(0).await
     }
 
+    /// Request the head header from the trusted peers on the `header-ex` protocol.
+    ///
+    /// Returns the highest head reported by at least `quorum` peers, together with the peers
+    /// that disagreed with it. If no head was reported by the `quorum` of peers, the highest
+    /// one is returned.
+    pub async fn get_head_by_quorum(&self, quorum: usize) -> Result<HeadQuorumResponse> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::HeaderExHeadRequest {
+            quorum,
+            respond_to: tx,
+        })
+        .await?;
+
+        rx.await?
+    }
+
     /// Request the header by hash on the `header-ex` protocol.
     pub async fn get_header(&self, hash: Hash) -> Result<ExtendedHeader> {
         self.header_ex_request(HeaderRequest {
@@ -389,6 +472,8 @@ impl P2p {
     /// Request the headers following the one given with the `header-ex` protocol.
     ///
     /// First header from the requested range will be verified against the provided one, then each subsequent is verified against the previous one.
+    ///
+    /// Either all the `amount` of headers is returned, or an error if any of them can't be received.
     pub async fn get_verified_headers_range(
         &self,
         from: &ExtendedHeader,
@@ -398,7 +483,12 @@ impl P2p {
 
         let height = from.height().value() + 1;
 
-        let mut session = HeaderSession::new(height, amount, self.cmd_tx.clone())?;
+        let mut session = HeaderSession::new(
+            height,
+            amount,
+            self.cmd_tx.clone(),
+            self.header_ex_throughput(),
+        )?;
         let headers = session.run().await?;
 
         from.verify_adjacent_range(&headers)
@@ -407,6 +497,49 @@ impl P2p {
         Ok(headers)
     }
 
+    /// Request the header with the given hash and the headers following it, up to and
+    /// including the `to_height`, on the `header-ex` protocol.
+    ///
+    /// Each header following the one with `from_hash` is verified against the previous one.
+    pub async fn get_headers_by_hash_range(
+        &self,
+        from_hash: Hash,
+        to_height: u64,
+    ) -> Result<Vec<ExtendedHeader>> {
+        let from = self.get_header(from_hash).await?;
+        let from_height = from.height().value();
+
+        if to_height < from_height {
+            return Err(HeaderExError::InvalidRequest.into());
+        }
+
+        if to_height == from_height {
+            return Ok(vec![from]);
+        }
+
+        let range = self
+            .get_verified_headers_range(&from, to_height - from_height)
+            .await?;
+
+        Ok(iter::once(from).chain(range).collect())
+    }
+
+    /// Verify the `untrusted` header against the `trusted` one, skipping the headers between them.
+    ///
+    /// If the validator set changed too much between the two headers, the headers
+    /// in the middle of the range are requested on the `header-ex` protocol and
+    /// verified first, until a chain of verifiable headers is found.
+    ///
+    /// Returns the headers verified on the way, ending with the `untrusted` one.
+    pub async fn verify_by_bisection(
+        &self,
+        trusted: &ExtendedHeader,
+        untrusted: &ExtendedHeader,
+        options: &VerificationOptions,
+    ) -> Result<Vec<ExtendedHeader>> {
+        bisection::verify(self, trusted, untrusted, options).await
+    }
+
     /// Request a [`Cid`] on bitswap protocol.
     async fn get_shwap_cid(&self, cid: Cid, timeout: Option<Duration>) -> Result<Vec<u8>> {
         let (tx, rx) = oneshot::channel();
@@ -435,6 +568,17 @@ impl P2p {
         Ok(Row::decode(&data[..])?)
     }
 
+    /// Request a [`Column`] on bitswap protocol.
+    ///
+    /// Columns aren't part of the celestia-node shwap protocol, so they can be received
+    /// only from the Lumina peers. This method awaits for a verified `Column` until
+    /// timeout of 30 seconds is reached.
+    pub async fn get_column(&self, column_index: u16, block_height: u64) -> Result<Column> {
+        let cid = column_cid(column_index, block_height)?;
+        let data = self.get_shwap_cid(cid, Some(GET_COLUMN_TIMEOUT)).await?;
+        Ok(Column::decode(&data[..])?)
+    }
+
     /// Request a [`Sample`] on bitswap protocol.
     ///
     /// This method awaits for a verified `Sample` until timeout of 10 second
@@ -447,10 +591,54 @@ impl P2p {
         block_height: u64,
     ) -> Result<Sample> {
         let cid = sample_cid(row_index, column_index, block_height)?;
+        let _any_axis = self.required_proof_axes.require(cid, None);
         let data = self.get_shwap_cid(cid, Some(GET_SAMPLE_TIMEOUT)).await?;
         Ok(Sample::decode(&data[..])?)
     }
 
+    /// Request a [`Sample`] proven along the `proof_axis` on bitswap protocol.
+    ///
+    /// Serving peer chooses the axis of the proof and Go nodes prove samples only
+    /// along the rows. Because of that, as soon as a peer serves the sample proven along
+    /// the other axis, the sample is requested again accepting any axis. The sample is
+    /// also accepted if another request for it accepts its axis. [`Sample::proof_type`]
+    /// tells the axis the sample was proven along.
+    ///
+    /// Like [`get_sample`], this method awaits for a verified `Sample` until timeout
+    /// of 10 second is reached.
+    ///
+    /// [`get_sample`]: P2p::get_sample
+    pub async fn get_sample_with_proof_axis(
+        &self,
+        row_index: u16,
+        column_index: u16,
+        proof_axis: AxisType,
+        block_height: u64,
+    ) -> Result<Sample> {
+        let cid = sample_cid(row_index, column_index, block_height)?;
+
+        let get_sample = async {
+            let required_axis = self.required_proof_axes.require(cid, Some(proof_axis));
+
+            select! {
+                res = self.get_shwap_cid(cid, None) => return res,
+                _ = required_axis.mismatched() => {}
+            }
+
+            debug!("Sample {cid} not served along {proof_axis:?}, accepting any axis");
+            let _any_axis = self.required_proof_axes.require(cid, None);
+            drop(required_axis);
+
+            self.get_shwap_cid(cid, None).await
+        };
+
+        let data = executor::timeout(GET_SAMPLE_TIMEOUT, get_sample)
+            .await
+            .map_err(|_| P2pError::BitswapQueryTimeout)??;
+
+        Ok(Sample::decode(&data[..])?)
+    }
+
     /// Request a [`NamespacedData`] on bitswap protocol.
     pub async fn get_namespaced_data(
         &self,
@@ -464,6 +652,33 @@ impl P2p {
         Ok(NamespacedData::decode(&data[..])?)
     }
 
+    /// Request [`NamespacedData`] of all the rows which may contain the `namespace` on bitswap
+    /// protocol.
+    ///
+    /// Rows are requested concurrently and verified together against the `dah`, so that
+    /// no row is missing.
+    pub async fn get_namespaced_rows(
+        &self,
+        namespace: Namespace,
+        dah: &DataAvailabilityHeader,
+        block_height: u64,
+    ) -> Result<NamespacedRows> {
+        let requests = dah
+            .row_indexes_for_namespace(namespace)
+            .into_iter()
+            .map(|row_index| self.get_namespaced_data(namespace, row_index, block_height));
+
+        let rows = NamespacedRows {
+            namespace,
+            block_height,
+            rows: futures::future::try_join_all(requests).await?,
+        };
+
+        rows.verify(dah).map_err(P2pError::ShwapVerification)?;
+
+        Ok(rows)
+    }
+
     /// Get the addresses where [`P2p`] listens on for incoming connections.
     pub async fn listeners(&self) -> Result<Vec<Multiaddr>> {
         let (tx, rx) = oneshot::channel();
@@ -536,6 +751,7 @@ where
     header_sub_watcher: watch::Sender<Option<ExtendedHeader>>,
     bitswap_queries: HashMap<beetswap::QueryId, OneshotResultSender<Vec<u8>, P2pError>>,
     network_compromised_token: CancellationToken,
+    fork_detector: ForkDetector,
     store: Arc<S>,
 }
 
@@ -549,6 +765,7 @@ where
         cmd_rx: mpsc::Receiver<P2pCmd>,
         header_sub_watcher: watch::Sender<Option<ExtendedHeader>>,
         peer_tracker: Arc<PeerTracker>,
+        required_proof_axes: RequiredProofAxes,
     ) -> Result<Self, P2pError> {
         let local_peer_id = PeerId::from(args.local_keypair.public());
 
@@ -560,16 +777,25 @@ where
             args.local_keypair.public(),
         ));
 
-        let header_sub_topic = gossipsub_ident_topic(&args.network_id, "/header-sub/v0.0.1");
+        let header_sub_topic = gossipsub_ident_topic(
+            &args.network_id,
+            &format!("/header-sub/{}", args.protocol_versions.header_sub),
+        );
         let bad_encoding_fraud_sub_topic =
             fraudsub_ident_topic(BadEncodingFraudProof::TYPE, &args.network_id);
         let gossipsub = init_gossipsub(&args, [&header_sub_topic, &bad_encoding_fraud_sub_topic])?;
 
         let kademlia = init_kademlia(&args)?;
-        let bitswap = init_bitswap(args.blockstore, args.store.clone(), &args.network_id)?;
+        let bitswap = init_bitswap(
+            args.blockstore,
+            args.store.clone(),
+            required_proof_axes,
+            &args.network_id,
+        )?;
 
         let header_ex = HeaderExBehaviour::new(HeaderExConfig {
             network_id: &args.network_id,
+            protocol_version: &args.protocol_versions.header_ex,
             peer_tracker: peer_tracker.clone(),
             header_store: args.store.clone(),
         });
@@ -607,6 +833,7 @@ where
             header_sub_watcher,
             bitswap_queries: HashMap::new(),
             network_compromised_token: CancellationToken::new(),
+            fork_detector: ForkDetector::new(),
             store: args.store,
         })
     }
@@ -672,9 +899,8 @@ where
                 BehaviourEvent::Gossipsub(ev) => self.on_gossip_sub_event(ev).await,
                 BehaviourEvent::Kademlia(ev) => self.on_kademlia_event(ev).await?,
                 BehaviourEvent::Bitswap(ev) => self.on_bitswap_event(ev).await,
-                BehaviourEvent::Autonat(_)
-                | BehaviourEvent::Ping(_)
-                | BehaviourEvent::HeaderEx(_) => {}
+                BehaviourEvent::HeaderEx(ev) => self.on_header_ex_event(ev).await,
+                BehaviourEvent::Autonat(_) | BehaviourEvent::Ping(_) => {}
             },
             SwarmEvent::ConnectionEstablished {
                 peer_id,
@@ -702,6 +928,9 @@ where
             P2pCmd::NetworkInfo { respond_to } => {
                 respond_to.maybe_send(self.swarm.network_info());
             }
+            P2pCmd::HeaderExServerStats { respond_to } => {
+                respond_to.maybe_send(self.swarm.behaviour().header_ex.server_stats());
+            }
             P2pCmd::HeaderExRequest {
                 request,
                 respond_to,
@@ -711,6 +940,12 @@ where
                     .header_ex
                     .send_request(request, respond_to);
             }
+            P2pCmd::HeaderExHeadRequest { quorum, respond_to } => {
+                self.swarm
+                    .behaviour_mut()
+                    .header_ex
+                    .send_head_request(quorum, respond_to);
+            }
             P2pCmd::Listeners { respond_to } => {
                 let local_peer_id = self.swarm.local_peer_id().to_owned();
                 let listeners = self
@@ -795,7 +1030,7 @@ where
                 };
 
                 let acceptance = if message.topic == self.header_sub_topic_hash {
-                    self.on_header_sub_message(&message.data[..]).await
+                    self.on_header_sub_message(&message.data[..], &peer).await
                 } else if message.topic == self.bad_encoding_fraud_sub_topic {
                     self.on_bad_encoding_fraud_sub_message(&message.data[..], &peer)
                         .await
@@ -836,6 +1071,11 @@ where
     #[instrument(level = "trace", skip_all)]
     fn on_get_shwap_cid(&mut self, cid: Cid, respond_to: OneshotResultSender<Vec<u8>, P2pError>) {
         trace!("Requesting CID {cid} from bitswap");
+
+        // Cancel the abandoned queries first, so that a CID requested again
+        // is wanted from the peers anew.
+        self.prune_canceled_bitswap_queries();
+
         let query_id = self.swarm.behaviour_mut().bitswap.get(&cid);
         self.bitswap_queries.insert(query_id, respond_to);
     }
@@ -909,7 +1149,126 @@ where
     }
 
     #[instrument(skip_all)]
-    async fn on_header_sub_message(&mut self, data: &[u8]) -> gossipsub::MessageAcceptance {
+    async fn on_header_ex_event(&mut self, ev: HeaderExEvent) {
+        match ev {
+            HeaderExEvent::HeadersReceived { peer, headers } => {
+                let store_head_height = self.store.head_height().await.unwrap_or(0);
+
+                for header in &headers {
+                    if self.check_for_fork(header, peer, store_head_height).await {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compares the validated `header` received from `peer` with the known ones.
+    ///
+    /// Returns `true` if the header conflicts with any of them.
+    async fn check_for_fork(
+        &mut self,
+        header: &ExtendedHeader,
+        peer: PeerId,
+        store_head_height: u64,
+    ) -> bool {
+        let height = header.height().value();
+
+        let stored = if height <= store_head_height {
+            self.store.get_by_height(height).await.ok()
+        } else {
+            None
+        };
+
+        let Some(conflict) = self.fork_detector.check(header, peer, stored.as_ref()) else {
+            return false;
+        };
+
+        match conflict {
+            Conflict::Stored => {
+                let stored = stored.expect("conflicts only with a stored header");
+
+                self.on_fork_detected(stored.hash(), header, &[peer]);
+
+                let evidence = ForkEvidence {
+                    header: stored,
+                    peer: None,
+                    conflicting_header: header.to_owned(),
+                    conflicting_peer: Some(peer),
+                };
+                insert_fork_evidence(&*self.store, evidence).await;
+            }
+            Conflict::Received {
+                hash,
+                peer: known_peer,
+            } => {
+                // Only the hash of the header received before is kept, so it's requested back
+                // from its sender to complete the evidence
+                let (tx, rx) = oneshot::channel();
+                self.swarm.behaviour_mut().header_ex.send_request_to(
+                    known_peer,
+                    HeaderRequest::with_hash(hash),
+                    tx,
+                );
+
+                self.on_fork_detected(hash, header, &[known_peer, peer]);
+
+                let store = self.store.clone();
+                let conflicting_header = header.to_owned();
+
+                spawn(async move {
+                    let known_header = match rx.await {
+                        Ok(Ok(headers)) => headers.into_iter().next(),
+                        _ => None,
+                    };
+
+                    let Some(known_header) = known_header else {
+                        warn!("Failed to receive header {hash} of the fork from {known_peer}");
+                        return;
+                    };
+
+                    let evidence = ForkEvidence {
+                        header: known_header,
+                        peer: Some(known_peer),
+                        conflicting_header,
+                        conflicting_peer: Some(peer),
+                    };
+                    insert_fork_evidence(&*store, evidence).await;
+                });
+            }
+        }
+
+        true
+    }
+
+    /// Blocks the `peers` that provided the conflicting headers and stops all the services.
+    #[instrument(skip_all, fields(height = %conflicting_header.height()))]
+    fn on_fork_detected(
+        &mut self,
+        hash: Hash,
+        conflicting_header: &ExtendedHeader,
+        peers: &[PeerId],
+    ) {
+        warn!(
+            "Received conflicting headers {hash} and {} committed by the same validators",
+            conflicting_header.hash()
+        );
+
+        for peer in peers {
+            self.peer_tracker.penalize(*peer, FORK_PENALTY_DURATION);
+            self.swarm.behaviour_mut().gossipsub.blacklist_peer(peer);
+        }
+
+        // trigger cancellation for all services
+        self.network_compromised_token.cancel();
+    }
+
+    #[instrument(skip_all)]
+    async fn on_header_sub_message(
+        &mut self,
+        data: &[u8],
+        peer: &PeerId,
+    ) -> gossipsub::MessageAcceptance {
         let Ok(header) = ExtendedHeader::decode_and_validate(data) else {
             trace!("Malformed or invalid header from header-sub");
             return gossipsub::MessageAcceptance::Reject;
@@ -917,6 +1276,12 @@ where
 
         trace!("Received header from header-sub ({header})");
 
+        let store_head_height = self.store.head_height().await.unwrap_or(0);
+
+        if self.check_for_fork(&header, *peer, store_head_height).await {
+            return gossipsub::MessageAcceptance::Reject;
+        }
+
         let updated = self.header_sub_watcher.send_if_modified(move |state| {
             let Some(known_header) = state else {
                 debug!("HeaderSub not initialized yet");
@@ -990,6 +1355,16 @@ where
     }
 }
 
+/// Stores the evidence of a detected fork, logging the failure.
+async fn insert_fork_evidence<S>(store: &S, evidence: ForkEvidence)
+where
+    S: Store,
+{
+    if let Err(e) = store.insert_fork_evidence(evidence).await {
+        warn!("Failed to store the fork evidence: {e}");
+    }
+}
+
 /// Awaits at least one channel from the `bitswap_queries` to close.
 async fn poll_closed(
     bitswap_queries: &mut HashMap<beetswap::QueryId, OneshotResultSender<Vec<u8>, P2pError>>,
@@ -1084,6 +1459,7 @@ where
 fn init_bitswap<B, S>(
     blockstore: B,
     store: Arc<S>,
+    required_proof_axes: RequiredProofAxes,
     network_id: &str,
 ) -> Result<beetswap::Behaviour<MAX_MH_SIZE, B>>
 where
@@ -1094,7 +1470,7 @@ where
 
     Ok(beetswap::Behaviour::builder(blockstore)
         .protocol_prefix(&protocol_prefix)?
-        .register_multihasher(ShwapMultihasher::new(store))
+        .register_multihasher(ShwapMultihasher::new(store, required_proof_axes))
         .client_set_send_dont_have(false)
         .build())
 }
@@ -1102,3 +1478,98 @@ where
 fn network_head_height(watcher: &watch::Sender<Option<ExtendedHeader>>) -> Option<Height> {
     watcher.borrow().as_ref().map(|header| header.height())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::spawn;
+    use crate::test_utils::async_test;
+    use celestia_types::test_utils::{generate_eds, ExtendedHeaderGenerator};
+
+    #[async_test]
+    async fn get_headers_by_hash_range() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let from_hash = headers[2].hash();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = p2p.get_headers_by_hash_range(from_hash, 8).await;
+            result_tx.send(res).unwrap();
+        });
+
+        let (hash, respond_to) = p2p_mock.expect_header_request_for_hash_cmd().await;
+        assert_eq!(hash, from_hash);
+        respond_to.send(Ok(vec![headers[2].clone()])).unwrap();
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 4);
+        assert_eq!(amount, 5);
+        respond_to.send(Ok(headers[3..8].to_vec())).unwrap();
+
+        p2p_mock.expect_no_cmd().await;
+
+        let received = result_rx.await.unwrap().unwrap();
+        assert_eq!(received, headers[2..8]);
+    }
+
+    #[async_test]
+    async fn get_sample_with_proof_axis_falls_back_to_any_axis() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let eds = generate_eds(4);
+        let sample = Sample::new(1, 2, AxisType::Row, &eds, 1).unwrap();
+        let sample_bytes = sample.encode_vec().unwrap();
+
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = p2p.get_sample_with_proof_axis(1, 2, AxisType::Col, 1).await;
+            result_tx.send(res).unwrap();
+        });
+
+        let (cid, _respond_to) = p2p_mock.expect_get_shwap_cid().await;
+        assert_eq!(p2p_mock.required_proof_axis(&cid), Some(AxisType::Col));
+
+        // A peer served the sample proven along the row
+        assert!(!p2p_mock.required_proof_axes.accepts(&cid, AxisType::Row));
+
+        let (fallback_cid, respond_to) = p2p_mock.expect_get_shwap_cid().await;
+        assert_eq!(fallback_cid, cid);
+        assert_eq!(p2p_mock.required_proof_axis(&cid), None);
+        respond_to.send(Ok(sample_bytes)).unwrap();
+
+        p2p_mock.expect_no_cmd().await;
+
+        let received = result_rx.await.unwrap().unwrap();
+        assert_eq!(received.proof_type, AxisType::Row);
+    }
+
+    #[async_test]
+    async fn get_headers_by_hash_range_single_header() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let header = gen.next_many(3).pop().unwrap();
+
+        let from_hash = header.hash();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let single = p2p.get_headers_by_hash_range(from_hash, 3).await;
+            let reversed = p2p.get_headers_by_hash_range(from_hash, 2).await;
+            result_tx.send((single, reversed)).unwrap();
+        });
+
+        for _ in 0..2 {
+            let (_, respond_to) = p2p_mock.expect_header_request_for_hash_cmd().await;
+            respond_to.send(Ok(vec![header.clone()])).unwrap();
+        }
+
+        p2p_mock.expect_no_cmd().await;
+
+        let (single, reversed) = result_rx.await.unwrap();
+        assert_eq!(single.unwrap(), [header]);
+        assert!(matches!(
+            reversed.unwrap_err(),
+            P2pError::HeaderEx(HeaderExError::InvalidRequest)
+        ));
+    }
+}
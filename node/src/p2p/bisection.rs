@@ -0,0 +1,168 @@
+//! Skipping verification of the headers, in the style of the Tendermint light client.
+//!
+//! The untrusted header is verified directly against the trusted one if enough of the trusted
+//! validators signed it. Otherwise the header in the middle of the range is requested and the
+//! verification is retried for both halves, until each step can be verified.
+
+use celestia_types::{ExtendedHeader, VerificationError, VerificationOptions};
+use tracing::debug;
+
+use crate::p2p::{P2p, P2pError};
+
+/// Maximum number of headers requested while verifying a single header.
+const MAX_BISECTION_STEPS: usize = 64;
+
+type Result<T, E = P2pError> = std::result::Result<T, E>;
+
+pub(crate) async fn verify(
+    p2p: &P2p,
+    trusted: &ExtendedHeader,
+    untrusted: &ExtendedHeader,
+    options: &VerificationOptions,
+) -> Result<Vec<ExtendedHeader>> {
+    let mut trusted = trusted.to_owned();
+    let mut verified = Vec::new();
+    // Headers waiting for verification, the lowest one is verified first
+    let mut pending = vec![untrusted.to_owned()];
+    let mut steps = 0;
+
+    while let Some(untrusted) = pending.last() {
+        match trusted.verify_with_options(untrusted, options) {
+            Ok(()) => {
+                trusted = pending.pop().expect("header is pending");
+                verified.push(trusted.clone());
+            }
+            Err(celestia_types::Error::Verification(
+                e @ VerificationError::NotEnoughVotingPower(..),
+            )) => {
+                let low = trusted.height().value();
+                let high = untrusted.height().value();
+
+                // Adjacent headers don't need the voting power of the trusted validators
+                debug_assert!(high - low > 1);
+
+                if steps == MAX_BISECTION_STEPS {
+                    return Err(P2pError::HeaderVerification(e.into()));
+                }
+
+                let pivot_height = low + (high - low) / 2;
+                debug!("Verifying {high} from {low} failed, bisecting at {pivot_height}");
+
+                let pivot = p2p.get_header_by_height(pivot_height).await?;
+                pending.push(pivot);
+                steps += 1;
+            }
+            Err(e) => return Err(P2pError::HeaderVerification(e)),
+        }
+    }
+
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::spawn;
+    use crate::test_utils::async_test;
+    use celestia_types::test_utils::{unverify, ExtendedHeaderGenerator};
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    #[async_test]
+    async fn verify_without_bisection() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(100);
+
+        let verified = verify(
+            &p2p,
+            &headers[0],
+            &headers[99],
+            &VerificationOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(verified, [headers[99].clone()]);
+
+        p2p_mock.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn verify_with_changed_validator_set() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        // Validator set changes after height 30
+        let mut headers = gen.next_many(29);
+        gen.change_validator_set();
+        headers.extend(gen.next_many(71));
+
+        let trusted = headers[0].clone();
+        let untrusted = headers[99].clone();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = verify(&p2p, &trusted, &untrusted, &VerificationOptions::default()).await;
+            result_tx.send(res).unwrap();
+        });
+
+        // Bisect until the header announcing the new validator set is reached
+        for expected_height in [50, 25, 37, 31, 28, 29, 30] {
+            let (height, amount, respond_to) =
+                p2p_mock.expect_header_request_for_height_cmd().await;
+            assert_eq!(height, expected_height);
+            assert_eq!(amount, 1);
+            respond_to
+                .send(Ok(vec![headers[height as usize - 1].clone()]))
+                .unwrap();
+        }
+
+        p2p_mock.expect_no_cmd().await;
+
+        let verified = result_rx.await.unwrap().unwrap();
+        let verified_heights: Vec<_> = verified.iter().map(|h| h.height().value()).collect();
+        assert_eq!(verified_heights, [25, 28, 29, 30, 31, 37, 50, 100]);
+    }
+
+    #[async_test]
+    async fn verify_fails_on_invalid_signature() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let mut untrusted = headers[9].clone();
+        unverify(&mut untrusted);
+
+        // Invalid signatures are not a reason for bisection
+        let res = verify(
+            &p2p,
+            &headers[0],
+            &untrusted,
+            &VerificationOptions::default(),
+        )
+        .await;
+        p2p_mock.expect_no_cmd().await;
+
+        assert!(matches!(res, Err(P2pError::HeaderVerification(_))));
+    }
+
+    #[async_test]
+    async fn verify_fails_on_expired_trusted_header() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let options = VerificationOptions {
+            trusting_period: Some(Duration::ZERO),
+            ..Default::default()
+        };
+
+        let res = verify(&p2p, &headers[0], &headers[9], &options).await;
+        p2p_mock.expect_no_cmd().await;
+
+        assert!(matches!(
+            res,
+            Err(P2pError::HeaderVerification(
+                celestia_types::Error::Verification(VerificationError::TrustedHeaderExpired(1))
+            ))
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+//! Detection of the conflicting headers announced in the network.
+//!
+//! Every validated header received from the peers is compared with the stored one and
+//! with the one received before at the same height. Two different headers of the same chain
+//! committed by the same validator set mean that the validators equivocated and the chain
+//! forked.
+
+use std::collections::BTreeMap;
+
+use celestia_types::hash::Hash;
+use celestia_types::ExtendedHeader;
+use libp2p::PeerId;
+
+/// Maximum number of the recently received headers kept for the comparison.
+const MAX_TRACKED_HEADERS: usize = 1024;
+
+#[derive(Debug, Default)]
+pub(crate) struct ForkDetector {
+    /// The first header received at each of the recent heights
+    headers: BTreeMap<u64, TrackedHeader>,
+}
+
+/// The part of a received header needed to detect a conflict with it.
+#[derive(Debug)]
+struct TrackedHeader {
+    hash: Hash,
+    chain_id: String,
+    validators_hash: Hash,
+    peer: PeerId,
+}
+
+/// A known header that conflicts with the checked one.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Conflict {
+    /// The header in the store.
+    Stored,
+    /// The header received before from the `peer`, of which only the `hash` is kept.
+    Received { hash: Hash, peer: PeerId },
+}
+
+impl ForkDetector {
+    pub(crate) fn new() -> Self {
+        ForkDetector::default()
+    }
+
+    /// Checks the validated `header` received from `peer` against the `stored` header of
+    /// the same height and the one received before it.
+    ///
+    /// The header is tracked if it's the first one received at its height.
+    pub(crate) fn check(
+        &mut self,
+        header: &ExtendedHeader,
+        peer: PeerId,
+        stored: Option<&ExtendedHeader>,
+    ) -> Option<Conflict> {
+        let height = header.height().value();
+        let tracked = TrackedHeader::new(header, peer);
+
+        if let Some(stored) = stored {
+            if conflicts(&TrackedHeader::new(stored, peer), &tracked) {
+                return Some(Conflict::Stored);
+            }
+        }
+
+        if let Some(known) = self.headers.get(&height) {
+            if conflicts(known, &tracked) {
+                return Some(Conflict::Received {
+                    hash: known.hash,
+                    peer: known.peer,
+                });
+            }
+
+            return None;
+        }
+
+        // Don't let the old headers evict the recent ones
+        if self.headers.len() >= MAX_TRACKED_HEADERS
+            && self
+                .headers
+                .first_key_value()
+                .is_some_and(|(lowest, _)| *lowest > height)
+        {
+            return None;
+        }
+
+        self.headers.insert(height, tracked);
+
+        if self.headers.len() > MAX_TRACKED_HEADERS {
+            self.headers.pop_first();
+        }
+
+        None
+    }
+}
+
+impl TrackedHeader {
+    fn new(header: &ExtendedHeader, peer: PeerId) -> Self {
+        TrackedHeader {
+            hash: header.hash(),
+            chain_id: header.chain_id().to_string(),
+            validators_hash: header.header.validators_hash,
+            peer,
+        }
+    }
+}
+
+/// Returns `true` if both validated headers of the same height were committed by the same
+/// validators for the same chain, but they are different.
+fn conflicts(known: &TrackedHeader, header: &TrackedHeader) -> bool {
+    known.hash != header.hash
+        && known.chain_id == header.chain_id
+        && known.validators_hash == header.validators_hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+
+    #[test]
+    fn detects_conflicting_peers() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut fork_gen = gen.fork();
+        let header = gen.next();
+        let conflicting_header = fork_gen.next();
+        let peer = PeerId::random();
+        let conflicting_peer = PeerId::random();
+
+        let mut detector = ForkDetector::new();
+
+        assert!(detector.check(&header, peer, None).is_none());
+        // Same header from a different peer
+        assert!(detector.check(&header, conflicting_peer, None).is_none());
+
+        let conflict = detector
+            .check(&conflicting_header, conflicting_peer, None)
+            .unwrap();
+        assert_eq!(
+            conflict,
+            Conflict::Received {
+                hash: header.hash(),
+                peer,
+            }
+        );
+    }
+
+    #[test]
+    fn detects_conflict_with_store() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut fork_gen = gen.fork();
+        let stored = gen.next();
+        let conflicting_header = fork_gen.next();
+        let peer = PeerId::random();
+
+        let mut detector = ForkDetector::new();
+
+        assert!(detector.check(&stored, peer, Some(&stored)).is_none());
+
+        let conflict = detector
+            .check(&conflicting_header, peer, Some(&stored))
+            .unwrap();
+        assert_eq!(conflict, Conflict::Stored);
+    }
+
+    #[test]
+    fn ignores_different_chains() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut fork_gen = gen.fork();
+        let header = gen.next();
+        let mut other_chain_header = fork_gen.next();
+        other_chain_header.header.chain_id = "other-chain".parse().unwrap();
+
+        let mut detector = ForkDetector::new();
+
+        assert!(detector.check(&header, PeerId::random(), None).is_none());
+        assert!(detector
+            .check(&other_chain_header, PeerId::random(), None)
+            .is_none());
+    }
+
+    #[test]
+    fn ignores_different_validator_sets() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut other_gen = ExtendedHeaderGenerator::new();
+        let header = gen.next();
+        let other_header = other_gen.next();
+
+        let mut detector = ForkDetector::new();
+
+        assert!(detector.check(&header, PeerId::random(), None).is_none());
+        assert!(detector
+            .check(&other_header, PeerId::random(), None)
+            .is_none());
+    }
+
+    #[test]
+    fn tracks_recent_heights_only() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut fork_gen = gen.fork();
+        let headers = gen.next_many(MAX_TRACKED_HEADERS as u64 + 1);
+        let conflicting_headers = fork_gen.next_many(MAX_TRACKED_HEADERS as u64 + 1);
+        let peer = PeerId::random();
+
+        let mut detector = ForkDetector::new();
+
+        for header in &headers {
+            assert!(detector.check(header, peer, None).is_none());
+        }
+
+        // The lowest height was evicted
+        assert!(detector
+            .check(&conflicting_headers[0], peer, None)
+            .is_none());
+        assert!(detector
+            .check(&conflicting_headers[1], peer, None)
+            .is_some());
+    }
+}
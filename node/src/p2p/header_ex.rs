@@ -11,9 +11,9 @@ use libp2p::{
     core::Endpoint,
     request_response::{self, Codec, InboundFailure, OutboundFailure, ProtocolSupport},
     swarm::{
-        handler::ConnectionEvent, ConnectionDenied, ConnectionHandler, ConnectionHandlerEvent,
-        ConnectionId, FromSwarm, NetworkBehaviour, SubstreamProtocol, THandlerInEvent,
-        THandlerOutEvent, ToSwarm,
+        handler::ConnectionEvent, CloseConnection, ConnectionDenied, ConnectionHandler,
+        ConnectionHandlerEvent, ConnectionId, FromSwarm, NetworkBehaviour, SubstreamProtocol,
+        THandlerInEvent, THandlerOutEvent, ToSwarm,
     },
     Multiaddr, PeerId, StreamProtocol,
 };
@@ -26,7 +26,9 @@ pub(crate) mod utils;
 
 use crate::executor::timeout;
 use crate::p2p::header_ex::client::HeaderExClientHandler;
+pub use crate::p2p::header_ex::client::{HeadDisagreement, HeadQuorumResponse};
 use crate::p2p::header_ex::server::HeaderExServerHandler;
+pub use crate::p2p::header_ex::server::HeaderExServerStats;
 use crate::p2p::P2pError;
 use crate::peer_tracker::PeerTracker;
 use crate::store::Store;
@@ -57,14 +59,31 @@ where
     req_resp: ReqRespBehaviour,
     client_handler: HeaderExClientHandler,
     server_handler: HeaderExServerHandler<S>,
+    peer_tracker: Arc<PeerTracker>,
 }
 
 pub(crate) struct HeaderExConfig<'a, S> {
     pub network_id: &'a str,
+    pub protocol_version: &'a str,
     pub peer_tracker: Arc<PeerTracker>,
     pub header_store: Arc<S>,
 }
 
+/// Events emitted by the [`HeaderExBehaviour`].
+#[derive(Debug)]
+pub(crate) enum HeaderExEvent {
+    /// Validated headers were received from a peer.
+    HeadersReceived {
+        peer: PeerId,
+        headers: Vec<ExtendedHeader>,
+    },
+}
+
+/// Reason of denying the connection of a penalized peer.
+#[derive(Debug, thiserror::Error)]
+#[error("Peer {0} is blocked")]
+struct PeerBlocked(PeerId);
+
 /// Representation of all the errors that can occur when interacting with the header-ex.
 #[derive(Debug, thiserror::Error)]
 pub enum HeaderExError {
@@ -97,13 +116,33 @@ where
         HeaderExBehaviour {
             req_resp: ReqRespBehaviour::new(
                 [(
-                    protocol_id(config.network_id, "/header-ex/v0.0.3"),
+                    protocol_id(
+                        config.network_id,
+                        &format!("/header-ex/{}", config.protocol_version),
+                    ),
                     ProtocolSupport::Full,
                 )],
                 request_response::Config::default(),
             ),
-            client_handler: HeaderExClientHandler::new(config.peer_tracker),
-            server_handler: HeaderExServerHandler::new(config.header_store),
+            client_handler: HeaderExClientHandler::new(config.peer_tracker.clone()),
+            server_handler: HeaderExServerHandler::new(
+                config.header_store,
+                config.peer_tracker.clone(),
+            ),
+            peer_tracker: config.peer_tracker,
+        }
+    }
+
+    /// Returns the statistics of the requests rejected by the server.
+    pub(crate) fn server_stats(&self) -> HeaderExServerStats {
+        self.server_handler.stats()
+    }
+
+    fn deny_blocked(&self, peer: PeerId) -> Result<(), ConnectionDenied> {
+        if self.peer_tracker.is_blocked(peer) {
+            Err(ConnectionDenied::new(PeerBlocked(peer)))
+        } else {
+            Ok(())
         }
     }
 
@@ -117,16 +156,65 @@ where
             .on_send_request(&mut self.req_resp, request, respond_to);
     }
 
+    /// Sends the `request` to the given `peer`, instead of choosing the best one.
+    #[instrument(level = "trace", skip(self, respond_to))]
+    pub(crate) fn send_request_to(
+        &mut self,
+        peer: PeerId,
+        request: HeaderRequest,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
+        self.client_handler
+            .on_send_request_to(&mut self.req_resp, peer, request, respond_to);
+    }
+
+    #[instrument(level = "trace", skip(self, respond_to))]
+    pub(crate) fn send_head_request(
+        &mut self,
+        quorum: usize,
+        respond_to: OneshotResultSender<HeadQuorumResponse, P2pError>,
+    ) {
+        self.client_handler
+            .on_send_head_request(&mut self.req_resp, quorum, respond_to);
+    }
+
     fn on_to_swarm(
         &mut self,
         ev: ToSwarm<ReqRespEvent, THandlerInEvent<ReqRespBehaviour>>,
-    ) -> Option<ToSwarm<(), THandlerInEvent<Self>>> {
+    ) -> Option<ToSwarm<HeaderExEvent, THandlerInEvent<Self>>> {
         match ev {
             ToSwarm::GenerateEvent(ev) => {
                 self.on_req_resp_event(ev);
                 None
             }
-            _ => Some(ev.map_out(|_| ())),
+            ToSwarm::Dial { opts } => Some(ToSwarm::Dial { opts }),
+            ToSwarm::ListenOn { opts } => Some(ToSwarm::ListenOn { opts }),
+            ToSwarm::RemoveListener { id } => Some(ToSwarm::RemoveListener { id }),
+            ToSwarm::NotifyHandler {
+                peer_id,
+                handler,
+                event,
+            } => Some(ToSwarm::NotifyHandler {
+                peer_id,
+                handler,
+                event,
+            }),
+            ToSwarm::NewExternalAddrCandidate(addr) => {
+                Some(ToSwarm::NewExternalAddrCandidate(addr))
+            }
+            ToSwarm::ExternalAddrConfirmed(addr) => Some(ToSwarm::ExternalAddrConfirmed(addr)),
+            ToSwarm::ExternalAddrExpired(addr) => Some(ToSwarm::ExternalAddrExpired(addr)),
+            ToSwarm::CloseConnection {
+                peer_id,
+                connection,
+            } => Some(ToSwarm::CloseConnection {
+                peer_id,
+                connection,
+            }),
+            _ => {
+                warn!("Dropping an unsupported request-response event");
+                None
+            }
         }
     }
 
@@ -191,7 +279,7 @@ where
     S: Store + 'static,
 {
     type ConnectionHandler = ConnHandler;
-    type ToSwarm = ();
+    type ToSwarm = HeaderExEvent;
 
     fn handle_established_inbound_connection(
         &mut self,
@@ -200,6 +288,7 @@ where
         local_addr: &Multiaddr,
         remote_addr: &Multiaddr,
     ) -> Result<Self::ConnectionHandler, ConnectionDenied> {
+        self.deny_blocked(peer)?;
         self.req_resp
             .handle_established_inbound_connection(connection_id, peer, local_addr, remote_addr)
             .map(ConnHandler)
@@ -212,6 +301,7 @@ where
         addr: &Multiaddr,
         role_override: Endpoint,
     ) -> Result<Self::ConnectionHandler, ConnectionDenied> {
+        self.deny_blocked(peer)?;
         self.req_resp
             .handle_established_outbound_connection(connection_id, peer, addr, role_override)
             .map(ConnHandler)
@@ -269,14 +359,24 @@ where
                 continue;
             }
 
-            if self.client_handler.poll(cx).is_ready() {
-                continue;
+            if let Poll::Ready((peer, headers)) = self.client_handler.poll(cx) {
+                return Poll::Ready(ToSwarm::GenerateEvent(HeaderExEvent::HeadersReceived {
+                    peer,
+                    headers,
+                }));
             }
 
             if self.server_handler.poll(cx, &mut self.req_resp).is_ready() {
                 continue;
             }
 
+            if let Some(peer_id) = self.server_handler.next_penalized_peer() {
+                return Poll::Ready(ToSwarm::CloseConnection {
+                    peer_id,
+                    connection: CloseConnection::All,
+                });
+            }
+
             return Poll::Pending;
         }
     }
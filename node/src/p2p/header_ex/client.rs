@@ -9,19 +9,49 @@ use celestia_proto::p2p::pb::header_request::Data;
 use celestia_proto::p2p::pb::{HeaderRequest, HeaderResponse};
 use celestia_types::ExtendedHeader;
 use futures::future::join_all;
+use instant::Instant;
 use libp2p::request_response::{OutboundFailure, OutboundRequestId};
 use libp2p::PeerId;
-use tokio::sync::oneshot;
-use tracing::{debug, instrument, trace};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, instrument, trace, warn};
 
 use crate::executor::{spawn, yield_now};
 use crate::p2p::header_ex::utils::{HeaderRequestExt, HeaderResponseExt};
 use crate::p2p::header_ex::{HeaderExError, ReqRespBehaviour};
 use crate::p2p::P2pError;
 use crate::peer_tracker::PeerTracker;
-use crate::utils::{OneshotResultSender, OneshotResultSenderExt, VALIDATIONS_PER_YIELD};
+use crate::utils::{
+    OneshotResultSender, OneshotResultSenderExt, OneshotSenderExt, VALIDATIONS_PER_YIELD,
+};
 
 const MAX_PEERS: usize = 10;
+/// Number of the received responses buffered for the fork detection.
+const MAX_BUFFERED_RECEIVED: usize = 32;
+/// Number of peers that need to agree on the HEAD requested with [`HeaderRequest::head_request`].
+const MIN_HEAD_RESPONSES: usize = 2;
+
+/// HEAD reported by the trusted peers, chosen by the quorum of them.
+#[derive(Debug, Clone)]
+pub struct HeadQuorumResponse {
+    /// The highest HEAD reported by at least the quorum of peers or, if the quorum wasn't
+    /// reached for any, the highest HEAD reported.
+    pub head: ExtendedHeader,
+    /// Whether at least the quorum of peers reported the `head`.
+    pub quorum_reached: bool,
+    /// Peers that reported the `head`.
+    pub agreeing_peers: Vec<PeerId>,
+    /// Peers that reported a different HEAD or failed to report one.
+    pub disagreements: Vec<HeadDisagreement>,
+}
+
+/// A peer that didn't report the HEAD chosen by the quorum.
+#[derive(Debug, Clone)]
+pub struct HeadDisagreement {
+    /// Id of the peer.
+    pub peer_id: PeerId,
+    /// HEAD reported by the peer, or `None` if it failed to respond with a valid one.
+    pub head: Option<ExtendedHeader>,
+}
 
 pub(super) struct HeaderExClientHandler<S = ReqRespBehaviour>
 where
@@ -29,11 +59,19 @@ where
 {
     reqs: HashMap<S::RequestId, State>,
     peer_tracker: Arc<PeerTracker>,
+    received_tx: mpsc::Sender<(PeerId, Vec<ExtendedHeader>)>,
+    received_rx: mpsc::Receiver<(PeerId, Vec<ExtendedHeader>)>,
 }
 
+type HeadResponseRx = (
+    PeerId,
+    oneshot::Receiver<Result<Vec<ExtendedHeader>, P2pError>>,
+);
+
 struct State {
     request: HeaderRequest,
     respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    sent_at: Instant,
 }
 
 pub(super) trait RequestSender {
@@ -55,9 +93,13 @@ where
     S: RequestSender,
 {
     pub(super) fn new(peer_tracker: Arc<PeerTracker>) -> Self {
+        let (received_tx, received_rx) = mpsc::channel(MAX_BUFFERED_RECEIVED);
+
         HeaderExClientHandler {
             reqs: HashMap::new(),
             peer_tracker,
+            received_tx,
+            received_rx,
         }
     }
 
@@ -99,31 +141,89 @@ where
             return;
         };
 
+        self.send_request_to_peer(sender, peer, request, respond_to);
+    }
+
+    /// Sends the `request` to the given `peer`, instead of choosing the best one.
+    ///
+    /// Used for requesting a specific header back from the peer that provided it.
+    #[instrument(level = "trace", skip(self, sender, respond_to))]
+    pub(super) fn on_send_request_to(
+        &mut self,
+        sender: &mut S,
+        peer: PeerId,
+        request: HeaderRequest,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
+        if !request.is_valid()
+            || request.is_head_request()
+            || usize::try_from(request.amount).is_err()
+        {
+            respond_to.maybe_send_err(HeaderExError::InvalidRequest);
+            return;
+        }
+
+        self.send_request_to_peer(sender, peer, request, respond_to);
+
+        trace!("Request initiated");
+    }
+
+    fn send_request_to_peer(
+        &mut self,
+        sender: &mut S,
+        peer: PeerId,
+        request: HeaderRequest,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
         let req_id = sender.send_request(&peer, request.clone());
         let state = State {
             request,
             respond_to,
+            sent_at: Instant::now(),
         };
 
         self.reqs.insert(req_id, state);
     }
 
+    #[instrument(level = "trace", skip(self, sender, respond_to))]
+    pub(super) fn on_send_head_request(
+        &mut self,
+        sender: &mut S,
+        quorum: usize,
+        respond_to: OneshotResultSender<HeadQuorumResponse, P2pError>,
+    ) {
+        if quorum == 0 {
+            respond_to.maybe_send_err(HeaderExError::InvalidRequest);
+            return;
+        }
+
+        let rxs = self.request_head(sender, HeaderRequest::head_request());
+
+        spawn(async move {
+            respond_to.maybe_send(wait_for_head(rxs, quorum).await);
+        });
+
+        trace!("Head request initiated");
+    }
+
     fn send_head_request(
         &mut self,
         sender: &mut S,
         request: HeaderRequest,
         respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
     ) {
-        const MIN_HEAD_RESPONSES: usize = 2;
+        let rxs = self.request_head(sender, request);
 
+        spawn(async move {
+            let head = wait_for_head(rxs, MIN_HEAD_RESPONSES).await;
+            respond_to.maybe_send(head.map(|resp| vec![resp.head]));
+        });
+    }
+
+    /// Requests HEAD from the trusted peers, returning the channels of their responses.
+    fn request_head(&mut self, sender: &mut S, request: HeaderRequest) -> Vec<HeadResponseRx> {
         // For now HEAD is requested from trusted peers only!
         let peers = self.peer_tracker.trusted_n_peers(MAX_PEERS);
-
-        if peers.is_empty() {
-            respond_to.maybe_send_err(P2pError::NoConnectedPeers);
-            return;
-        }
-
         let mut rxs = Vec::with_capacity(peers.len());
 
         for peer in peers {
@@ -133,54 +233,14 @@ where
             let state = State {
                 request: request.clone(),
                 respond_to: tx,
+                sent_at: Instant::now(),
             };
 
             self.reqs.insert(req_id, state);
-            rxs.push(rx);
+            rxs.push((peer, rx));
         }
 
-        // Choose the best HEAD.
-        //
-        // Algorithm: https://github.com/celestiaorg/go-header/blob/e50090545cc7e049d2f965d2b5c773eaa4a2c0b2/p2p/exchange.go#L357-L381
-        spawn(async move {
-            let mut resps: Vec<_> = join_all(rxs)
-                .await
-                .into_iter()
-                // In case of HEAD all responses have only 1 header.
-                // This was already enforced by `decode_and_verify_responses`.
-                .filter_map(|v| v.ok()?.ok()?.into_iter().next())
-                .collect();
-            let mut counter: HashMap<_, usize> = HashMap::new();
-
-            // In case of no responses, Celestia handles it as NotFound
-            if resps.is_empty() {
-                respond_to.maybe_send_err(HeaderExError::HeaderNotFound);
-                return;
-            }
-
-            // Count peers per response
-            for resp in &resps {
-                *counter.entry(resp.hash()).or_default() += 1;
-            }
-
-            // Sort by height and then peers in descending order
-            resps.sort_unstable_by_key(|resp| {
-                let num_of_peers = counter[&resp.hash()];
-                Reverse((resp.height(), num_of_peers))
-            });
-
-            // Return the header with the highest height that was received by at least 2 peers
-            for resp in &resps {
-                if counter[&resp.hash()] >= MIN_HEAD_RESPONSES {
-                    respond_to.maybe_send_ok(vec![resp.to_owned()]);
-                    return;
-                }
-            }
-
-            // Otherwise return the header with the maximum height
-            let resp = resps.into_iter().next().expect("no reposnes");
-            respond_to.maybe_send_ok(vec![resp]);
-        });
+        rxs
     }
 
     #[instrument(level = "trace", skip(self, responses), fields(responses.len = responses.len()))]
@@ -199,14 +259,34 @@ where
             state.request.amount
         );
 
+        let elapsed = state.sent_at.elapsed();
+        let peer_tracker = self.peer_tracker.clone();
+        let received_tx = self.received_tx.clone();
+
         spawn(async move {
+            // HEAD is requested from all the trusted peers at once, so it's not
+            // representative for the throughput of a peer
+            let is_head_request = state.request.is_head_request();
+
             match decode_and_verify_responses(&state.request, &responses).await {
                 Ok(headers) => {
-                    // TODO: Increase peer score
+                    if !is_head_request {
+                        if (headers.len() as u64) < state.request.amount {
+                            // Peer doesn't have the whole range, prefer others for the retry
+                            peer_tracker.record_failure(peer);
+                        } else {
+                            peer_tracker.record_response(peer, headers.len() as u64, elapsed);
+                        }
+                    }
+                    if received_tx.try_send((peer, headers.clone())).is_err() {
+                        debug!("Received headers buffer is full, skipping fork detection");
+                    }
                     state.respond_to.maybe_send_ok(headers);
                 }
                 Err(e) => {
-                    // TODO: Decrease peer score
+                    if !is_head_request {
+                        peer_tracker.record_failure(peer);
+                    }
                     state.respond_to.maybe_send_err(e);
                 }
             }
@@ -223,14 +303,113 @@ where
         debug!("Outbound failure");
 
         if let Some(state) = self.reqs.remove(&request_id) {
+            if !state.request.is_head_request() {
+                self.peer_tracker.record_failure(peer);
+            }
+
             state
                 .respond_to
                 .maybe_send_err(HeaderExError::OutboundFailure(error));
         }
     }
 
-    pub(super) fn poll(&mut self, _cx: &mut Context) -> Poll<()> {
-        Poll::Pending
+    /// Polls the validated headers received from the peers.
+    pub(super) fn poll(&mut self, cx: &mut Context) -> Poll<(PeerId, Vec<ExtendedHeader>)> {
+        self.received_rx
+            .poll_recv(cx)
+            .map(|received| received.expect("handler holds the sender"))
+    }
+}
+
+async fn wait_for_head(
+    rxs: Vec<HeadResponseRx>,
+    quorum: usize,
+) -> Result<HeadQuorumResponse, P2pError> {
+    if rxs.is_empty() {
+        return Err(P2pError::NoConnectedPeers);
+    }
+
+    let (peers, rxs): (Vec<_>, Vec<_>) = rxs.into_iter().unzip();
+
+    let resps = join_all(rxs)
+        .await
+        .into_iter()
+        // In case of HEAD all responses have only 1 header.
+        // This was already enforced by `decode_and_verify_responses`.
+        .map(|v| v.ok()?.ok()?.into_iter().next());
+
+    Ok(choose_head(peers.into_iter().zip(resps).collect(), quorum)?)
+}
+
+/// Chooses the best HEAD out of the ones reported by the peers.
+///
+/// Algorithm: https://github.com/celestiaorg/go-header/blob/e50090545cc7e049d2f965d2b5c773eaa4a2c0b2/p2p/exchange.go#L357-L381
+fn choose_head(
+    resps: Vec<(PeerId, Option<ExtendedHeader>)>,
+    quorum: usize,
+) -> Result<HeadQuorumResponse, HeaderExError> {
+    let mut counter: HashMap<_, usize> = HashMap::new();
+
+    // Count peers per response
+    for header in resps.iter().filter_map(|(_, header)| header.as_ref()) {
+        *counter.entry(header.hash()).or_default() += 1;
+    }
+
+    // Sort by height and then peers in descending order
+    let mut heads: Vec<_> = resps
+        .iter()
+        .filter_map(|(_, header)| header.as_ref())
+        .collect();
+    heads.sort_unstable_by_key(|head| Reverse((head.height(), counter[&head.hash()])));
+
+    // Choose the header with the highest height that was received by at least `quorum` peers,
+    // otherwise the header with the maximum height.
+    // In case of no responses, Celestia handles it as NotFound
+    let (head, quorum_reached) = match heads.iter().find(|head| counter[&head.hash()] >= quorum) {
+        Some(head) => ((*head).to_owned(), true),
+        None => (
+            (*heads.first().ok_or(HeaderExError::HeaderNotFound)?).to_owned(),
+            false,
+        ),
+    };
+
+    let mut agreeing_peers = Vec::new();
+    let mut disagreements = Vec::new();
+
+    for (peer_id, header) in resps {
+        match header {
+            Some(header) if header.hash() == head.hash() => agreeing_peers.push(peer_id),
+            head => disagreements.push(HeadDisagreement { peer_id, head }),
+        }
+    }
+
+    Ok(HeadQuorumResponse {
+        head,
+        quorum_reached,
+        agreeing_peers,
+        disagreements,
+    })
+}
+
+impl HeadQuorumResponse {
+    /// Returns the chosen HEAD, logging the disagreeing peers and whether the `quorum`
+    /// of them wasn't reached.
+    pub(crate) fn into_head(self, quorum: usize) -> ExtendedHeader {
+        for disagreement in &self.disagreements {
+            match &disagreement.head {
+                Some(head) => debug!(
+                    "Peer {} reported different head: {head}",
+                    disagreement.peer_id
+                ),
+                None => debug!("Peer {} didn't report head", disagreement.peer_id),
+            }
+        }
+
+        if !self.quorum_reached {
+            warn!("Head wasn't reported by {quorum} trusted peers, using the highest one reported");
+        }
+
+        self.head
     }
 }
 
@@ -633,6 +812,64 @@ mod tests {
         ));
     }
 
+    #[async_test]
+    async fn short_response_is_recorded_as_failure() {
+        let peer_tracker = peer_tracker_with_n_peers(2);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker.clone());
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_request(&mut mock_req, HeaderRequest::with_origin(5, 2), tx);
+        let short_peer = mock_req.reqs[0].peer;
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let header5 = gen.next();
+
+        mock_req.send_n_responses(&mut handler, 1, vec![header5.to_header_response()]);
+
+        let result = rx.await.unwrap().unwrap();
+        assert_eq!(result, vec![header5]);
+
+        for _ in 0..10 {
+            assert_ne!(peer_tracker.best_peer(), Some(short_peer));
+        }
+    }
+
+    #[async_test]
+    async fn request_to_peer() {
+        let peer_tracker = peer_tracker_with_n_peers(15);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+        let peer = PeerId::random();
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let expected_header = gen.next();
+
+        let (tx, rx) = oneshot::channel();
+        handler.on_send_request_to(
+            &mut mock_req,
+            peer,
+            HeaderRequest::with_hash(expected_header.hash()),
+            tx,
+        );
+        assert_eq!(mock_req.reqs[0].peer, peer);
+
+        mock_req.send_n_responses(&mut handler, 1, vec![expected_header.to_header_response()]);
+
+        let result = rx.await.unwrap().unwrap();
+        assert_eq!(result, vec![expected_header]);
+
+        // HEAD is always requested from the trusted peers
+        let (tx, rx) = oneshot::channel();
+        handler.on_send_request_to(&mut mock_req, peer, HeaderRequest::head_request(), tx);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::InvalidRequest)))
+        ));
+    }
+
     #[async_test]
     async fn request_range_responds_with_bigger_one() {
         let peer_tracker = peer_tracker_with_n_peers(15);
@@ -1030,6 +1267,82 @@ mod tests {
         assert!(matches!(rx.await, Ok(Err(P2pError::NoConnectedPeers))));
     }
 
+    #[async_test]
+    async fn head_quorum_reports_disagreements() {
+        let peer_tracker = peer_tracker_with_n_peers(4);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_head_request(&mut mock_req, 3, tx);
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let header5 = gen.next();
+        let header6 = gen.next();
+
+        let disagreeing_peer = mock_req.reqs[0].peer;
+        mock_req.send_n_responses(&mut handler, 1, vec![header6.to_header_response()]);
+        mock_req.send_n_responses(&mut handler, 3, vec![header5.to_header_response()]);
+
+        let result = rx.await.unwrap().unwrap();
+        assert_eq!(result.head, header5);
+        assert!(result.quorum_reached);
+        assert_eq!(result.agreeing_peers.len(), 3);
+        assert_eq!(result.disagreements.len(), 1);
+        assert_eq!(result.disagreements[0].peer_id, disagreeing_peer);
+        assert_eq!(result.disagreements[0].head, Some(header6));
+    }
+
+    #[async_test]
+    async fn head_quorum_not_reached() {
+        let peer_tracker = peer_tracker_with_n_peers(4);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_head_request(&mut mock_req, 3, tx);
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let header5 = gen.next();
+        let header6 = gen.next();
+
+        mock_req.send_n_responses(&mut handler, 2, vec![header5.to_header_response()]);
+        mock_req.send_n_responses(&mut handler, 1, vec![header6.to_header_response()]);
+        mock_req.send_n_failures(&mut handler, 1, OutboundFailure::Timeout);
+
+        let result = rx.await.unwrap().unwrap();
+        assert_eq!(result.head, header6);
+        assert!(!result.quorum_reached);
+        assert_eq!(result.agreeing_peers.len(), 1);
+        assert_eq!(result.disagreements.len(), 3);
+        assert_eq!(
+            result
+                .disagreements
+                .iter()
+                .filter(|disagreement| disagreement.head.is_none())
+                .count(),
+            1
+        );
+    }
+
+    #[async_test]
+    async fn head_quorum_of_zero_peers() {
+        let peer_tracker = peer_tracker_with_n_peers(4);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_head_request(&mut mock_req, 0, tx);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::InvalidRequest)))
+        ));
+    }
+
     #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
     struct MockReqId(u64);
 
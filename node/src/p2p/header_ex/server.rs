@@ -1,22 +1,39 @@
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use celestia_proto::p2p::pb::{header_request, HeaderRequest, HeaderResponse};
 use celestia_types::hash::Hash;
+use instant::{Duration, Instant};
 use libp2p::{
     request_response::{InboundFailure, InboundRequestId, ResponseChannel},
     PeerId,
 };
+use serde::Serialize;
 use tokio::sync::mpsc::{self, error::TrySendError};
-use tracing::{instrument, trace};
+use tracing::{instrument, trace, warn};
 
 use crate::executor::spawn;
 use crate::p2p::header_ex::utils::{ExtendedHeaderExt, HeaderRequestExt, HeaderResponseExt};
 use crate::p2p::header_ex::{ReqRespBehaviour, ResponseType};
+use crate::peer_tracker::PeerTracker;
 use crate::store::Store;
 
 const MAX_HEADERS_AMOUNT_RESPONSE: u64 = 512;
+/// Maximum amount of headers a peer can request at once, after being idle for a while.
+const MAX_REQUESTED_HEADERS_BURST: u64 = 4 * MAX_HEADERS_AMOUNT_RESPONSE;
+/// Amount of headers a peer can request per second, on average.
+const REQUESTED_HEADERS_PER_SECOND: u64 = MAX_HEADERS_AMOUNT_RESPONSE;
+/// Maximum amount of requests from a single peer handled at the same time.
+const MAX_IN_FLIGHT_REQUESTS_PER_PEER: usize = 8;
+/// Amount of rejected requests within [`REJECTED_REQUESTS_WINDOW`] after which a peer is penalized.
+const MAX_REJECTED_REQUESTS: u64 = 32;
+const REJECTED_REQUESTS_WINDOW: Duration = Duration::from_secs(60);
+/// How long a penalized peer is blocked for.
+const PENALTY_DURATION: Duration = Duration::from_secs(10 * 60);
+/// Amount of peers tracked, above which the idle ones are forgotten.
+const MAX_TRACKED_PEERS: usize = 1024;
 
 pub(super) struct HeaderExServerHandler<S, R = ReqRespBehaviour>
 where
@@ -24,9 +41,102 @@ where
     R: ResponseSender,
 {
     store: Arc<S>,
+    peer_tracker: Arc<PeerTracker>,
+    peer_budgets: HashMap<PeerId, PeerBudget>,
+    penalized_peers: VecDeque<PeerId>,
+    stats: HeaderExServerStats,
 
-    rx: mpsc::Receiver<(R::Channel, ResponseType)>,
-    tx: mpsc::Sender<(R::Channel, ResponseType)>,
+    rx: mpsc::Receiver<(PeerId, R::Channel, ResponseType)>,
+    tx: mpsc::Sender<(PeerId, R::Channel, ResponseType)>,
+}
+
+/// Statistics of the requests rejected by the header-ex server.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HeaderExServerStats {
+    /// Number of requests rejected because the peer exceeded its rate limit.
+    pub rate_limited_requests: u64,
+    /// Number of requests rejected because the peer had too many requests in flight.
+    pub concurrency_limited_requests: u64,
+    /// Number of times a peer was penalized for repeatedly exceeding the limits.
+    pub penalized_peers: u64,
+}
+
+/// Token bucket of a single peer, where each requested header takes a token.
+#[derive(Debug)]
+struct PeerBudget {
+    tokens: f64,
+    last_refill: Instant,
+    in_flight: usize,
+    rejected: u64,
+    rejected_since: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    RateLimited,
+    TooManyInFlight,
+}
+
+impl PeerBudget {
+    fn new(now: Instant) -> Self {
+        PeerBudget {
+            tokens: MAX_REQUESTED_HEADERS_BURST as f64,
+            last_refill: now,
+            in_flight: 0,
+            rejected: 0,
+            rejected_since: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * REQUESTED_HEADERS_PER_SECOND as f64)
+            .min(MAX_REQUESTED_HEADERS_BURST as f64);
+        self.last_refill = now;
+    }
+
+    fn try_acquire(&mut self, cost: u64, now: Instant) -> Result<(), Rejection> {
+        self.refill(now);
+
+        if self.in_flight >= MAX_IN_FLIGHT_REQUESTS_PER_PEER {
+            return Err(Rejection::TooManyInFlight);
+        }
+
+        if self.tokens < cost as f64 {
+            return Err(Rejection::RateLimited);
+        }
+
+        self.tokens -= cost as f64;
+        self.in_flight += 1;
+
+        Ok(())
+    }
+
+    /// Records a rejected request, returning `true` if peer should be penalized.
+    fn reject(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.rejected_since) > REJECTED_REQUESTS_WINDOW {
+            self.rejected = 0;
+            self.rejected_since = now;
+        }
+
+        self.rejected += 1;
+
+        if self.rejected >= MAX_REJECTED_REQUESTS {
+            self.rejected = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.in_flight == 0
+            && self.rejected == 0
+            && self.tokens >= MAX_REQUESTED_HEADERS_BURST as f64
+    }
 }
 
 pub(super) trait ResponseSender {
@@ -50,9 +160,26 @@ where
     S: Store + 'static,
     R: ResponseSender,
 {
-    pub(super) fn new(store: Arc<S>) -> Self {
+    pub(super) fn new(store: Arc<S>, peer_tracker: Arc<PeerTracker>) -> Self {
         let (tx, rx) = mpsc::channel(32);
-        HeaderExServerHandler { store, rx, tx }
+        HeaderExServerHandler {
+            store,
+            peer_tracker,
+            peer_budgets: HashMap::new(),
+            penalized_peers: VecDeque::new(),
+            stats: HeaderExServerStats::default(),
+            rx,
+            tx,
+        }
+    }
+
+    pub(super) fn stats(&self) -> HeaderExServerStats {
+        self.stats.clone()
+    }
+
+    /// Returns the next peer penalized for abusing the server, which should be disconnected.
+    pub(super) fn next_penalized_peer(&mut self) -> Option<PeerId> {
+        self.penalized_peers.pop_front()
     }
 
     #[instrument(level = "trace", skip(self, response_channel))]
@@ -65,20 +192,26 @@ where
     ) where
         Id: Display + Debug,
     {
+        if let Err(rejection) = self.try_acquire_budget(peer, &request) {
+            // Dropping the channel closes the stream without a response
+            trace!("Rejected request from {peer}: {rejection:?}");
+            return;
+        }
+
         let Some((amount, data)) = parse_request(request) else {
-            self.handle_invalid_request(response_channel);
+            self.handle_invalid_request(peer, response_channel);
             return;
         };
 
         match data {
             header_request::Data::Origin(0) => {
-                self.handle_request_current_head(response_channel);
+                self.handle_request_current_head(peer, response_channel);
             }
             header_request::Data::Origin(height) => {
-                self.handle_request_by_height(response_channel, height, amount);
+                self.handle_request_by_height(peer, response_channel, height, amount);
             }
             header_request::Data::Hash(hash) => {
-                self.handle_request_by_hash(response_channel, hash);
+                self.handle_request_by_hash(peer, response_channel, hash);
             }
         };
     }
@@ -99,7 +232,11 @@ where
 
     pub fn poll(&mut self, cx: &mut Context<'_>, sender: &mut R) -> Poll<()> {
         loop {
-            if let Poll::Ready(Some((channel, response))) = self.rx.poll_recv(cx) {
+            if let Poll::Ready(Some((peer, channel, response))) = self.rx.poll_recv(cx) {
+                if let Some(budget) = self.peer_budgets.get_mut(&peer) {
+                    budget.in_flight = budget.in_flight.saturating_sub(1);
+                }
+
                 sender.send_response(channel, response);
                 continue;
             }
@@ -108,7 +245,44 @@ where
         }
     }
 
-    fn handle_request_current_head(&mut self, channel: R::Channel) {
+    fn try_acquire_budget(
+        &mut self,
+        peer: PeerId,
+        request: &HeaderRequest,
+    ) -> Result<(), Rejection> {
+        let now = Instant::now();
+
+        if self.peer_budgets.len() >= MAX_TRACKED_PEERS {
+            self.peer_budgets.retain(|_, budget| {
+                budget.refill(now);
+                !budget.is_idle()
+            });
+        }
+
+        let budget = self
+            .peer_budgets
+            .entry(peer)
+            .or_insert_with(|| PeerBudget::new(now));
+
+        let Err(rejection) = budget.try_acquire(request_cost(request), now) else {
+            return Ok(());
+        };
+
+        match rejection {
+            Rejection::RateLimited => self.stats.rate_limited_requests += 1,
+            Rejection::TooManyInFlight => self.stats.concurrency_limited_requests += 1,
+        }
+
+        if budget.reject(now) && self.peer_tracker.penalize(peer, PENALTY_DURATION) {
+            warn!("Penalizing {peer} for exceeding header-ex request limits");
+            self.stats.penalized_peers += 1;
+            self.penalized_peers.push_back(peer);
+        }
+
+        Err(rejection)
+    }
+
+    fn handle_request_current_head(&mut self, peer: PeerId, channel: R::Channel) {
         let store = self.store.clone();
         let tx = self.tx.clone();
 
@@ -119,13 +293,13 @@ where
                 .map(|head| head.to_header_response())
                 .unwrap_or_else(|_| HeaderResponse::not_found());
 
-            let _ = tx.send((channel, vec![response])).await;
+            let _ = tx.send((peer, channel, vec![response])).await;
         });
     }
 
-    fn handle_request_by_hash(&mut self, channel: R::Channel, hash: Vec<u8>) {
+    fn handle_request_by_hash(&mut self, peer: PeerId, channel: R::Channel, hash: Vec<u8>) {
         let Ok(hash) = hash.try_into().map(Hash::Sha256) else {
-            self.handle_invalid_request(channel);
+            self.handle_invalid_request(peer, channel);
             return;
         };
 
@@ -139,43 +313,49 @@ where
                 .map(|head| head.to_header_response())
                 .unwrap_or_else(|_| HeaderResponse::not_found());
 
-            let _ = tx.send((channel, vec![response])).await;
+            let _ = tx.send((peer, channel, vec![response])).await;
         });
     }
 
-    fn handle_request_by_height(&mut self, channel: R::Channel, origin: u64, amount: u64) {
+    fn handle_request_by_height(
+        &mut self,
+        peer: PeerId,
+        channel: R::Channel,
+        origin: u64,
+        amount: u64,
+    ) {
         let store = self.store.clone();
         let tx = self.tx.clone();
 
         spawn(async move {
             let amount = amount.min(MAX_HEADERS_AMOUNT_RESPONSE);
-            let mut responses = vec![];
-
-            for i in origin..origin + amount {
-                match store.get_by_height(i).await {
-                    Ok(h) => {
-                        if responses.is_empty() {
-                            responses.reserve_exact(amount as usize);
-                        }
-
-                        responses.push(h.to_header_response());
-                    }
-                    Err(_) => break,
-                }
-            }
+            let head_height = store.head_height().await.unwrap_or(0);
+
+            // Serve the part of the requested range that we have, with a single store read
+            let end = origin.saturating_add(amount - 1).min(head_height);
+            let mut responses = if origin == 0 || origin > end {
+                vec![]
+            } else {
+                store
+                    .get_range(origin..=end)
+                    .await
+                    .map(|headers| headers.iter().map(|h| h.to_header_response()).collect())
+                    .unwrap_or_default()
+            };
 
             if responses.is_empty() {
                 responses.reserve_exact(1);
                 responses.push(HeaderResponse::not_found());
             }
 
-            let _ = tx.send((channel, responses)).await;
+            let _ = tx.send((peer, channel, responses)).await;
         });
     }
 
-    fn handle_invalid_request(&self, channel: R::Channel) {
+    fn handle_invalid_request(&self, peer: PeerId, channel: R::Channel) {
         if let Err(TrySendError::Full(response)) =
-            self.tx.try_send((channel, vec![HeaderResponse::invalid()]))
+            self.tx
+                .try_send((peer, channel, vec![HeaderResponse::invalid()]))
         {
             let tx = self.tx.clone();
 
@@ -186,6 +366,16 @@ where
     }
 }
 
+/// Returns the amount of headers requested, which is taken from the peer's budget.
+fn request_cost(request: &HeaderRequest) -> u64 {
+    match request.data {
+        Some(header_request::Data::Origin(height)) if height != 0 => {
+            request.amount.clamp(1, MAX_HEADERS_AMOUNT_RESPONSE)
+        }
+        _ => 1,
+    }
+}
+
 fn parse_request(request: HeaderRequest) -> Option<(u64, header_request::Data)> {
     if !request.is_valid() {
         return None;
@@ -210,7 +400,7 @@ mod tests {
     async fn request_head_test() {
         let (store, _) = gen_filled_store(4);
         let expected_head = store.get_head().await.unwrap();
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         handler.on_request_received(PeerId::random(), "test", HeaderRequest::head_request(), ());
 
@@ -226,7 +416,7 @@ mod tests {
     async fn request_header_test() {
         let (store, _) = gen_filled_store(3);
         let expected_genesis = store.get_by_height(1).await.unwrap();
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         handler.on_request_received(
             PeerId::random(),
@@ -246,7 +436,7 @@ mod tests {
     #[async_test]
     async fn invalid_amount_request_test() {
         let (store, _) = gen_filled_store(1);
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         handler.on_request_received(
             PeerId::random(),
@@ -264,7 +454,7 @@ mod tests {
     #[async_test]
     async fn none_data_request_test() {
         let (store, _) = gen_filled_store(1);
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         let request = HeaderRequest {
             data: None,
@@ -282,7 +472,7 @@ mod tests {
     async fn request_hash_test() {
         let (store, _) = gen_filled_store(1);
         let stored_header = store.get_head().await.unwrap();
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         handler.on_request_received(
             PeerId::random(),
@@ -302,7 +492,7 @@ mod tests {
     #[async_test]
     async fn request_malformed_hash_test() {
         let (store, _) = gen_filled_store(1);
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         let request = HeaderRequest {
             data: Some(header_request::Data::Hash(vec![0; 31])),
@@ -325,7 +515,7 @@ mod tests {
             store.get_by_height(6).await.unwrap(),
             store.get_by_height(7).await.unwrap(),
         ];
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         let request = HeaderRequest {
             data: Some(Data::Origin(5)),
@@ -349,7 +539,7 @@ mod tests {
         let expected_status_codes = [StatusCode::Ok];
         assert_eq!(expected_hashes.len(), expected_status_codes.len());
 
-        let mut handler = HeaderExServerHandler::new(Arc::new(store));
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
 
         let request = HeaderRequest::with_origin(5, 10);
         handler.on_request_received(PeerId::random(), "test", request, ());
@@ -369,6 +559,79 @@ mod tests {
         }
     }
 
+    #[async_test]
+    async fn rate_limit_test() {
+        let (store, _) = gen_filled_store(10);
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
+        let peer = PeerId::random();
+        let other_peer = PeerId::random();
+
+        // Drain the whole burst, in requests of the maximum size
+        for _ in 0..MAX_REQUESTED_HEADERS_BURST / MAX_HEADERS_AMOUNT_RESPONSE {
+            let request = HeaderRequest::with_origin(1, MAX_HEADERS_AMOUNT_RESPONSE);
+            handler.on_request_received(peer, "test", request, ());
+            poll_handler_for_result(&mut handler).await;
+        }
+
+        let request = HeaderRequest::with_origin(1, MAX_HEADERS_AMOUNT_RESPONSE);
+        handler.on_request_received(peer, "test", request, ());
+        assert_eq!(handler.stats().rate_limited_requests, 1);
+
+        // Other peers have their own budget
+        handler.on_request_received(other_peer, "test", HeaderRequest::head_request(), ());
+        let received = poll_handler_for_result(&mut handler).await;
+        assert_eq!(received[0].status_code, i32::from(StatusCode::Ok));
+        assert_eq!(handler.stats().rate_limited_requests, 1);
+    }
+
+    #[async_test]
+    async fn in_flight_limit_test() {
+        let (store, _) = gen_filled_store(10);
+        let mut handler = HeaderExServerHandler::new(Arc::new(store), Arc::new(PeerTracker::new()));
+        let peer = PeerId::random();
+
+        // Responses are not sent until the handler is polled
+        for _ in 0..MAX_IN_FLIGHT_REQUESTS_PER_PEER {
+            handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        }
+
+        handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        assert_eq!(handler.stats().concurrency_limited_requests, 1);
+
+        // A single poll can send multiple responses
+        while handler.peer_budgets[&peer].in_flight > 0 {
+            poll_handler_for_result(&mut handler).await;
+        }
+
+        handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        poll_handler_for_result(&mut handler).await;
+        assert_eq!(handler.stats().concurrency_limited_requests, 1);
+    }
+
+    #[async_test]
+    async fn penalize_abusive_peer_test() {
+        let (store, _) = gen_filled_store(10);
+        let peer_tracker = Arc::new(PeerTracker::new());
+        let mut handler: HeaderExServerHandler<_, TestResponseSender> =
+            HeaderExServerHandler::new(Arc::new(store), peer_tracker.clone());
+        let peer = PeerId::random();
+
+        for _ in 0..MAX_IN_FLIGHT_REQUESTS_PER_PEER {
+            handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        }
+
+        for _ in 0..MAX_REJECTED_REQUESTS - 1 {
+            handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        }
+        assert!(!peer_tracker.is_blocked(peer));
+        assert_eq!(handler.next_penalized_peer(), None);
+
+        handler.on_request_received(peer, "test", HeaderRequest::head_request(), ());
+        assert!(peer_tracker.is_blocked(peer));
+        assert_eq!(handler.next_penalized_peer(), Some(peer));
+        assert_eq!(handler.stats().penalized_peers, 1);
+    }
+
     #[derive(Debug)]
     struct TestResponseSender(pub Option<oneshot::Sender<ResponseType>>);
 
@@ -1,37 +1,84 @@
+use std::collections::VecDeque;
+
 use celestia_proto::p2p::pb::HeaderRequest;
 use celestia_types::ExtendedHeader;
+use instant::Duration;
 use tokio::sync::{mpsc, oneshot};
 use tracing::debug;
 
 use crate::executor::spawn;
 use crate::p2p::header_ex::utils::HeaderRequestExt;
 use crate::p2p::{HeaderExError, P2pCmd, P2pError};
-
-const MAX_AMOUNT_PER_REQ: u64 = 64;
-const MAX_CONCURRENT_REQS: usize = 8;
+use crate::peer_tracker::HeaderExThroughput;
+
+/// Amount of headers per request used until the throughput of the peers is known.
+const DEFAULT_AMOUNT_PER_REQ: u64 = 64;
+const MIN_AMOUNT_PER_REQ: u64 = 16;
+/// Maximum amount of headers a server responds with.
+const MAX_AMOUNT_PER_REQ: u64 = 512;
+/// Number of concurrent requests used until the throughput of the peers is known.
+const DEFAULT_CONCURRENT_REQS: usize = 8;
+const MAX_CONCURRENT_REQS: usize = 16;
+/// Number of concurrent requests sent to a single peer, if it responds within
+/// the `TARGET_REQUEST_DURATION`.
+const CONCURRENT_REQS_PER_PEER: usize = 2;
+/// Expected duration of a single request, used for choosing the amount of headers per request.
+const TARGET_REQUEST_DURATION: Duration = Duration::from_secs(1);
+/// Number of failed attempts of a chunk of headers after which the session backs off to
+/// a single request at a time.
+const MAX_ATTEMPTS_PER_CHUNK: u32 = 4;
 
 type Result<T, E = P2pError> = std::result::Result<T, E>;
 
 pub(crate) struct HeaderSession {
     next_height: u64,
     remaining_amount: u64,
+    amount_per_req: u64,
+    max_concurrent_reqs: usize,
+    concurrent_reqs: usize,
+    retries: VecDeque<Chunk>,
     cmd_tx: mpsc::Sender<P2pCmd>,
-    response_tx: mpsc::Sender<(u64, u64, Result<Vec<ExtendedHeader>>)>,
-    response_rx: mpsc::Receiver<(u64, u64, Result<Vec<ExtendedHeader>>)>,
+    response_tx: mpsc::Sender<(Chunk, Result<Vec<ExtendedHeader>>)>,
+    response_rx: mpsc::Receiver<(Chunk, Result<Vec<ExtendedHeader>>)>,
     ongoing: usize,
 }
 
+/// A range of headers requested at once.
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    height: u64,
+    amount: u64,
+    attempts: u32,
+}
+
 impl HeaderSession {
-    pub(crate) fn new(from_height: u64, amount: u64, cmd_tx: mpsc::Sender<P2pCmd>) -> Result<Self> {
+    /// Creates a session requesting `amount` of headers starting at `from_height`.
+    ///
+    /// Requests are sized according to the `throughput` observed so far, if any.
+    pub(crate) fn new(
+        from_height: u64,
+        amount: u64,
+        cmd_tx: mpsc::Sender<P2pCmd>,
+        throughput: Option<HeaderExThroughput>,
+    ) -> Result<Self> {
         if from_height < 1 || amount < 1 {
             return Err(P2pError::HeaderEx(HeaderExError::InvalidRequest));
         }
 
+        let (amount_per_req, max_concurrent_reqs) = match throughput {
+            Some(throughput) => session_sizing(throughput),
+            None => (DEFAULT_AMOUNT_PER_REQ, DEFAULT_CONCURRENT_REQS),
+        };
+
         let (response_tx, response_rx) = mpsc::channel(MAX_CONCURRENT_REQS);
 
         Ok(HeaderSession {
             next_height: from_height,
             remaining_amount: amount,
+            amount_per_req,
+            max_concurrent_reqs,
+            concurrent_reqs: max_concurrent_reqs,
+            retries: VecDeque::new(),
             cmd_tx,
             response_tx,
             response_rx,
@@ -39,19 +86,19 @@ impl HeaderSession {
         })
     }
 
+    /// Requests all the headers of the session.
+    ///
+    /// Failed requests and short responses are retried, which usually reaches a different peer.
+    /// The session fails only if the headers can't be requested at all, e.g. there are no
+    /// connected peers, once the ongoing requests finish.
     pub(crate) async fn run(&mut self) -> Result<Vec<ExtendedHeader>> {
         let mut responses = Vec::new();
+        let mut error = None;
 
-        for _ in 0..MAX_CONCURRENT_REQS {
-            if self.remaining_amount == 0 {
-                break;
-            }
-
-            self.send_next_request().await?;
-        }
+        self.send_next_requests().await?;
 
         while self.ongoing > 0 {
-            let (height, requested_amount, res) = self.recv_response().await;
+            let (chunk, res) = self.recv_response().await;
 
             match res {
                 Ok(headers) => {
@@ -59,22 +106,40 @@ impl HeaderSession {
 
                     responses.push(headers);
 
-                    if headers_len < requested_amount {
-                        // Reschedule the missing sub-range
-                        let height = height + headers_len;
-                        let amount = requested_amount - headers_len;
-                        self.send_request(height, amount).await?;
+                    if headers_len < chunk.amount {
+                        // Reschedule the missing sub-range, counting it as a failed attempt
+                        self.retry(Chunk {
+                            height: chunk.height + headers_len,
+                            amount: chunk.amount - headers_len,
+                            attempts: chunk.attempts,
+                        });
                     } else {
-                        // Schedule next request
-                        self.send_next_request().await?;
+                        self.concurrent_reqs =
+                            (self.concurrent_reqs + 1).min(self.max_concurrent_reqs);
                     }
                 }
                 Err(P2pError::HeaderEx(e)) => {
                     debug!("HeaderEx error: {e}");
-                    self.send_request(height, requested_amount).await?;
+                    self.retry(chunk);
+                }
+                Err(e) => {
+                    debug!(
+                        "Giving up on batch {} until {}: {e}",
+                        chunk.height,
+                        chunk.height + chunk.amount - 1
+                    );
+                    error.get_or_insert(e);
                 }
-                Err(e) => return Err(e),
             }
+
+            // Once a chunk is lost, wait for the ongoing requests only
+            if error.is_none() {
+                self.send_next_requests().await?;
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(error);
         }
 
         let mut headers = responses.into_iter().flatten().collect::<Vec<_>>();
@@ -84,30 +149,66 @@ impl HeaderSession {
         Ok(headers)
     }
 
-    async fn recv_response(&mut self) -> (u64, u64, Result<Vec<ExtendedHeader>>) {
-        let (height, requested_amount, res) =
-            self.response_rx.recv().await.expect("channel never closes");
+    /// Schedules a chunk after a failed attempt.
+    ///
+    /// The peer that failed is avoided by the [`PeerTracker`], so the retry reaches a
+    /// different one if possible. Once the attempts are exhausted, the chunk starts over with
+    /// a single request at a time instead of failing the session.
+    ///
+    /// [`PeerTracker`]: crate::peer_tracker::PeerTracker
+    fn retry(&mut self, chunk: Chunk) {
+        if chunk.attempts < MAX_ATTEMPTS_PER_CHUNK {
+            self.retries.push_back(chunk);
+            // Back off, the peers can't keep up with us
+            self.concurrent_reqs = (self.concurrent_reqs / 2).max(1);
+        } else {
+            debug!(
+                "Batch {} until {} failed {} times, starting over",
+                chunk.height,
+                chunk.height + chunk.amount - 1,
+                chunk.attempts
+            );
+            self.retries.push_back(Chunk {
+                attempts: 0,
+                ..chunk
+            });
+            self.concurrent_reqs = 1;
+        }
+    }
+
+    async fn recv_response(&mut self) -> (Chunk, Result<Vec<ExtendedHeader>>) {
+        let (chunk, res) = self.response_rx.recv().await.expect("channel never closes");
 
         self.ongoing -= 1;
 
-        (height, requested_amount, res)
+        (chunk, res)
     }
 
-    pub(crate) async fn send_next_request(&mut self) -> Result<()> {
-        if self.remaining_amount == 0 {
-            return Ok(());
+    async fn send_next_requests(&mut self) -> Result<()> {
+        while self.ongoing < self.concurrent_reqs {
+            if let Some(chunk) = self.retries.pop_front() {
+                self.send_request(chunk).await?;
+            } else if self.remaining_amount > 0 {
+                let amount = self.remaining_amount.min(self.amount_per_req);
+                let chunk = Chunk {
+                    height: self.next_height,
+                    amount,
+                    attempts: 0,
+                };
+                self.send_request(chunk).await?;
+
+                self.next_height += amount;
+                self.remaining_amount -= amount;
+            } else {
+                break;
+            }
         }
 
-        let amount = self.remaining_amount.min(MAX_AMOUNT_PER_REQ);
-        self.send_request(self.next_height, amount).await?;
-
-        self.next_height += amount;
-        self.remaining_amount -= amount;
-
         Ok(())
     }
 
-    pub(crate) async fn send_request(&mut self, height: u64, amount: u64) -> Result<()> {
+    async fn send_request(&mut self, chunk: Chunk) -> Result<()> {
+        let Chunk { height, amount, .. } = chunk;
         debug!("Fetching batch {} until {}", height, height + amount - 1);
 
         let request = HeaderRequest::with_origin(height, amount);
@@ -122,13 +223,17 @@ impl HeaderSession {
             .map_err(|_| P2pError::WorkerDied)?;
 
         let response_tx = self.response_tx.clone();
+        let chunk = Chunk {
+            attempts: chunk.attempts + 1,
+            ..chunk
+        };
 
         spawn(async move {
             let result = match rx.await {
                 Ok(result) => result,
                 Err(_) => Err(P2pError::WorkerDied),
             };
-            let _ = response_tx.send((height, amount, result)).await;
+            let _ = response_tx.send((chunk, result)).await;
         });
 
         self.ongoing += 1;
@@ -137,6 +242,24 @@ impl HeaderSession {
     }
 }
 
+/// Returns the amount of headers per request and the number of concurrent requests
+/// suitable for the observed `throughput`.
+fn session_sizing(throughput: HeaderExThroughput) -> (u64, usize) {
+    let amount_per_req =
+        (throughput.headers_per_second * TARGET_REQUEST_DURATION.as_secs_f64()) as u64;
+    let amount_per_req = amount_per_req.clamp(MIN_AMOUNT_PER_REQ, MAX_AMOUNT_PER_REQ);
+
+    // Peers responding slower than expected get a single request at a time
+    let reqs_per_peer = if throughput.latency > TARGET_REQUEST_DURATION {
+        1
+    } else {
+        CONCURRENT_REQS_PER_PEER
+    };
+    let concurrent_reqs = (throughput.num_peers * reqs_per_peer).clamp(1, MAX_CONCURRENT_REQS);
+
+    (amount_per_req, concurrent_reqs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,7 +273,7 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(64);
 
-        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone(), None).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -179,7 +302,7 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(520);
 
-        let mut session = HeaderSession::new(1, 520, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session = HeaderSession::new(1, 520, p2p_mock.cmd_tx.clone(), None).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -215,7 +338,7 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(64);
 
-        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone(), None).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -244,7 +367,7 @@ mod tests {
     async fn no_peers_is_fatal() {
         let (_p2p, mut p2p_mock) = P2p::mocked();
 
-        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone(), None).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -263,4 +386,107 @@ mod tests {
             Ok(Err(P2pError::NoConnectedPeers))
         ));
     }
+
+    #[async_test]
+    async fn failed_chunk_is_retried() {
+        let (_p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(128);
+
+        let mut session = HeaderSession::new(1, 128, p2p_mock.cmd_tx.clone(), None).unwrap();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = session.run().await;
+            result_tx.send(res).unwrap();
+        });
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (1, 64));
+        respond_to.send(Ok(headers[..64].to_vec())).unwrap();
+
+        // Exhausting the attempts doesn't fail the session
+        for _ in 0..MAX_ATTEMPTS_PER_CHUNK + 1 {
+            let (height, amount, respond_to) =
+                p2p_mock.expect_header_request_for_height_cmd().await;
+            assert_eq!((height, amount), (65, 64));
+            respond_to
+                .send(Err(P2pError::HeaderEx(HeaderExError::InvalidResponse)))
+                .unwrap();
+        }
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (65, 64));
+        respond_to.send(Ok(headers[64..].to_vec())).unwrap();
+
+        p2p_mock.expect_no_cmd().await;
+
+        let received_headers = result_rx.await.unwrap().unwrap();
+        assert_eq!(headers, received_headers);
+    }
+
+    #[async_test]
+    async fn short_responses_are_retried() {
+        let (_p2p, mut p2p_mock) = P2p::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(64);
+
+        let mut session = HeaderSession::new(1, 64, p2p_mock.cmd_tx.clone(), None).unwrap();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = session.run().await;
+            result_tx.send(res).unwrap();
+        });
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (1, 64));
+        respond_to.send(Ok(headers[..60].to_vec())).unwrap();
+
+        // Empty responses exhaust the attempts, but don't fail the session
+        for _ in 0..MAX_ATTEMPTS_PER_CHUNK {
+            let (height, amount, respond_to) =
+                p2p_mock.expect_header_request_for_height_cmd().await;
+            assert_eq!((height, amount), (61, 4));
+            respond_to.send(Ok(Vec::new())).unwrap();
+        }
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (61, 4));
+        respond_to.send(Ok(headers[60..].to_vec())).unwrap();
+
+        p2p_mock.expect_no_cmd().await;
+
+        let received_headers = result_rx.await.unwrap().unwrap();
+        assert_eq!(headers, received_headers);
+    }
+
+    #[async_test]
+    async fn sizing_follows_throughput() {
+        let (_p2p, mut p2p_mock) = P2p::mocked();
+        let throughput = HeaderExThroughput {
+            num_peers: 1,
+            headers_per_second: 100.0,
+            latency: Duration::from_secs(2),
+        };
+
+        let mut session =
+            HeaderSession::new(1, 1000, p2p_mock.cmd_tx.clone(), Some(throughput)).unwrap();
+        spawn(async move {
+            let _ = session.run().await;
+        });
+
+        // Slow peer gets a single request at a time
+        let (height, amount, _respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (1, 100));
+        p2p_mock.expect_no_cmd().await;
+
+        let fast = HeaderExThroughput {
+            num_peers: 20,
+            headers_per_second: 10_000.0,
+            latency: Duration::from_millis(100),
+        };
+        assert_eq!(
+            session_sizing(fast),
+            (MAX_AMOUNT_PER_REQ, MAX_CONCURRENT_REQS)
+        );
+    }
 }
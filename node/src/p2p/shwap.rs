@@ -1,16 +1,20 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use beetswap::multihasher::{Multihasher, MultihasherError};
 use blockstore::block::CidError;
 use celestia_tendermint_proto::Protobuf;
+use celestia_types::column::{Column, ColumnId, COLUMN_ID_MULTIHASH_CODE};
 use celestia_types::namespaced_data::{
     NamespacedData, NamespacedDataId, NAMESPACED_DATA_ID_MULTIHASH_CODE,
 };
 use celestia_types::nmt::Namespace;
 use celestia_types::row::{Row, RowId, ROW_ID_MULTIHASH_CODE};
 use celestia_types::sample::{Sample, SampleId, SAMPLE_ID_MULTIHASH_CODE};
+use celestia_types::AxisType;
 use cid::{Cid, CidGeneric};
 use libp2p::multihash::Multihash;
+use tokio::sync::Notify;
 
 use crate::p2p::{P2pError, Result, MAX_MH_SIZE};
 use crate::store::Store;
@@ -21,14 +25,118 @@ where
     S: Store + 'static,
 {
     header_store: Arc<S>,
+    required_proof_axes: RequiredProofAxes,
 }
 
 impl<S> ShwapMultihasher<S>
 where
     S: Store + 'static,
 {
-    pub(super) fn new(header_store: Arc<S>) -> Self {
-        ShwapMultihasher { header_store }
+    pub(super) fn new(header_store: Arc<S>, required_proof_axes: RequiredProofAxes) -> Self {
+        ShwapMultihasher {
+            header_store,
+            required_proof_axes,
+        }
+    }
+}
+
+/// Axes along which the samples being requested must be proven.
+///
+/// Every sample request registers the axis it requires, or `None` if it accepts any.
+/// Shared between the [`P2p`] and the [`ShwapMultihasher`], which ignores a sample only
+/// if none of the pending requests for it accepts its axis, and notifies them about it.
+///
+/// [`P2p`]: crate::p2p::P2p
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RequiredProofAxes {
+    requests: Arc<Mutex<HashMap<Cid, Vec<RequiredProofAxis>>>>,
+}
+
+#[derive(Debug)]
+struct RequiredProofAxis {
+    axis: Option<AxisType>,
+    mismatch: Arc<Notify>,
+}
+
+impl RequiredProofAxes {
+    /// Register a request for the sample of `cid` proven along `axis`, or along any axis
+    /// if `None`, until the returned guard is dropped.
+    pub(super) fn require(&self, cid: Cid, axis: Option<AxisType>) -> RequiredProofAxisGuard {
+        let mismatch = Arc::new(Notify::new());
+
+        self.requests
+            .lock()
+            .expect("lock poisoned")
+            .entry(cid)
+            .or_default()
+            .push(RequiredProofAxis {
+                axis,
+                mismatch: mismatch.clone(),
+            });
+
+        RequiredProofAxisGuard {
+            axes: self.clone(),
+            cid,
+            mismatch,
+        }
+    }
+
+    /// Returns the axis required by any of the requests for the sample of `cid`.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub(crate) fn get(&self, cid: &Cid) -> Option<AxisType> {
+        let requests = self.requests.lock().expect("lock poisoned");
+        requests.get(cid)?.iter().find_map(|required| required.axis)
+    }
+
+    /// Check whether the sample of `cid` proven along `axis` can be accepted by any
+    /// of its requests, notifying the requesters if it can't.
+    pub(super) fn accepts(&self, cid: &Cid, axis: AxisType) -> bool {
+        let requests = self.requests.lock().expect("lock poisoned");
+
+        let Some(requests) = requests.get(cid) else {
+            return true;
+        };
+
+        if requests
+            .iter()
+            .any(|required| required.axis.map_or(true, |required| required == axis))
+        {
+            return true;
+        }
+
+        for required in requests {
+            required.mismatch.notify_one();
+        }
+
+        false
+    }
+}
+
+/// Keeps the request for a sample registered until dropped.
+pub(super) struct RequiredProofAxisGuard {
+    axes: RequiredProofAxes,
+    cid: Cid,
+    mismatch: Arc<Notify>,
+}
+
+impl RequiredProofAxisGuard {
+    /// Resolves once a peer served the sample proven along another axis than the required.
+    pub(super) async fn mismatched(&self) {
+        self.mismatch.notified().await
+    }
+}
+
+impl Drop for RequiredProofAxisGuard {
+    fn drop(&mut self) {
+        let mut requests = self.axes.requests.lock().expect("lock poisoned");
+
+        if let Some(cid_requests) = requests.get_mut(&self.cid) {
+            cid_requests.retain(|required| !Arc::ptr_eq(&required.mismatch, &self.mismatch));
+
+            if cid_requests.is_empty() {
+                requests.remove(&self.cid);
+            }
+        }
     }
 }
 
@@ -82,14 +190,32 @@ where
 
                 Ok(hash)
             }
-            SAMPLE_ID_MULTIHASH_CODE => {
-                let sample = Sample::decode(input).map_err(MultihasherError::custom_fatal)?;
+            COLUMN_ID_MULTIHASH_CODE => {
+                let column = Column::decode(input).map_err(MultihasherError::custom_fatal)?;
 
-                let hash = convert_cid(&sample.id.into())
+                let hash = convert_cid(&column.id.into())
                     .map_err(MultihasherError::custom_fatal)?
                     .hash()
                     .to_owned();
 
+                let header = self
+                    .header_store
+                    .get_by_height(column.id.block_height())
+                    .await
+                    .map_err(MultihasherError::custom_fatal)?;
+
+                column
+                    .verify(&header.dah)
+                    .map_err(MultihasherError::custom_fatal)?;
+
+                Ok(hash)
+            }
+            SAMPLE_ID_MULTIHASH_CODE => {
+                let sample = Sample::decode(input).map_err(MultihasherError::custom_fatal)?;
+
+                let cid = convert_cid(&sample.id.into()).map_err(MultihasherError::custom_fatal)?;
+                let hash = cid.hash().to_owned();
+
                 let header = self
                     .header_store
                     .get_by_height(sample.id.block_height())
@@ -100,6 +226,14 @@ where
                     .verify(&header.dah)
                     .map_err(MultihasherError::custom_fatal)?;
 
+                // The sample is valid, but not what we asked for, so just ignore it
+                if !self.required_proof_axes.accepts(&cid, sample.proof_type) {
+                    return Err(MultihasherError::custom(format!(
+                        "Sample proven along {:?} instead of the required axis",
+                        sample.proof_type
+                    )));
+                }
+
                 Ok(hash)
             }
             _ => Err(MultihasherError::UnknownMultihashCode),
@@ -112,6 +246,11 @@ pub(super) fn row_cid(row_index: u16, block_height: u64) -> Result<Cid> {
     convert_cid(&row_id.into())
 }
 
+pub(super) fn column_cid(column_index: u16, block_height: u64) -> Result<Cid> {
+    let column_id = ColumnId::new(column_index, block_height).map_err(P2pError::Cid)?;
+    convert_cid(&column_id.into())
+}
+
 pub(super) fn sample_cid(row_index: u16, column_index: u16, block_height: u64) -> Result<Cid> {
     let sample_id = SampleId::new(row_index, column_index, block_height).map_err(P2pError::Cid)?;
     convert_cid(&sample_id.into())
@@ -136,10 +275,14 @@ pub(crate) fn convert_cid<const S: usize>(cid: &CidGeneric<S>) -> Result<Cid> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::executor::spawn;
+    use crate::p2p::P2p;
     use crate::store::InMemoryStore;
     use crate::test_utils::async_test;
+    use celestia_types::nmt::NS_SIZE;
     use celestia_types::test_utils::{generate_eds, ExtendedHeaderGenerator};
     use celestia_types::{AxisType, DataAvailabilityHeader};
+    use tokio::sync::oneshot;
 
     #[async_test]
     async fn hash() {
@@ -158,11 +301,172 @@ mod tests {
         sample.verify(&dah).unwrap();
         store.append_single(header).await.unwrap();
 
-        let hash = ShwapMultihasher::new(store)
+        let hash = ShwapMultihasher::new(store, RequiredProofAxes::default())
             .hash(SAMPLE_ID_MULTIHASH_CODE, &sample_bytes)
             .await
             .unwrap();
 
         assert_eq!(hash, *cid.hash());
     }
+
+    #[async_test]
+    async fn hash_with_required_proof_axis() {
+        let store = Arc::new(InMemoryStore::new());
+
+        let eds = generate_eds(4);
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+
+        let mut gen = ExtendedHeaderGenerator::new();
+        let header = gen.next_with_dah(dah);
+        store.append_single(header).await.unwrap();
+
+        let row_sample = Sample::new(1, 2, AxisType::Row, &eds, 1).unwrap();
+        let row_sample_bytes = row_sample.encode_vec().unwrap();
+        let col_sample = Sample::new(1, 2, AxisType::Col, &eds, 1).unwrap();
+        let col_sample_bytes = col_sample.encode_vec().unwrap();
+        let cid = sample_cid(1, 2, 1).unwrap();
+
+        let required_proof_axes = RequiredProofAxes::default();
+        let hasher = ShwapMultihasher::new(store, required_proof_axes.clone());
+
+        let guard = required_proof_axes.require(cid, Some(AxisType::Col));
+        assert_eq!(required_proof_axes.get(&cid), Some(AxisType::Col));
+
+        // Sample proven along the other axis is ignored and the requester notified
+        let err = hasher
+            .hash(SAMPLE_ID_MULTIHASH_CODE, &row_sample_bytes)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MultihasherError::Custom(_)));
+        guard.mismatched().await;
+
+        let hash = hasher
+            .hash(SAMPLE_ID_MULTIHASH_CODE, &col_sample_bytes)
+            .await
+            .unwrap();
+        assert_eq!(hash, *cid.hash());
+
+        // Any axis is accepted if another request for the same sample accepts it
+        let any_axis_guard = required_proof_axes.require(cid, None);
+
+        let hash = hasher
+            .hash(SAMPLE_ID_MULTIHASH_CODE, &row_sample_bytes)
+            .await
+            .unwrap();
+        assert_eq!(hash, *cid.hash());
+
+        drop(guard);
+        drop(any_axis_guard);
+        assert_eq!(required_proof_axes.get(&cid), None);
+        assert!(required_proof_axes.requests.lock().unwrap().is_empty());
+    }
+
+    #[async_test]
+    async fn column_hash() {
+        let store = Arc::new(InMemoryStore::new());
+
+        let eds = generate_eds(4);
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+
+        let mut gen = ExtendedHeaderGenerator::new();
+        let header = gen.next_with_dah(dah);
+        store.append_single(header).await.unwrap();
+
+        let column = Column::new(3, &eds, 1).unwrap();
+        let column_bytes = column.encode_vec().unwrap();
+        let cid = column_cid(3, 1).unwrap();
+
+        let hasher = ShwapMultihasher::new(store, RequiredProofAxes::default());
+        let hash = hasher
+            .hash(COLUMN_ID_MULTIHASH_CODE, &column_bytes)
+            .await
+            .unwrap();
+        assert_eq!(hash, *cid.hash());
+
+        // Column doesn't match the root at the index it claims
+        let mut column = column;
+        column.id = ColumnId::new(2, 1).unwrap();
+        let column_bytes = column.encode_vec().unwrap();
+        hasher
+            .hash(COLUMN_ID_MULTIHASH_CODE, &column_bytes)
+            .await
+            .unwrap_err();
+    }
+
+    fn namespaced_eds() -> (Namespace, DataAvailabilityHeader, Vec<NamespacedData>) {
+        let eds = generate_eds(8);
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+        let namespace = Namespace::from_raw(&eds.share(0, 0).unwrap()[..NS_SIZE]).unwrap();
+        let rows = eds.get_namespaced_data(namespace, &dah, 1).unwrap();
+
+        (namespace, dah, rows)
+    }
+
+    #[async_test]
+    async fn get_namespaced_rows() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let (namespace, dah, rows) = namespaced_eds();
+        // Namespace spans all the rows of the original data square
+        assert_eq!(rows.len(), 4);
+
+        let (tx, rx) = oneshot::channel();
+        let dah_clone = dah.clone();
+        spawn(async move {
+            let res = p2p.get_namespaced_rows(namespace, &dah_clone, 1).await;
+            tx.send(res).unwrap();
+        });
+
+        for _ in 0..rows.len() {
+            let (cid, respond_to) = p2p_mock.expect_get_shwap_cid().await;
+            let row = rows
+                .iter()
+                .find(|row| namespaced_data_cid(namespace, row.id.row_index(), 1).unwrap() == cid)
+                .unwrap();
+            respond_to.send(Ok(row.encode_vec().unwrap())).unwrap();
+        }
+
+        let namespaced_rows = rx.await.unwrap().unwrap();
+        namespaced_rows.verify(&dah).unwrap();
+        assert_eq!(namespaced_rows.rows.len(), 4);
+        assert_eq!(namespaced_rows.shares().count(), 16);
+    }
+
+    #[async_test]
+    async fn get_namespaced_rows_of_absent_namespace() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let (_, dah, _) = namespaced_eds();
+        // Shares of the generated square are all in a single v0 namespace
+        let absent_namespace = Namespace::TAIL_PADDING;
+
+        // Absence is proven by the row roots, without requesting any row
+        let rows = p2p
+            .get_namespaced_rows(absent_namespace, &dah, 1)
+            .await
+            .unwrap();
+        p2p_mock.expect_no_cmd().await;
+
+        assert!(rows.rows.is_empty());
+        rows.verify_absence(&dah).unwrap();
+    }
+
+    #[async_test]
+    async fn get_namespaced_rows_with_wrong_row() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let (namespace, dah, rows) = namespaced_eds();
+
+        let (tx, rx) = oneshot::channel();
+        spawn(async move {
+            let res = p2p.get_namespaced_rows(namespace, &dah, 1).await;
+            tx.send(res).unwrap();
+        });
+
+        // Every row is responded with the data of the first one
+        for _ in 0..rows.len() {
+            let (_, respond_to) = p2p_mock.expect_get_shwap_cid().await;
+            respond_to.send(Ok(rows[0].encode_vec().unwrap())).unwrap();
+        }
+
+        let res = rx.await.unwrap();
+        assert!(matches!(res, Err(P2pError::ShwapVerification(_))));
+    }
 }
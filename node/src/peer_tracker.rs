@@ -5,12 +5,18 @@ use std::borrow::Borrow;
 use dashmap::mapref::entry::Entry;
 use dashmap::mapref::one::RefMut;
 use dashmap::DashMap;
+use instant::{Duration, Instant};
 use libp2p::{identify, swarm::ConnectionId, Multiaddr, PeerId};
 use rand::seq::SliceRandom;
 use serde::Serialize;
 use smallvec::SmallVec;
 use tokio::sync::watch;
 
+/// Weight of the newest measurement in the moving averages of the peer's throughput.
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+/// For how long the peer is avoided after a failed `header-ex` request.
+const FAILED_PEER_COOLDOWN: Duration = Duration::from_secs(30);
+
 /// Keeps track various information about peers.
 #[derive(Debug)]
 pub struct PeerTracker {
@@ -27,12 +33,33 @@ pub struct PeerTrackerInfo {
     pub num_connected_trusted_peers: u64,
 }
 
+/// Observed performance of the `header-ex` requests, averaged over the connected peers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HeaderExThroughput {
+    /// Number of the connected peers with the performance measured.
+    pub num_peers: usize,
+    /// Average number of headers received per second from a single peer.
+    pub headers_per_second: f64,
+    /// Average time of a single request.
+    pub latency: Duration,
+}
+
 #[derive(Debug)]
 struct PeerInfo {
     state: PeerState,
     addrs: SmallVec<[Multiaddr; 4]>,
     connections: SmallVec<[ConnectionId; 1]>,
     trusted: bool,
+    blocked_until: Option<Instant>,
+    throughput: Option<PeerThroughput>,
+    failed_at: Option<Instant>,
+}
+
+/// Moving averages of the peer's `header-ex` responses.
+#[derive(Debug, Clone, Copy)]
+struct PeerThroughput {
+    headers_per_second: f64,
+    latency: Duration,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +74,16 @@ impl PeerInfo {
     fn is_connected(&self) -> bool {
         matches!(self.state, PeerState::Connected | PeerState::Identified)
     }
+
+    fn is_blocked(&self) -> bool {
+        self.blocked_until
+            .is_some_and(|blocked_until| blocked_until > Instant::now())
+    }
+
+    fn has_failed_recently(&self) -> bool {
+        self.failed_at
+            .is_some_and(|failed_at| failed_at.elapsed() < FAILED_PEER_COOLDOWN)
+    }
 }
 
 impl PeerTracker {
@@ -79,6 +116,9 @@ impl PeerTracker {
                     addrs: SmallVec::new(),
                     connections: SmallVec::new(),
                     trusted: false,
+                    blocked_until: None,
+                    throughput: None,
+                    failed_at: None,
                 });
                 true
             }
@@ -95,6 +135,9 @@ impl PeerTracker {
             addrs: SmallVec::new(),
             connections: SmallVec::new(),
             trusted: false,
+            blocked_until: None,
+            throughput: None,
+            failed_at: None,
         })
     }
 
@@ -204,6 +247,94 @@ impl PeerTracker {
         peer_info.state = PeerState::Identified;
     }
 
+    /// Penalizes the peer for misbehaving, blocking it for the given `duration`.
+    ///
+    /// Trusted peers are never blocked. Returns `true` if peer was blocked.
+    pub fn penalize(&self, peer: PeerId, duration: Duration) -> bool {
+        let mut peer_info = self.get(peer);
+
+        if peer_info.trusted {
+            return false;
+        }
+
+        peer_info.blocked_until = Some(Instant::now() + duration);
+        true
+    }
+
+    /// Returns true if peer is blocked because of a penalty.
+    pub fn is_blocked(&self, peer: PeerId) -> bool {
+        self.peers
+            .get(&peer)
+            .is_some_and(|peer_info| peer_info.is_blocked())
+    }
+
+    /// Records a successful `header-ex` response of the peer with `amount` of headers,
+    /// received `elapsed` after sending the request.
+    pub fn record_response(&self, peer: PeerId, amount: u64, elapsed: Duration) {
+        let mut peer_info = self.get(peer);
+
+        let elapsed = elapsed.max(Duration::from_millis(1));
+        let headers_per_second = amount as f64 / elapsed.as_secs_f64();
+
+        peer_info.throughput = Some(match peer_info.throughput {
+            Some(prev) => PeerThroughput {
+                headers_per_second: THROUGHPUT_SMOOTHING * headers_per_second
+                    + (1.0 - THROUGHPUT_SMOOTHING) * prev.headers_per_second,
+                latency: elapsed.mul_f64(THROUGHPUT_SMOOTHING)
+                    + prev.latency.mul_f64(1.0 - THROUGHPUT_SMOOTHING),
+            },
+            None => PeerThroughput {
+                headers_per_second,
+                latency: elapsed,
+            },
+        });
+        peer_info.failed_at = None;
+    }
+
+    /// Records a failed `header-ex` request to the peer.
+    ///
+    /// The peer is avoided by [`PeerTracker::best_peer`] for a while after the failure.
+    pub fn record_failure(&self, peer: PeerId) {
+        let mut peer_info = self.get(peer);
+
+        if let Some(throughput) = &mut peer_info.throughput {
+            throughput.headers_per_second /= 2.0;
+        }
+        peer_info.failed_at = Some(Instant::now());
+    }
+
+    /// Returns the `header-ex` performance averaged over the connected peers,
+    /// or `None` if it wasn't measured for any of them yet.
+    pub fn header_ex_throughput(&self) -> Option<HeaderExThroughput> {
+        let mut num_peers = 0;
+        let mut headers_per_second = 0.0;
+        let mut latency = Duration::ZERO;
+
+        for pair in self.peers.iter() {
+            let peer_info = pair.value();
+
+            if !peer_info.is_connected() || peer_info.is_blocked() {
+                continue;
+            }
+
+            if let Some(throughput) = peer_info.throughput {
+                num_peers += 1;
+                headers_per_second += throughput.headers_per_second;
+                latency += throughput.latency;
+            }
+        }
+
+        if num_peers == 0 {
+            return None;
+        }
+
+        Some(HeaderExThroughput {
+            num_peers,
+            headers_per_second: headers_per_second / num_peers as f64,
+            latency: latency / num_peers as u32,
+        })
+    }
+
     /// Returns true if peer is connected.
     pub fn is_connected(&self, peer: PeerId) -> bool {
         self.get(peer).is_connected()
@@ -229,21 +360,53 @@ impl PeerTracker {
     }
 
     /// Returns one of the best peers.
+    ///
+    /// Peers are chosen randomly, weighted by their measured `header-ex` throughput,
+    /// avoiding the ones that failed recently if possible.
     pub fn best_peer(&self) -> Option<PeerId> {
         const MAX_PEER_SAMPLE: usize = 128;
 
-        // TODO: Implement peer score and return the best.
-        let mut peers = self
-            .peers
-            .iter()
-            .filter(|pair| pair.value().is_connected())
-            .take(MAX_PEER_SAMPLE)
-            .map(|pair| pair.key().to_owned())
-            .collect::<SmallVec<[_; MAX_PEER_SAMPLE]>>();
+        let mut peers = SmallVec::<[_; MAX_PEER_SAMPLE]>::new();
+        let mut failed_peers = SmallVec::<[_; MAX_PEER_SAMPLE]>::new();
+
+        for pair in self.peers.iter() {
+            let peer_info = pair.value();
+
+            if !peer_info.is_connected() || peer_info.is_blocked() {
+                continue;
+            }
+
+            let candidate = (
+                pair.key().to_owned(),
+                peer_info.throughput.map(|t| t.headers_per_second),
+            );
+
+            if peer_info.has_failed_recently() {
+                failed_peers.push(candidate);
+            } else {
+                peers.push(candidate);
+            }
+
+            if peers.len() == MAX_PEER_SAMPLE {
+                break;
+            }
+        }
 
-        peers.shuffle(&mut rand::thread_rng());
+        if peers.is_empty() {
+            peers = failed_peers;
+        }
 
-        peers.first().copied()
+        // Peers that weren't measured yet are weighted as the average one
+        let measured = peers.iter().filter_map(|(_, weight)| *weight);
+        let (sum, count) = measured.fold((0.0, 0), |(sum, count), w| (sum + w, count + 1));
+        let default_weight = if count > 0 { sum / count as f64 } else { 1.0 };
+
+        peers
+            .choose_weighted(&mut rand::thread_rng(), |(_, weight)| {
+                weight.unwrap_or(default_weight).max(f64::MIN_POSITIVE)
+            })
+            .ok()
+            .map(|(peer, _)| *peer)
     }
 
     /// Returns up to N amount of best peers.
@@ -251,7 +414,7 @@ impl PeerTracker {
         // TODO: Implement peer score and return the best N peers.
         self.peers
             .iter()
-            .filter(|pair| pair.value().is_connected())
+            .filter(|pair| pair.value().is_connected() && !pair.value().is_blocked())
             .take(limit)
             .map(|pair| pair.key().to_owned())
             // collect instead of returning an iter to not block the dashmap
@@ -362,4 +525,74 @@ mod tests {
         assert_eq!(info.num_connected_peers, 1);
         assert_eq!(info.num_connected_trusted_peers, 0);
     }
+
+    #[test]
+    fn penalize() {
+        let tracker = PeerTracker::new();
+        let peer = PeerId::random();
+        let trusted_peer = PeerId::random();
+
+        tracker.set_connected(peer, ConnectionId::new_unchecked(1), None);
+        tracker.set_trusted(trusted_peer, true);
+        tracker.set_connected(trusted_peer, ConnectionId::new_unchecked(2), None);
+
+        assert!(tracker.penalize(peer, Duration::from_secs(60)));
+        assert!(!tracker.penalize(trusted_peer, Duration::from_secs(60)));
+
+        assert!(tracker.is_blocked(peer));
+        assert!(!tracker.is_blocked(trusted_peer));
+        assert_eq!(tracker.best_n_peers(10), [trusted_peer]);
+        assert_eq!(tracker.best_peer(), Some(trusted_peer));
+
+        assert!(tracker.penalize(peer, Duration::ZERO));
+        assert!(!tracker.is_blocked(peer));
+    }
+
+    #[test]
+    fn header_ex_throughput() {
+        let tracker = PeerTracker::new();
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+
+        tracker.set_connected(peer1, ConnectionId::new_unchecked(1), None);
+        tracker.set_connected(peer2, ConnectionId::new_unchecked(2), None);
+        assert_eq!(tracker.header_ex_throughput(), None);
+
+        tracker.record_response(peer1, 100, Duration::from_secs(1));
+        tracker.record_response(peer2, 300, Duration::from_secs(3));
+
+        let throughput = tracker.header_ex_throughput().unwrap();
+        assert_eq!(throughput.num_peers, 2);
+        assert_eq!(throughput.headers_per_second, 100.0);
+        assert_eq!(throughput.latency, Duration::from_secs(2));
+
+        tracker.set_maybe_disconnected(peer2, ConnectionId::new_unchecked(2));
+
+        let throughput = tracker.header_ex_throughput().unwrap();
+        assert_eq!(throughput.num_peers, 1);
+        assert_eq!(throughput.latency, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn best_peer_avoids_failed_peers() {
+        let tracker = PeerTracker::new();
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+
+        tracker.set_connected(peer1, ConnectionId::new_unchecked(1), None);
+        tracker.set_connected(peer2, ConnectionId::new_unchecked(2), None);
+
+        tracker.record_failure(peer1);
+
+        for _ in 0..10 {
+            assert_eq!(tracker.best_peer(), Some(peer2));
+        }
+
+        // If all the peers failed, any of them can be retried
+        tracker.record_failure(peer2);
+        assert!(tracker.best_peer().is_some());
+
+        tracker.record_response(peer1, 10, Duration::from_secs(1));
+        assert_eq!(tracker.best_peer(), Some(peer1));
+    }
 }
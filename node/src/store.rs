@@ -5,10 +5,12 @@ use std::io::Cursor;
 use std::ops::{Bound, RangeBounds, RangeInclusive};
 
 use async_trait::async_trait;
+use celestia_proto::header::pb::ExtendedHeader as RawExtendedHeader;
 use celestia_tendermint_proto::Protobuf;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
+use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -16,14 +18,18 @@ use thiserror::Error;
 pub use in_memory_store::InMemoryStore;
 #[cfg(target_arch = "wasm32")]
 pub use indexed_db_store::IndexedDbStore;
+pub use integrity::{IntegrityIssue, IntegrityReport};
 #[cfg(not(target_arch = "wasm32"))]
 pub use redb_store::RedbStore;
 #[cfg(not(target_arch = "wasm32"))]
 pub use sled_store::SledStore;
 
+pub mod archive;
 mod in_memory_store;
 #[cfg(target_arch = "wasm32")]
 mod indexed_db_store;
+mod integrity;
+mod migrations;
 #[cfg(not(target_arch = "wasm32"))]
 mod redb_store;
 #[cfg(not(target_arch = "wasm32"))]
@@ -42,6 +48,64 @@ pub struct SamplingMetadata {
     /// List of CIDs used, when decision to accept or reject the header was taken. Can be used
     /// to remove associated data from Blockstore, when cleaning up the old ExtendedHeaders
     pub cids_sampled: Vec<Cid>,
+
+    /// Sampled CIDs paired with the axis along which they were proven.
+    ///
+    /// CIDs of which the axis is unknown, e.g. sampled before the axes were recorded,
+    /// are only in `cids_sampled`.
+    #[serde(default)]
+    pub proof_axes: Vec<(Cid, AxisType)>,
+}
+
+impl SamplingMetadata {
+    /// Returns the sampled CIDs paired with the axis along which they were proven.
+    ///
+    /// CIDs sampled before the axes were recorded are paired with [`AxisType::Row`], as back
+    /// then the samples were served only by the Go nodes, which prove them along the rows.
+    pub fn samples(&self) -> Vec<(Cid, AxisType)> {
+        self.cids_sampled
+            .iter()
+            .map(|cid| {
+                let axis = self
+                    .proof_axes
+                    .iter()
+                    .find(|(sampled, _)| sampled == cid)
+                    .map_or(AxisType::Row, |(_, axis)| *axis);
+                (*cid, axis)
+            })
+            .collect()
+    }
+
+    /// Merge the newly sampled CIDs, paired with their proof axes, into the metadata.
+    pub(crate) fn update(&mut self, accepted: bool, samples: Vec<(Cid, AxisType)>) {
+        self.accepted = accepted;
+
+        for (cid, axis) in samples {
+            if !self.cids_sampled.contains(&cid) {
+                self.cids_sampled.push(cid);
+            }
+
+            if !self.proof_axes.iter().any(|(sampled, _)| *sampled == cid) {
+                self.proof_axes.push((cid, axis));
+            }
+        }
+    }
+}
+
+/// Two validly signed headers of the same height, committed by the same validator set.
+///
+/// It proves that the validators equivocated and the chain forked, so the network
+/// can no longer be trusted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForkEvidence {
+    /// The header that was seen first.
+    pub header: ExtendedHeader,
+    /// The peer that provided `header`, unknown if it was already in the store.
+    pub peer: Option<PeerId>,
+    /// The header conflicting with `header`.
+    pub conflicting_header: ExtendedHeader,
+    /// The peer that provided `conflicting_header`.
+    pub conflicting_peer: Option<PeerId>,
 }
 
 type Result<T, E = StoreError> = std::result::Result<T, E>;
@@ -70,36 +134,15 @@ pub trait Store: Send + Sync + Debug {
     /// If end of the range is unbounded, the last returned header will be the last header in the
     /// store.
     ///
+    /// Headers are read at once, with a single range scan, rather than one by one.
+    ///
     /// # Errors
     ///
     /// If range contains a height of a header that is not found in the store or [`RangeBounds`]
     /// cannot be converted to a valid range.
     async fn get_range<R>(&self, range: R) -> Result<Vec<ExtendedHeader>>
     where
-        R: RangeBounds<u64> + Send,
-    {
-        let head_height = self.head_height().await?;
-        let range = to_headers_range(range, head_height)?;
-
-        let amount = if range.is_empty() {
-            0
-        } else {
-            range.end() - range.start() + 1 // add one as it's inclusive
-        };
-
-        let mut headers = Vec::with_capacity(
-            amount
-                .try_into()
-                .map_err(|_| StoreError::InvalidHeadersRange)?,
-        );
-
-        for height in range {
-            let header = self.get_by_height(height).await?;
-            headers.push(header);
-        }
-
-        Ok(headers)
-    }
+        R: RangeBounds<u64> + Send;
 
     /// Returns the highest known height.
     async fn head_height(&self) -> Result<u64>;
@@ -110,6 +153,18 @@ pub trait Store: Send + Sync + Debug {
     /// Returns true if height exists in the store.
     async fn has_at(&self, height: u64) -> bool;
 
+    /// Returns the ranges of the stored headers, in ascending order.
+    ///
+    /// Headers are kept continuous from the genesis to the head, so there is at most
+    /// a single range.
+    async fn get_stored_header_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        match self.head_height().await {
+            Ok(head_height) => Ok(vec![1..=head_height]),
+            Err(StoreError::NotFound) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Append single header maintaining continuity from the genesis to the head.
     ///
     /// # Note
@@ -122,15 +177,16 @@ pub trait Store: Send + Sync + Debug {
 
     /// Sets or updates sampling result for the header.
     ///
-    /// In case of update, provided CID list is appended onto the existing one, as not to lose
-    /// references to previously sampled blocks.
+    /// Each sampled CID is paired with the axis along which it was proven. In case of update,
+    /// provided samples are appended onto the existing ones, as not to lose references to
+    /// previously sampled blocks.
     ///
     /// Returns next unsampled header or error, if occured
     async fn update_sampling_metadata(
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64>;
 
     /// Gets the sampling metadata for the height.
@@ -141,17 +197,34 @@ pub trait Store: Send + Sync + Debug {
     /// `Ok(None)` indicates that header is in the store but sampling metadata is not set yet.
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>>;
 
+    /// Persists the evidence of a fork found in the network.
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()>;
+
+    /// Returns all the persisted fork evidence, in the order of insertion.
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>>;
+
     /// Append a range of headers maintaining continuity from the genesis to the head.
     ///
+    /// Headers are appended atomically, either all of them are inserted or none of them.
+    ///
     /// # Note
     ///
     /// This method does not validate or verify that `headers` are indeed correct.
-    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
-        for header in headers.into_iter() {
-            self.append_single_unchecked(header).await?;
-        }
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()>;
+
+    /// Removes all the headers above `height`, together with their sampling metadata,
+    /// making `height` the new head.
+    ///
+    /// The lowest unsampled height is recomputed from the remaining sampling metadata.
+    async fn truncate(&self, height: u64) -> Result<()>;
 
-        Ok(())
+    /// Walks all the headers, verifying each against its predecessor, and cross-checks them
+    /// with the hash index, the head height and the lowest unsampled height.
+    ///
+    /// Inconsistencies are reported rather than returned as errors, and can be repaired with
+    /// [`Store::truncate`] to [`IntegrityReport::last_consistent_height`].
+    async fn check_integrity(&self) -> Result<IntegrityReport> {
+        integrity::check_integrity(self).await
     }
 
     /// Append single header maintaining continuity from the genesis to the head.
@@ -257,12 +330,24 @@ struct RawSamplingMetadata {
 
     #[prost(message, repeated, tag = "2")]
     cids_sampled: Vec<Vec<u8>>,
+
+    #[prost(message, repeated, tag = "3")]
+    proof_axes: Vec<RawProofAxis>,
+}
+
+#[derive(Message)]
+struct RawProofAxis {
+    #[prost(bytes = "vec", tag = "1")]
+    cid: Vec<u8>,
+
+    #[prost(int32, tag = "2")]
+    axis: i32,
 }
 
 impl Protobuf<RawSamplingMetadata> for SamplingMetadata {}
 
 impl TryFrom<RawSamplingMetadata> for SamplingMetadata {
-    type Error = cid::Error;
+    type Error = StoreError;
 
     fn try_from(item: RawSamplingMetadata) -> Result<Self, Self::Error> {
         let cids_sampled = item
@@ -272,11 +357,25 @@ impl TryFrom<RawSamplingMetadata> for SamplingMetadata {
                 let buffer = Cursor::new(cid);
                 Cid::read_bytes(buffer)
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<_, _>>()
+            .map_err(|e| StoreError::StoredDataError(e.to_string()))?;
+
+        let proof_axes = item
+            .proof_axes
+            .into_iter()
+            .map(|proof_axis| {
+                let cid = Cid::read_bytes(Cursor::new(proof_axis.cid))
+                    .map_err(|e| StoreError::StoredDataError(e.to_string()))?;
+                let axis = u8::try_from(proof_axis.axis)
+                    .map_err(|_| celestia_types::Error::InvalidAxis(proof_axis.axis))?;
+                Ok((cid, AxisType::try_from(axis)?))
+            })
+            .collect::<Result<_, StoreError>>()?;
 
         Ok(SamplingMetadata {
             accepted: item.accepted,
             cids_sampled,
+            proof_axes,
         })
     }
 }
@@ -285,13 +384,99 @@ impl From<SamplingMetadata> for RawSamplingMetadata {
     fn from(item: SamplingMetadata) -> Self {
         let cids_sampled = item.cids_sampled.iter().map(|cid| cid.to_bytes()).collect();
 
+        let proof_axes = item
+            .proof_axes
+            .into_iter()
+            .map(|(cid, axis)| RawProofAxis {
+                cid: cid.to_bytes(),
+                axis: axis as i32,
+            })
+            .collect();
+
         RawSamplingMetadata {
             accepted: item.accepted,
             cids_sampled,
+            proof_axes,
         }
     }
 }
 
+#[derive(Message)]
+struct RawForkEvidence {
+    #[prost(message, optional, tag = "1")]
+    header: Option<RawExtendedHeader>,
+
+    #[prost(bytes = "vec", optional, tag = "2")]
+    peer: Option<Vec<u8>>,
+
+    #[prost(message, optional, tag = "3")]
+    conflicting_header: Option<RawExtendedHeader>,
+
+    #[prost(bytes = "vec", optional, tag = "4")]
+    conflicting_peer: Option<Vec<u8>>,
+}
+
+impl Protobuf<RawForkEvidence> for ForkEvidence {}
+
+impl TryFrom<RawForkEvidence> for ForkEvidence {
+    type Error = StoreError;
+
+    fn try_from(item: RawForkEvidence) -> Result<Self, Self::Error> {
+        let header = |raw: Option<RawExtendedHeader>| -> Result<ExtendedHeader> {
+            let raw = raw.ok_or_else(|| {
+                StoreError::StoredDataError("fork evidence without a header".to_string())
+            })?;
+            Ok(raw.try_into()?)
+        };
+        let peer = |raw: Option<Vec<u8>>| -> Result<Option<PeerId>> {
+            raw.map(|bytes| {
+                PeerId::from_bytes(&bytes).map_err(|e| StoreError::StoredDataError(e.to_string()))
+            })
+            .transpose()
+        };
+
+        Ok(ForkEvidence {
+            header: header(item.header)?,
+            peer: peer(item.peer)?,
+            conflicting_header: header(item.conflicting_header)?,
+            conflicting_peer: peer(item.conflicting_peer)?,
+        })
+    }
+}
+
+impl From<ForkEvidence> for RawForkEvidence {
+    fn from(item: ForkEvidence) -> Self {
+        RawForkEvidence {
+            header: Some(item.header.into()),
+            peer: item.peer.map(|peer| peer.to_bytes()),
+            conflicting_header: Some(item.conflicting_header.into()),
+            conflicting_peer: item.conflicting_peer.map(|peer| peer.to_bytes()),
+        }
+    }
+}
+
+/// Check that the headers continue the store's head and each other.
+fn check_continuity(head_height: u64, headers: &[ExtendedHeader]) -> Result<()> {
+    let mut prev_height = head_height;
+
+    for header in headers {
+        let height = header.height().value();
+
+        // A light check before checking the whole map
+        if prev_height > 0 && height <= prev_height {
+            return Err(StoreError::HeightExists(height));
+        }
+
+        if prev_height + 1 != height {
+            return Err(StoreError::NonContinuousAppend(prev_height, height));
+        }
+
+        prev_height = height;
+    }
+
+    Ok(())
+}
+
 /// a helper function to convert any kind of range to the inclusive range of header heights.
 fn to_headers_range(bounds: impl RangeBounds<u64>, last_index: u64) -> Result<RangeInclusive<u64>> {
     let start = match bounds.start_bound() {
@@ -664,6 +849,69 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_failed_append_range_is_atomic<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut s = s;
+        let mut gen = fill_store(&mut s, 10).await;
+        let mut hs = gen.next_many(6);
+
+        // remove height 14
+        let removed = hs.remove(3);
+
+        s.append_unchecked(hs.clone()).await.unwrap_err();
+
+        // none of the headers preceding the gap were inserted
+        assert_eq!(s.head_height().await.unwrap(), 10);
+        assert!(!s.has_at(11).await);
+        assert!(!s.has(&hs[0].hash()).await);
+
+        hs.insert(3, removed);
+        s.append_unchecked(hs).await.unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 16);
+    }
+
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_get_range<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut s = s;
+        fill_store(&mut s, 20).await;
+
+        let all = s.get_range(..).await.unwrap();
+        assert_eq!(all.len(), 20);
+        for (header, height) in all.iter().zip(1..) {
+            assert_eq!(header.height().value(), height);
+            assert_eq!(header, &s.get_by_height(height).await.unwrap());
+        }
+
+        let part = s.get_range(5..10).await.unwrap();
+        assert_eq!(part, all[4..9]);
+
+        let tail = s.get_range(15..).await.unwrap();
+        assert_eq!(tail, all[14..]);
+
+        assert!(matches!(
+            s.get_range(15..=21).await,
+            Err(StoreError::NotFound)
+        ));
+    }
+
     #[rstest]
     #[case::in_memory(new_in_memory_store())]
     #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
@@ -806,9 +1054,12 @@ mod tests {
         let cid2 = "bafkreieq5jui4j25lacwomsqgjeswwl3y5zcdrresptwgmfylxo2depppq"
             .parse()
             .unwrap();
+        let cid3 = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"
+            .parse()
+            .unwrap();
 
         store
-            .update_sampling_metadata(1, false, vec![cid0])
+            .update_sampling_metadata(1, false, vec![(cid0, AxisType::Row)])
             .await
             .unwrap();
         assert_eq!(store.next_unsampled_height().await.unwrap(), 2);
@@ -822,9 +1073,10 @@ mod tests {
         let sampling_data = store.get_sampling_metadata(1).await.unwrap().unwrap();
         assert!(!sampling_data.accepted);
         assert_eq!(sampling_data.cids_sampled, vec![cid0]);
+        assert_eq!(sampling_data.proof_axes, vec![(cid0, AxisType::Row)]);
 
         store
-            .update_sampling_metadata(1, true, vec![cid1])
+            .update_sampling_metadata(1, true, vec![(cid1, AxisType::Col)])
             .await
             .unwrap();
         assert_eq!(store.next_unsampled_height().await.unwrap(), 2);
@@ -832,9 +1084,14 @@ mod tests {
         let sampling_data = store.get_sampling_metadata(1).await.unwrap().unwrap();
         assert!(sampling_data.accepted);
         assert_eq!(sampling_data.cids_sampled, vec![cid0, cid1]);
+        assert_eq!(
+            sampling_data.proof_axes,
+            vec![(cid0, AxisType::Row), (cid1, AxisType::Col)]
+        );
 
+        // Axes of CIDs sampled before aren't overwritten
         store
-            .update_sampling_metadata(1, true, vec![cid0, cid2])
+            .update_sampling_metadata(1, true, vec![(cid0, AxisType::Col), (cid2, AxisType::Row)])
             .await
             .unwrap();
         assert_eq!(store.next_unsampled_height().await.unwrap(), 2);
@@ -842,6 +1099,54 @@ mod tests {
         let sampling_data = store.get_sampling_metadata(1).await.unwrap().unwrap();
         assert!(sampling_data.accepted);
         assert_eq!(sampling_data.cids_sampled, vec![cid0, cid1, cid2]);
+        assert_eq!(
+            sampling_data.proof_axes,
+            vec![
+                (cid0, AxisType::Row),
+                (cid1, AxisType::Col),
+                (cid2, AxisType::Row)
+            ]
+        );
+
+        store
+            .update_sampling_metadata(1, true, vec![(cid1, AxisType::Row), (cid3, AxisType::Col)])
+            .await
+            .unwrap();
+
+        let sampling_data = store.get_sampling_metadata(1).await.unwrap().unwrap();
+        assert_eq!(sampling_data.cids_sampled, vec![cid0, cid1, cid2, cid3]);
+        assert_eq!(
+            sampling_data.proof_axes,
+            vec![
+                (cid0, AxisType::Row),
+                (cid1, AxisType::Col),
+                (cid2, AxisType::Row),
+                (cid3, AxisType::Col)
+            ]
+        );
+        assert_eq!(sampling_data.samples(), sampling_data.proof_axes);
+    }
+
+    #[test]
+    async fn samples_of_unknown_axes() {
+        let cid0 = "zdpuAyvkgEDQm9TenwGkd5eNaosSxjgEYd8QatfPetgB1CdEZ"
+            .parse()
+            .unwrap();
+        let cid1 = "zb2rhe5P4gXftAwvA4eXQ5HJwsER2owDyS9sKaQRRVQPn93bA"
+            .parse()
+            .unwrap();
+
+        // Sampled before the axes were recorded
+        let metadata = SamplingMetadata {
+            accepted: true,
+            cids_sampled: vec![cid0, cid1],
+            proof_axes: vec![(cid1, AxisType::Col)],
+        };
+
+        assert_eq!(
+            metadata.samples(),
+            vec![(cid0, AxisType::Row), (cid1, AxisType::Col)]
+        );
     }
 
     #[rstest]
@@ -867,17 +1172,18 @@ mod tests {
         .iter()
         .map(|s| s.parse().unwrap())
         .collect();
+        let samples: Vec<_> = cids.iter().map(|cid| (*cid, AxisType::Row)).collect();
 
         store
-            .update_sampling_metadata(1, true, cids.clone())
+            .update_sampling_metadata(1, true, samples.clone())
             .await
             .unwrap();
         store
-            .update_sampling_metadata(2, true, cids[0..1].to_vec())
+            .update_sampling_metadata(2, true, samples[0..1].to_vec())
             .await
             .unwrap();
         store
-            .update_sampling_metadata(4, false, cids[3..].to_vec())
+            .update_sampling_metadata(4, false, samples[3..].to_vec())
             .await
             .unwrap();
         store
@@ -919,6 +1225,150 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_check_integrity<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let report = s.check_integrity().await.unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.head_height, 0);
+
+        let mut s = s;
+        fill_store(&mut s, 20).await;
+        for height in 1..=5 {
+            s.update_sampling_metadata(height, true, vec![])
+                .await
+                .unwrap();
+        }
+
+        let report = s.check_integrity().await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.head_height, 20);
+        assert_eq!(report.last_consistent_height, 20);
+        assert_eq!(report.next_unsampled_height, 6);
+    }
+
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_check_integrity_broken_chain<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut s = s;
+        fill_store(&mut s, 20).await;
+
+        // header from another chain is appended without verification
+        let mut other_gen = ExtendedHeaderGenerator::new();
+        let foreign = other_gen.next_many(21).pop().unwrap();
+        s.append_single_unchecked(foreign).await.unwrap();
+
+        let report = s.check_integrity().await.unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.head_height, 21);
+        assert_eq!(report.last_consistent_height, 20);
+        assert_eq!(report.issues, [IntegrityIssue::BrokenChain(21)]);
+
+        s.truncate(report.last_consistent_height).await.unwrap();
+        assert!(s.check_integrity().await.unwrap().is_consistent());
+    }
+
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_truncate<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut s = s;
+        fill_store(&mut s, 20).await;
+        for height in 1..=15 {
+            s.update_sampling_metadata(height, true, vec![])
+                .await
+                .unwrap();
+        }
+        let removed = s.get_range(11..).await.unwrap();
+
+        s.truncate(10).await.unwrap();
+
+        assert_eq!(s.head_height().await.unwrap(), 10);
+        assert_eq!(s.next_unsampled_height().await.unwrap(), 11);
+        assert!(!s.has_at(11).await);
+        assert!(!s.has(&removed[0].hash()).await);
+        assert!(s.check_integrity().await.unwrap().is_consistent());
+
+        // removed headers can be appended again
+        s.append_unchecked(removed).await.unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 20);
+        assert!(s.get_sampling_metadata(11).await.unwrap().is_none());
+        assert_eq!(s.next_unsampled_height().await.unwrap(), 11);
+    }
+
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_fork_evidence<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut fork_gen = gen.fork();
+
+        assert!(s.get_fork_evidence().await.unwrap().is_empty());
+
+        let evidence = (0..3)
+            .map(|i| ForkEvidence {
+                header: gen.next(),
+                peer: (i != 0).then(PeerId::random),
+                conflicting_header: fork_gen.next(),
+                conflicting_peer: Some(PeerId::random()),
+            })
+            .collect::<Vec<_>>();
+
+        for evidence in &evidence {
+            s.insert_fork_evidence(evidence.clone()).await.unwrap();
+        }
+
+        assert_eq!(s.get_fork_evidence().await.unwrap(), evidence);
+    }
+
+    #[rstest]
+    #[case::in_memory(new_in_memory_store())]
+    #[cfg_attr(not(target_arch = "wasm32"), case::sled(new_sled_store()))]
+    #[cfg_attr(not(target_arch = "wasm32"), case::redb(new_redb_store()))]
+    #[cfg_attr(target_arch = "wasm32", case::indexed_db(new_indexed_db_store()))]
+    #[self::test]
+    async fn test_stored_header_ranges<S: Store>(
+        #[case]
+        #[future(awt)]
+        s: S,
+    ) {
+        let mut s = s;
+        assert!(s.get_stored_header_ranges().await.unwrap().is_empty());
+
+        fill_store(&mut s, 10).await;
+        assert_eq!(s.get_stored_header_ranges().await.unwrap(), vec![1..=10]);
+    }
+
     /// Fills an empty store
     async fn fill_store<S: Store>(store: &mut S, amount: u64) -> ExtendedHeaderGenerator {
         assert!(!store.has_at(1).await, "Store is not empty");
@@ -0,0 +1,526 @@
+//! Portable archive of the [`ExtendedHeader`]s and their [`SamplingMetadata`].
+//!
+//! Archive allows moving a synchronized header chain between the stores, e.g. to seed
+//! a fresh node without fetching all the headers from the network again.
+//!
+//! # Format
+//!
+//! The archive starts with the [`MAGIC`] bytes followed by a single byte of the format
+//! version. Then it contains a record for each header, in ascending order of heights:
+//!
+//! - header length as big endian `u32`, followed by the protobuf encoded [`ExtendedHeader`]
+//! - `0` if the header has no sampling metadata, or `1` followed by the metadata length as
+//!   big endian `u32` and the protobuf encoded [`SamplingMetadata`]
+
+use std::ops::RangeBounds;
+
+use celestia_tendermint_proto::Protobuf;
+use celestia_types::hash::Hash;
+use celestia_types::ExtendedHeader;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::store::{to_headers_range, SamplingMetadata, Store, StoreError};
+use crate::utils::validate_headers;
+
+/// Bytes identifying the headers archive.
+pub const MAGIC: &[u8; 8] = b"LUMINAHA";
+
+/// Current version of the archive format.
+pub const VERSION: u8 = 1;
+
+/// Maximum size of a single encoded header or sampling metadata.
+const MAX_RECORD_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Number of headers read from the store or appended to it at once.
+const BATCH_SIZE: u64 = 512;
+
+type Result<T, E = ArchiveError> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when exporting or importing the archive.
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    /// Reading or writing the archive failed.
+    #[error("Archive I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An error propagated from the [`Store`].
+    #[error(transparent)]
+    Store(#[from] StoreError),
+
+    /// An error propagated from the [`celestia_types`], e.g. when verification fails.
+    #[error(transparent)]
+    CelestiaTypes(#[from] celestia_types::Error),
+
+    /// Data doesn't start with the archive [`MAGIC`].
+    #[error("Not a headers archive")]
+    InvalidMagic,
+
+    /// Archive was created with an unsupported format version.
+    #[error("Unsupported archive version {0}, expected {VERSION}")]
+    UnsupportedVersion(u8),
+
+    /// Header in the archive differs from the one already in the store.
+    #[error("Header at height {0} differs from the one in the store")]
+    Conflict(u64),
+
+    /// Archive imported to the empty store doesn't start with the genesis header of the network.
+    #[error("Archive doesn't start with the network's genesis header")]
+    InvalidGenesis,
+
+    /// Record in the archive is malformed.
+    #[error("Invalid archive record: {0}")]
+    InvalidRecord(String),
+}
+
+/// Write the headers from the given range of heights, with their sampling metadata, to the archive.
+///
+/// Returns the number of exported headers.
+pub async fn export<S, R, W>(store: &S, range: R, mut writer: W) -> Result<u64>
+where
+    S: Store,
+    R: RangeBounds<u64> + Send,
+    W: AsyncWrite + Unpin,
+{
+    let head_height = store.head_height().await?;
+    let range = to_headers_range(range, head_height)?;
+
+    writer.write_all(MAGIC).await?;
+    writer.write_all(&[VERSION]).await?;
+
+    let mut exported = 0;
+    let mut start = *range.start();
+
+    while start <= *range.end() {
+        let end = (start + BATCH_SIZE - 1).min(*range.end());
+
+        for header in store.get_range(start..=end).await? {
+            let height = header.height().value();
+            let metadata = store.get_sampling_metadata(height).await?;
+
+            // encoding to protobuf is infallible
+            write_record(&mut writer, &header.encode_vec().unwrap()).await?;
+
+            match metadata {
+                Some(metadata) => {
+                    writer.write_all(&[1]).await?;
+                    write_record(&mut writer, &metadata.encode_vec().unwrap()).await?;
+                }
+                None => writer.write_all(&[0]).await?,
+            }
+
+            exported += 1;
+        }
+
+        start = end + 1;
+    }
+
+    writer.flush().await?;
+
+    Ok(exported)
+}
+
+/// Read the headers from the archive and append them to the store.
+///
+/// Headers are verified against the store's head. If the store is empty, the archive
+/// must start with the genesis header, matching the `genesis_hash` if one is provided.
+/// Archive may overlap with the headers already in the store, but it must not leave
+/// a gap after the store's head.
+///
+/// Returns the number of imported headers.
+pub async fn import<S, R>(store: &S, mut reader: R, genesis_hash: Option<Hash>) -> Result<u64>
+where
+    S: Store,
+    R: AsyncRead + Unpin,
+{
+    let mut magic = [0u8; MAGIC.len()];
+    reader.read_exact(&mut magic).await?;
+
+    if &magic != MAGIC {
+        return Err(ArchiveError::InvalidMagic);
+    }
+
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version).await?;
+
+    if version[0] != VERSION {
+        return Err(ArchiveError::UnsupportedVersion(version[0]));
+    }
+
+    let mut imported = 0;
+    let mut batch = Vec::new();
+
+    while let Some(header) = read_record(&mut reader).await? {
+        let header = ExtendedHeader::decode_vec(&header)
+            .map_err(|e| ArchiveError::InvalidRecord(e.to_string()))?;
+
+        let mut has_metadata = [0u8; 1];
+        reader.read_exact(&mut has_metadata).await?;
+
+        let metadata = match has_metadata[0] {
+            0 => None,
+            1 => {
+                let metadata = read_record(&mut reader).await?.ok_or_else(|| {
+                    ArchiveError::InvalidRecord("missing sampling metadata".into())
+                })?;
+                let metadata = SamplingMetadata::decode_vec(&metadata)
+                    .map_err(|e| ArchiveError::InvalidRecord(e.to_string()))?;
+                Some(metadata)
+            }
+            n => {
+                return Err(ArchiveError::InvalidRecord(format!(
+                    "invalid sampling metadata marker {n}"
+                )))
+            }
+        };
+
+        batch.push((header, metadata));
+
+        if batch.len() as u64 == BATCH_SIZE {
+            imported += append_batch(store, std::mem::take(&mut batch), genesis_hash).await?;
+        }
+    }
+
+    imported += append_batch(store, batch, genesis_hash).await?;
+
+    Ok(imported)
+}
+
+async fn append_batch<S>(
+    store: &S,
+    batch: Vec<(ExtendedHeader, Option<SamplingMetadata>)>,
+    genesis_hash: Option<Hash>,
+) -> Result<u64>
+where
+    S: Store,
+{
+    if batch.is_empty() {
+        return Ok(0);
+    }
+
+    let (headers, metadata): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+
+    validate_headers(&headers).await?;
+
+    let mut imported = 0;
+
+    // The store may be appended concurrently, e.g. by the syncer of a running node,
+    // so we retry if we lose the race.
+    loop {
+        let head = match store.get_head().await {
+            Ok(head) => Some(head),
+            Err(StoreError::NotFound) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let head_height = head.as_ref().map_or(0, |head| head.height().value());
+
+        // Skip the headers which are already in the store, making sure they are the same
+        let known = headers
+            .iter()
+            .take_while(|header| header.height().value() <= head_height)
+            .count();
+
+        for header in &headers[..known] {
+            let height = header.height().value();
+
+            if store.get_by_height(height).await?.hash() != header.hash() {
+                return Err(ArchiveError::Conflict(height));
+            }
+        }
+
+        let new_headers = &headers[known..];
+
+        if new_headers.is_empty() {
+            break;
+        }
+
+        match head {
+            Some(head) => head.verify_adjacent_range(new_headers)?,
+            // Empty store, the archive must be anchored at the genesis
+            None => {
+                let genesis = &new_headers[0];
+
+                if genesis.height().value() != 1 {
+                    return Err(ArchiveError::InvalidGenesis);
+                }
+
+                match genesis_hash {
+                    Some(hash) if hash != genesis.hash() => {
+                        return Err(ArchiveError::InvalidGenesis)
+                    }
+                    Some(_) => (),
+                    None => warn!("Genesis hash is not set, trusting the archive's genesis."),
+                }
+
+                genesis.verify_adjacent_range(&new_headers[1..])?
+            }
+        }
+
+        match store.append_unchecked(new_headers.to_vec()).await {
+            Ok(()) => {
+                imported = new_headers.len() as u64;
+                break;
+            }
+            Err(StoreError::NonContinuousAppend(..) | StoreError::HeightExists(_)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    for (header, metadata) in headers.iter().zip(metadata) {
+        let height = header.height().value();
+
+        // Don't override the sampling done by the node itself
+        if let Some(metadata) = metadata {
+            if store.get_sampling_metadata(height).await?.is_none() {
+                store
+                    .update_sampling_metadata(height, metadata.accepted, metadata.samples())
+                    .await?;
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+async fn write_record<W>(writer: &mut W, data: &[u8]) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let len = u32::try_from(data.len())
+        .ok()
+        .filter(|len| *len <= MAX_RECORD_SIZE)
+        .ok_or_else(|| ArchiveError::InvalidRecord(format!("record of {} bytes", data.len())))?;
+
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(data).await?;
+
+    Ok(())
+}
+
+/// Read the length prefixed record, returning `None` on the end of the archive.
+async fn read_record<R>(reader: &mut R) -> Result<Option<Vec<u8>>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len = [0u8; 4];
+
+    // Archive may only end at the record boundary
+    if reader.read(&mut len[..1]).await? == 0 {
+        return Ok(None);
+    }
+    reader.read_exact(&mut len[1..]).await?;
+
+    let len = u32::from_be_bytes(len);
+
+    if len > MAX_RECORD_SIZE {
+        return Err(ArchiveError::InvalidRecord(format!(
+            "record of {len} bytes"
+        )));
+    }
+
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data).await?;
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{async_test, gen_filled_store};
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::AxisType;
+    use cid::Cid;
+
+    async fn export_to_vec<S: Store>(store: &S, range: impl RangeBounds<u64> + Send) -> Vec<u8> {
+        let mut archive = Vec::new();
+        export(store, range, &mut archive).await.unwrap();
+        archive
+    }
+
+    #[async_test]
+    async fn export_import_roundtrip() {
+        let (store, _) = gen_filled_store(1200);
+        let cid: Cid = "bafkreieq5jui4j25lacwomsqgjeswwl3y5zcdrresptwgmfylxo2depppq"
+            .parse()
+            .unwrap();
+        store
+            .update_sampling_metadata(1, true, vec![(cid, AxisType::Col)])
+            .await
+            .unwrap();
+        store
+            .update_sampling_metadata(2, false, vec![])
+            .await
+            .unwrap();
+
+        let archive = export_to_vec(&store, ..).await;
+
+        let genesis_hash = store.get_by_height(1).await.unwrap().hash();
+        let imported_store = InMemoryStore::new();
+        let imported = import(&imported_store, &archive[..], Some(genesis_hash))
+            .await
+            .unwrap();
+
+        assert_eq!(imported, 1200);
+        assert_eq!(imported_store.head_height().await.unwrap(), 1200);
+        assert_eq!(
+            imported_store.get_by_height(777).await.unwrap(),
+            store.get_by_height(777).await.unwrap()
+        );
+
+        let metadata = imported_store
+            .get_sampling_metadata(1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(metadata.accepted);
+        assert_eq!(metadata.cids_sampled, vec![cid]);
+        assert_eq!(metadata.proof_axes, vec![(cid, AxisType::Col)]);
+
+        let metadata = imported_store
+            .get_sampling_metadata(2)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!metadata.accepted);
+        assert!(imported_store
+            .get_sampling_metadata(3)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(imported_store.next_unsampled_height().await.unwrap(), 3);
+    }
+
+    #[async_test]
+    async fn import_continues_store() {
+        let (store, _) = gen_filled_store(100);
+        let archive = export_to_vec(&store, 51..).await;
+
+        let partial_store = InMemoryStore::new();
+        partial_store
+            .append_unchecked(store.get_range(..=50).await.unwrap())
+            .await
+            .unwrap();
+
+        let imported = import(&partial_store, &archive[..], None).await.unwrap();
+
+        assert_eq!(imported, 50);
+        assert_eq!(partial_store.head_height().await.unwrap(), 100);
+    }
+
+    #[async_test]
+    async fn import_overlapping() {
+        let (store, _) = gen_filled_store(100);
+        let archive = export_to_vec(&store, ..).await;
+
+        let partial_store = InMemoryStore::new();
+        partial_store
+            .append_unchecked(store.get_range(..=50).await.unwrap())
+            .await
+            .unwrap();
+
+        let imported = import(&partial_store, &archive[..], None).await.unwrap();
+
+        assert_eq!(imported, 50);
+        assert_eq!(partial_store.head_height().await.unwrap(), 100);
+
+        // importing again is a no-op
+        let imported = import(&partial_store, &archive[..], None).await.unwrap();
+        assert_eq!(imported, 0);
+    }
+
+    #[async_test]
+    async fn import_not_adjacent() {
+        let (store, _) = gen_filled_store(100);
+        let archive = export_to_vec(&store, 60..).await;
+
+        let partial_store = InMemoryStore::new();
+        partial_store
+            .append_unchecked(store.get_range(..=50).await.unwrap())
+            .await
+            .unwrap();
+
+        let e = import(&partial_store, &archive[..], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, ArchiveError::CelestiaTypes(_)));
+        assert_eq!(partial_store.head_height().await.unwrap(), 50);
+    }
+
+    #[async_test]
+    async fn import_different_chain() {
+        let (store, _) = gen_filled_store(100);
+        let archive = export_to_vec(&store, 51..).await;
+
+        let other_store = InMemoryStore::new();
+        let mut gen = ExtendedHeaderGenerator::new();
+        other_store
+            .append_unchecked(gen.next_many(50))
+            .await
+            .unwrap();
+
+        let e = import(&other_store, &archive[..], None).await.unwrap_err();
+        assert!(matches!(e, ArchiveError::CelestiaTypes(_)));
+        assert_eq!(other_store.head_height().await.unwrap(), 50);
+
+        let archive = export_to_vec(&store, ..).await;
+        let e = import(&other_store, &archive[..], None).await.unwrap_err();
+        assert!(matches!(e, ArchiveError::Conflict(1)));
+    }
+
+    #[async_test]
+    async fn import_untrusted_genesis() {
+        let (store, _) = gen_filled_store(100);
+        let (other_store, _) = gen_filled_store(100);
+        let genesis_hash = store.get_by_height(1).await.unwrap().hash();
+
+        let empty_store = InMemoryStore::new();
+
+        let archive = export_to_vec(&other_store, ..).await;
+        let e = import(&empty_store, &archive[..], Some(genesis_hash))
+            .await
+            .unwrap_err();
+        assert!(matches!(e, ArchiveError::InvalidGenesis));
+
+        let archive = export_to_vec(&store, 51..).await;
+        let e = import(&empty_store, &archive[..], Some(genesis_hash))
+            .await
+            .unwrap_err();
+        assert!(matches!(e, ArchiveError::InvalidGenesis));
+
+        let e = import(&empty_store, &archive[..], None).await.unwrap_err();
+        assert!(matches!(e, ArchiveError::InvalidGenesis));
+
+        assert!(empty_store.head_height().await.is_err());
+    }
+
+    #[async_test]
+    async fn import_invalid_archive() {
+        let store = InMemoryStore::new();
+
+        let e = import(&store, &b"NOTANARCHIVE"[..], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, ArchiveError::InvalidMagic));
+
+        let mut archive = MAGIC.to_vec();
+        archive.push(VERSION + 1);
+        let e = import(&store, &archive[..], None).await.unwrap_err();
+        assert!(matches!(e, ArchiveError::UnsupportedVersion(_)));
+    }
+
+    #[async_test]
+    async fn import_truncated_archive() {
+        let (store, _) = gen_filled_store(10);
+        let mut archive = export_to_vec(&store, ..).await;
+        archive.truncate(archive.len() - 10);
+
+        let imported_store = InMemoryStore::new();
+        let e = import(&imported_store, &archive[..], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, ArchiveError::Io(_)));
+    }
+}
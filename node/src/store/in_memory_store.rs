@@ -1,16 +1,21 @@
+use std::collections::HashSet;
+use std::ops::RangeBounds;
 use std::pin::pin;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
 use async_trait::async_trait;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use tokio::sync::Notify;
 use tracing::{debug, info};
 
-use crate::store::{Result, SamplingMetadata, Store, StoreError};
+use crate::store::{
+    check_continuity, to_headers_range, ForkEvidence, Result, SamplingMetadata, Store, StoreError,
+};
 
 /// A non-persistent in memory [`Store`] implementation.
 #[derive(Debug)]
@@ -27,6 +32,10 @@ pub struct InMemoryStore {
     lowest_unsampled_height: AtomicU64,
     /// Notify when a new header is added
     header_added_notifier: Notify,
+    /// Serializes the appends
+    append_lock: Mutex<()>,
+    /// Evidence of the forks found in the network
+    fork_evidence: Mutex<Vec<ForkEvidence>>,
 }
 
 impl InMemoryStore {
@@ -39,6 +48,8 @@ impl InMemoryStore {
             head_height: AtomicU64::new(0),
             lowest_unsampled_height: AtomicU64::new(1),
             header_added_notifier: Notify::new(),
+            append_lock: Mutex::new(()),
+            fork_evidence: Mutex::new(Vec::new()),
         }
     }
 
@@ -59,46 +70,52 @@ impl InMemoryStore {
     }
 
     pub(crate) fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let hash = header.hash();
-        let height = header.height().value();
+        self.append_unchecked(vec![header])
+    }
+
+    fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        // Appends are serialized, so that the whole batch can be checked before it is inserted
+        let _guard = self.append_lock.lock().expect("append lock poisoned");
+
         let head_height = self.get_head_height().unwrap_or(0);
+        check_continuity(head_height, &headers)?;
 
-        // A light check before checking the whole map
-        if head_height > 0 && height <= head_height {
-            return Err(StoreError::HeightExists(height));
-        }
+        let mut hashes = HashSet::with_capacity(headers.len());
+
+        for header in &headers {
+            let hash = header.hash();
 
-        // Check if it's continuous before checking the whole map.
-        if head_height + 1 != height {
-            return Err(StoreError::NonContinuousAppend(head_height, height));
+            if !hashes.insert(hash) || self.headers.contains_key(&hash) {
+                return Err(StoreError::HashExists(hash));
+            }
         }
 
-        // lock both maps to ensure consistency
-        // this shouldn't deadlock as long as we don't hold references across awaits if any
-        // https://github.com/xacrimon/dashmap/issues/233
-        let hash_entry = self.headers.entry(hash);
-        let height_entry = self.height_to_hash.entry(height);
+        let Some(new_head_height) = headers.last().map(|h| h.height().value()) else {
+            return Ok(());
+        };
 
-        if matches!(hash_entry, Entry::Occupied(_)) {
-            return Err(StoreError::HashExists(hash));
-        }
+        for header in headers {
+            let hash = header.hash();
+            let height = header.height().value();
 
-        if matches!(height_entry, Entry::Occupied(_)) {
-            // Reaching this point means another thread won the race and
-            // there is a new head already.
-            return Err(StoreError::HeightExists(height));
+            debug!("Inserting header {hash} with height {height}");
+            self.headers.insert(hash, header);
+            self.height_to_hash.insert(height, hash);
         }
 
-        debug!("Inserting header {hash} with height {height}");
-        hash_entry.insert(header);
-        height_entry.insert(hash);
-
-        self.head_height.store(height, Ordering::Release);
+        self.head_height.store(new_head_height, Ordering::Release);
         self.header_added_notifier.notify_waiters();
 
         Ok(())
     }
 
+    fn get_range(&self, range: impl RangeBounds<u64>) -> Result<Vec<ExtendedHeader>> {
+        let head_height = self.get_head_height()?;
+        let range = to_headers_range(range, head_height)?;
+
+        range.map(|height| self.get_by_height(height)).collect()
+    }
+
     fn get_head(&self) -> Result<ExtendedHeader> {
         let head_height = self.get_head_height()?;
         self.get_by_height(head_height)
@@ -140,29 +157,25 @@ impl InMemoryStore {
             .ok_or(StoreError::LostHash(hash))
     }
 
-    fn update_sampling_metadata(&self, height: u64, accepted: bool, cids: Vec<Cid>) -> Result<u64> {
+    fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        samples: Vec<(Cid, AxisType)>,
+    ) -> Result<u64> {
         if !self.contains_height(height) {
             return Err(StoreError::NotFound);
         }
 
         let new_inserted = match self.sampling_data.entry(height) {
             Entry::Vacant(entry) => {
-                entry.insert(SamplingMetadata {
-                    accepted,
-                    cids_sampled: cids,
-                });
+                let mut metadata = SamplingMetadata::default();
+                metadata.update(accepted, samples);
+                entry.insert(metadata);
                 true
             }
             Entry::Occupied(mut entry) => {
-                let metadata = entry.get_mut();
-                metadata.accepted = accepted;
-
-                for cid in &cids {
-                    if !metadata.cids_sampled.contains(cid) {
-                        metadata.cids_sampled.push(cid.to_owned());
-                    }
-                }
-
+                entry.get_mut().update(accepted, samples);
                 false
             }
         };
@@ -196,6 +209,27 @@ impl InMemoryStore {
         }
     }
 
+    fn truncate(&self, height: u64) -> Result<()> {
+        let _guard = self.append_lock.lock().expect("append lock poisoned");
+        let height = height.min(self.head_height.load(Ordering::Acquire));
+
+        self.height_to_hash.retain(|&h, hash| {
+            if h > height {
+                self.headers.remove(hash);
+                false
+            } else {
+                true
+            }
+        });
+        self.sampling_data.retain(|&h, _| h <= height);
+
+        self.head_height.store(height, Ordering::Release);
+        self.lowest_unsampled_height.store(1, Ordering::Release);
+        self.update_lowest_unsampled_height()?;
+
+        Ok(())
+    }
+
     fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         if !self.contains_height(height) {
             return Err(StoreError::NotFound);
@@ -251,10 +285,25 @@ impl Store for InMemoryStore {
         self.contains_height(height)
     }
 
+    async fn get_range<R>(&self, range: R) -> Result<Vec<ExtendedHeader>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.get_range(range)
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         self.append_single_unchecked(header)
     }
 
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        self.append_unchecked(headers)
+    }
+
+    async fn truncate(&self, height: u64) -> Result<()> {
+        self.truncate(height)
+    }
+
     async fn next_unsampled_height(&self) -> Result<u64> {
         Ok(self.get_next_unsampled_height())
     }
@@ -263,14 +312,30 @@ impl Store for InMemoryStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
-        self.update_sampling_metadata(height, accepted, cids)
+        self.update_sampling_metadata(height, accepted, samples)
     }
 
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         self.get_sampling_metadata(height)
     }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        self.fork_evidence
+            .lock()
+            .expect("fork evidence lock poisoned")
+            .push(evidence);
+        Ok(())
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        Ok(self
+            .fork_evidence
+            .lock()
+            .expect("fork evidence lock poisoned")
+            .clone())
+    }
 }
 
 impl Default for InMemoryStore {
@@ -290,6 +355,13 @@ impl Clone for InMemoryStore {
                 self.lowest_unsampled_height.load(Ordering::Acquire),
             ),
             header_added_notifier: Notify::new(),
+            append_lock: Mutex::new(()),
+            fork_evidence: Mutex::new(
+                self.fork_evidence
+                    .lock()
+                    .expect("fork evidence lock poisoned")
+                    .clone(),
+            ),
         }
     }
 }
@@ -1,26 +1,49 @@
 use std::cell::RefCell;
 use std::convert::Infallible;
+use std::ops::RangeBounds;
 use std::pin::pin;
 
 use async_trait::async_trait;
 use celestia_tendermint_proto::Protobuf;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
+use futures::future::LocalBoxFuture;
 use rexie::{Direction, Index, KeyRange, ObjectStore, Rexie, TransactionMode};
 use send_wrapper::SendWrapper;
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::{from_value, to_value};
 use tokio::sync::Notify;
 
-use crate::store::{Result, SamplingMetadata, Store, StoreError};
+use crate::store::migrations::{self, Migration};
+use crate::store::{
+    check_continuity, to_headers_range, ForkEvidence, Result, SamplingMetadata, Store, StoreError,
+};
 
 /// indexeddb version, needs to be incremented on every schema schange
-const DB_VERSION: u32 = 2;
+const DB_VERSION: u32 = 3;
+
+type MigrationFn = fn(&Rexie) -> LocalBoxFuture<'_, Result<()>>;
+
+/// Upgrade steps from the previous versions of the schema, run after the object stores
+/// are upgraded.
+const MIGRATIONS: [Migration<MigrationFn>; 2] = [
+    Migration {
+        version: 2,
+        description: "add sampling metadata",
+        run: migrate_to_v2,
+    },
+    Migration {
+        version: 3,
+        description: "add fork evidence",
+        run: migrate_to_v3,
+    },
+];
 
 // Data stores (SQL table analogue) used in IndexedDb
 const HEADER_STORE_NAME: &str = "headers";
 const SAMPLING_STORE_NAME: &str = "sampling";
+const FORK_EVIDENCE_STORE_NAME: &str = "fork_evidence";
 
 // Additional indexes set on HEADER_STORE, for querying by height and hash
 const HASH_INDEX_NAME: &str = "hash";
@@ -29,6 +52,9 @@ const HEIGHT_INDEX_NAME: &str = "height";
 // Maximumum number of headers to fetch at once when updating sampling height from database
 const MAX_UNSAMPLED_HEIGHT_SCAN_BATCH_SIZE: u32 = 100;
 
+// Maximumum number of headers to fetch at once when removing them from the top of database
+const MAX_TRUNCATE_BATCH_SIZE: u32 = 100;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ExtendedHeaderEntry {
     // We use those fields as indexes, names need to match ones in `add_index`
@@ -37,6 +63,11 @@ struct ExtendedHeaderEntry {
     header: Vec<u8>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct ForkEvidenceEntry {
+    evidence: Vec<u8>,
+}
+
 /// A [`Store`] implementation based on a `IndexedDB` browser database.
 #[derive(Debug)]
 pub struct IndexedDbStore {
@@ -50,6 +81,21 @@ pub struct IndexedDbStore {
 impl IndexedDbStore {
     /// Create or open a persistent store.
     pub async fn new(name: &str) -> Result<IndexedDbStore> {
+        // Object stores are upgraded when the database is opened with the new version,
+        // so the version of the existing database needs to be checked beforehand.
+        let existing = Rexie::builder(name)
+            .build()
+            .await
+            .map_err(|e| StoreError::OpenFailed(e.to_string()))?;
+        // A database without any object stores was just created
+        let found_version = (!existing.store_names().is_empty()).then(|| existing.version() as u64);
+        existing.close();
+
+        let pending = match found_version {
+            Some(found) => migrations::pending(&MIGRATIONS, found, u64::from(DB_VERSION))?,
+            None => &[],
+        };
+
         let rexie = Rexie::builder(name)
             .version(DB_VERSION)
             .add_object_store(
@@ -61,10 +107,15 @@ impl IndexedDbStore {
                     .add_index(Index::new(HEIGHT_INDEX_NAME, "height").unique(true)),
             )
             .add_object_store(ObjectStore::new(SAMPLING_STORE_NAME))
+            .add_object_store(ObjectStore::new(FORK_EVIDENCE_STORE_NAME).auto_increment(true))
             .build()
             .await
             .map_err(|e| StoreError::OpenFailed(e.to_string()))?;
 
+        for migration in pending {
+            (migration.run)(&rexie).await?;
+        }
+
         let db_head = match get_head_from_database(&rexie).await {
             Ok(v) => Some(v),
             Err(StoreError::NotFound) => None,
@@ -147,61 +198,112 @@ impl IndexedDbStore {
             .map_err(|e| StoreError::CelestiaTypes(e.into()))
     }
 
-    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let height = header.height().value();
-        let hash = header.hash();
+    async fn get_range(&self, range: impl RangeBounds<u64>) -> Result<Vec<ExtendedHeader>> {
+        let head_height = self.get_head_height()?;
+        let range = to_headers_range(range, head_height)?;
+        let (start, end) = range.into_inner();
 
-        let head_height = self.get_head_height().unwrap_or(0);
+        let tx = self
+            .db
+            .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let height_index = header_store.index(HEIGHT_INDEX_NAME)?;
+
+        let heights_range = KeyRange::bound(&to_value(&start)?, &to_value(&end)?, false, false)?;
+        let entries = height_index
+            .get_all(Some(&heights_range), None, None, Some(Direction::Next))
+            .await?;
+
+        let mut headers = Vec::with_capacity(entries.len());
+        let mut expected_height = start;
+
+        for (_, entry) in entries {
+            let entry = from_value::<ExtendedHeaderEntry>(entry)?;
 
-        // A light check before checking the whole map
-        if head_height > 0 && height <= head_height {
-            return Err(StoreError::HeightExists(height));
+            if entry.height != expected_height {
+                return Err(StoreError::LostHeight(expected_height));
+            }
+
+            let header = ExtendedHeader::decode(entry.header.as_ref())
+                .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+
+            headers.push(header);
+            expected_height += 1;
         }
 
-        // Check if it's continuous before checking the whole map.
-        if head_height + 1 != height {
-            return Err(StoreError::NonContinuousAppend(head_height, height));
+        if expected_height <= end {
+            return Err(StoreError::LostHeight(expected_height));
         }
 
+        Ok(headers)
+    }
+
+    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.append_unchecked(vec![header]).await
+    }
+
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let head_height = self.get_head_height().unwrap_or(0);
+
+        // Light checks before checking the whole map
+        check_continuity(head_height, &headers)?;
+
+        let Some(new_head) = headers.last().cloned() else {
+            return Ok(());
+        };
+
         let tx = self
             .db
             .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadWrite)?;
         let header_store = tx.store(HEADER_STORE_NAME)?;
-
         let height_index = header_store.index(HEIGHT_INDEX_NAME)?;
-        let jsvalue_height_key = KeyRange::only(&to_value(&height)?)?;
-        if height_index
-            .count(Some(&jsvalue_height_key))
-            .await
-            .unwrap_or(0)
-            != 0
-        {
-            return Err(StoreError::HeightExists(height));
-        }
-
         let hash_index = header_store.index(HASH_INDEX_NAME)?;
-        let jsvalue_hash_key = KeyRange::only(&to_value(&hash)?)?;
-        if hash_index.count(Some(&jsvalue_hash_key)).await.unwrap_or(0) != 0 {
-            return Err(StoreError::HashExists(hash));
-        }
 
-        // make sure Result is Infallible, we unwrap it later
-        let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
+        let mut entries = Vec::with_capacity(headers.len());
 
-        let header_height = header.height().value();
-        let header_entry = ExtendedHeaderEntry {
-            height: header_height,
-            hash: header.hash(),
-            header: serialized_header.unwrap(),
-        };
+        for header in headers {
+            let height = header.height().value();
+            let hash = header.hash();
+
+            let jsvalue_height_key = KeyRange::only(&to_value(&height)?)?;
+            if height_index
+                .count(Some(&jsvalue_height_key))
+                .await
+                .unwrap_or(0)
+                != 0
+            {
+                return Err(StoreError::HeightExists(height));
+            }
+
+            let jsvalue_hash_key = KeyRange::only(&to_value(&hash)?)?;
+            if hash_index.count(Some(&jsvalue_hash_key)).await.unwrap_or(0) != 0 {
+                return Err(StoreError::HashExists(hash));
+            }
+
+            // make sure Result is Infallible, we unwrap it later
+            let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
+
+            let header_entry = ExtendedHeaderEntry {
+                height,
+                hash,
+                header: serialized_header.unwrap(),
+            };
+
+            entries.push(to_value(&header_entry)?);
+        }
 
-        let jsvalue_header = to_value(&header_entry)?;
+        for jsvalue_header in &entries {
+            if let Err(e) = header_store.add(jsvalue_header, None).await {
+                // Don't leave the headers added so far in the store
+                tx.abort().await?;
+                return Err(e.into());
+            }
+        }
 
-        header_store.add(&jsvalue_header, None).await?;
         tx.commit().await?;
 
         // this shouldn't panic, we don't borrow across await points and wasm is single threaded
-        self.head.replace(Some(header));
+        self.head.replace(Some(new_head));
         self.header_added_notifier.notify_waiters();
 
         Ok(())
@@ -234,7 +336,7 @@ impl IndexedDbStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
         // quick check with contains_height, which uses cached head
         if !self.contains_height(height) {
@@ -249,23 +351,12 @@ impl IndexedDbStore {
         let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
 
         let previous_entry = sampling_store.get(&height_key).await?;
-        let new_entry = if previous_entry.is_falsy() {
-            SamplingMetadata {
-                accepted,
-                cids_sampled: cids,
-            }
+        let mut new_entry: SamplingMetadata = if previous_entry.is_falsy() {
+            SamplingMetadata::default()
         } else {
-            let mut value: SamplingMetadata = from_value(previous_entry)?;
-            value.accepted = accepted;
-
-            for cid in &cids {
-                if !value.cids_sampled.contains(cid) {
-                    value.cids_sampled.push(cid.to_owned());
-                }
-            }
-
-            value
+            from_value(previous_entry)?
         };
+        new_entry.update(accepted, samples);
 
         let metadata_jsvalue = to_value(&new_entry)?;
 
@@ -296,6 +387,64 @@ impl IndexedDbStore {
         }
     }
 
+    async fn truncate(&self, height: u64) -> Result<()> {
+        let tx = self.db.transaction(
+            &[HEADER_STORE_NAME, SAMPLING_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+
+        // Headers are keyed by an auto increment, which follows their heights
+        'remove_headers: loop {
+            let header_entries = header_store
+                .get_all(
+                    None,
+                    Some(MAX_TRUNCATE_BATCH_SIZE),
+                    None,
+                    Some(Direction::Prev),
+                )
+                .await?;
+
+            if header_entries.is_empty() {
+                break;
+            }
+
+            for (id, entry) in header_entries {
+                if from_value::<ExtendedHeaderEntry>(entry)?.height <= height {
+                    break 'remove_headers;
+                }
+
+                header_store.delete(&id).await?;
+            }
+        }
+
+        let removed_range = KeyRange::lower_bound(&to_value(&height)?, true)?;
+        let sampling_entries = sampling_store
+            .get_all(Some(&removed_range), None, None, None)
+            .await?;
+
+        for (height_key, _) in sampling_entries {
+            sampling_store.delete(&height_key).await?;
+        }
+
+        tx.commit().await?;
+
+        let head = match get_head_from_database(&self.db).await {
+            Ok(head) => Some(head),
+            Err(StoreError::NotFound) => None,
+            Err(e) => return Err(e),
+        };
+        let lowest_unsampled_height = get_next_unsampled_height_from_database(&self.db, 1).await?;
+
+        // this shouldn't panic, we don't borrow across await points and wasm is single threaded
+        self.head.replace(head);
+        self.lowest_unsampled_height
+            .replace(lowest_unsampled_height);
+
+        Ok(())
+    }
+
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         if !self.contains_height(height) {
             return Err(StoreError::NotFound);
@@ -315,6 +464,45 @@ impl IndexedDbStore {
 
         Ok(Some(from_value(sampling_entry)?))
     }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        // make sure Result is Infallible, we unwrap it later
+        let serialized_evidence: std::result::Result<_, Infallible> = evidence.encode_vec();
+        let entry = ForkEvidenceEntry {
+            evidence: serialized_evidence.unwrap(),
+        };
+
+        let tx = self
+            .db
+            .transaction(&[FORK_EVIDENCE_STORE_NAME], TransactionMode::ReadWrite)?;
+        let store = tx.store(FORK_EVIDENCE_STORE_NAME)?;
+
+        store.add(&to_value(&entry)?, None).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        let tx = self
+            .db
+            .transaction(&[FORK_EVIDENCE_STORE_NAME], TransactionMode::ReadOnly)?;
+        let store = tx.store(FORK_EVIDENCE_STORE_NAME)?;
+
+        // keys are auto incremented, so the evidence is returned in the order of insertion
+        let entries = store
+            .get_all(None, None, None, Some(Direction::Next))
+            .await?;
+
+        entries
+            .into_iter()
+            .map(|(_, entry)| {
+                let entry = from_value::<ForkEvidenceEntry>(entry)?;
+                ForkEvidence::decode(entry.evidence.as_ref())
+                    .map_err(|e| StoreError::StoredDataError(e.to_string()))
+            })
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -362,11 +550,29 @@ impl Store for IndexedDbStore {
         self.contains_height(height)
     }
 
+    async fn get_range<R>(&self, range: R) -> Result<Vec<ExtendedHeader>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let fut = SendWrapper::new(self.get_range(range));
+        fut.await
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         let fut = SendWrapper::new(self.append_single_unchecked(header));
         fut.await
     }
 
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let fut = SendWrapper::new(self.append_unchecked(headers));
+        fut.await
+    }
+
+    async fn truncate(&self, height: u64) -> Result<()> {
+        let fut = SendWrapper::new(self.truncate(height));
+        fut.await
+    }
+
     async fn next_unsampled_height(&self) -> Result<u64> {
         // this shouldn't panic, we don't borrow across await points and wasm is single threaded
         Ok(*self.lowest_unsampled_height.borrow())
@@ -376,9 +582,9 @@ impl Store for IndexedDbStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
-        let fut = SendWrapper::new(self.update_sampling_metadata(height, accepted, cids));
+        let fut = SendWrapper::new(self.update_sampling_metadata(height, accepted, samples));
         fut.await
     }
 
@@ -386,6 +592,16 @@ impl Store for IndexedDbStore {
         let fut = SendWrapper::new(self.get_sampling_metadata(height));
         fut.await
     }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        let fut = SendWrapper::new(self.insert_fork_evidence(evidence));
+        fut.await
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        let fut = SendWrapper::new(self.get_fork_evidence());
+        fut.await
+    }
 }
 
 impl From<rexie::Error> for StoreError {
@@ -404,6 +620,16 @@ impl From<serde_wasm_bindgen::Error> for StoreError {
     }
 }
 
+/// Version 2 adds the sampling metadata object store, with all the existing headers left
+/// unsampled, so there is no data to migrate.
+fn migrate_to_v2(_db: &Rexie) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async { Ok(()) })
+}
+
+fn migrate_to_v3(_db: &Rexie) -> LocalBoxFuture<'_, Result<()>> {
+    Box::pin(async { Ok(()) })
+}
+
 async fn get_head_from_database(db: &Rexie) -> Result<ExtendedHeader> {
     let tx = db.transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
     let store = tx.store(HEADER_STORE_NAME)?;
@@ -583,6 +809,26 @@ pub mod tests {
         ));
     }
 
+    #[named]
+    #[wasm_bindgen_test]
+    async fn refuses_newer_schema() {
+        let store_name = function_name!();
+        Rexie::delete(store_name).await.unwrap();
+
+        let rexie = Rexie::builder(store_name)
+            .version(DB_VERSION + 1)
+            .add_object_store(ObjectStore::new(HEADER_STORE_NAME))
+            .build()
+            .await
+            .unwrap();
+        rexie.close();
+
+        assert!(matches!(
+            IndexedDbStore::new(store_name).await,
+            Err(StoreError::OpenFailed(_))
+        ));
+    }
+
     mod migration_v1 {
         use super::*;
 
@@ -0,0 +1,204 @@
+//! Consistency checks of the [`Store`] contents.
+
+use std::fmt;
+
+use celestia_types::ExtendedHeader;
+use serde::{Deserialize, Serialize};
+
+use crate::store::{Result, Store, StoreError};
+
+/// Number of headers read at once while walking the store.
+const BATCH_SIZE: u64 = 512;
+
+/// Result of [`Store::check_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Head height recorded by the store, 0 for an empty store.
+    pub head_height: u64,
+    /// Highest height up to which all the headers are consistent, 0 if none are.
+    pub last_consistent_height: u64,
+    /// Lowest unsampled height recorded by the store.
+    pub next_unsampled_height: u64,
+    /// Inconsistencies found, ordered by height.
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Returns true if no inconsistencies were found.
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A single inconsistency found by [`Store::check_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityIssue {
+    /// Header within the head height can't be read.
+    MissingHeader(u64),
+    /// Header stored under a height has a different height.
+    HeightMismatch {
+        /// Height under which the header is stored.
+        height: u64,
+        /// Height of the header itself.
+        found: u64,
+    },
+    /// Header doesn't verify against its predecessor.
+    BrokenChain(u64),
+    /// Header can't be found by its hash, or a different header is found instead.
+    HashIndexMismatch(u64),
+    /// Header is stored above the head height.
+    HeaderAboveHead(u64),
+    /// Lowest unsampled height doesn't match the stored sampling metadata.
+    SamplingWatermark {
+        /// Lowest unsampled height recorded by the store.
+        recorded: u64,
+        /// Lowest unsampled height according to the sampling metadata.
+        expected: u64,
+    },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::MissingHeader(height) => write!(f, "header {height} is missing"),
+            IntegrityIssue::HeightMismatch { height, found } => {
+                write!(f, "header stored at {height} has height {found}")
+            }
+            IntegrityIssue::BrokenChain(height) => {
+                write!(f, "header {height} doesn't verify against its predecessor")
+            }
+            IntegrityIssue::HashIndexMismatch(height) => {
+                write!(f, "header {height} is not indexed by its hash")
+            }
+            IntegrityIssue::HeaderAboveHead(height) => {
+                write!(f, "header {height} is stored above the head")
+            }
+            IntegrityIssue::SamplingWatermark { recorded, expected } => {
+                write!(
+                    f,
+                    "lowest unsampled height is {recorded}, expected {expected}"
+                )
+            }
+        }
+    }
+}
+
+pub(crate) async fn check_integrity<S>(store: &S) -> Result<IntegrityReport>
+where
+    S: Store + ?Sized,
+{
+    let head_height = match store.head_height().await {
+        Ok(height) => height,
+        Err(StoreError::NotFound) => 0,
+        Err(e) => return Err(e),
+    };
+
+    let mut issues = Vec::new();
+    let mut last_consistent_height = 0;
+    let mut prev: Option<ExtendedHeader> = None;
+
+    for start in (1..=head_height).step_by(BATCH_SIZE as usize) {
+        let end = (start + BATCH_SIZE - 1).min(head_height);
+
+        for (height, header) in read_batch(store, start, end).await? {
+            let Some(header) = header else {
+                issues.push(IntegrityIssue::MissingHeader(height));
+                prev = None;
+                continue;
+            };
+
+            let issues_before = issues.len();
+
+            if header.height().value() != height {
+                issues.push(IntegrityIssue::HeightMismatch {
+                    height,
+                    found: header.height().value(),
+                });
+            }
+
+            // Headers following the missing ones can't be verified
+            if let Some(prev) = &prev {
+                if prev.verify(&header).is_err() {
+                    issues.push(IntegrityIssue::BrokenChain(height));
+                }
+            }
+
+            match store.get_by_hash(&header.hash()).await {
+                Ok(by_hash) if by_hash == header => {}
+                Ok(_) | Err(StoreError::NotFound) => {
+                    issues.push(IntegrityIssue::HashIndexMismatch(height));
+                }
+                Err(e) => return Err(e),
+            }
+
+            if issues.len() == issues_before && last_consistent_height + 1 == height {
+                last_consistent_height = height;
+            }
+
+            prev = Some(header);
+        }
+    }
+
+    if store.has_at(head_height + 1).await {
+        issues.push(IntegrityIssue::HeaderAboveHead(head_height + 1));
+    }
+
+    let next_unsampled_height = store.next_unsampled_height().await?;
+    let mut expected = 1;
+
+    while expected <= head_height {
+        match store.get_sampling_metadata(expected).await {
+            Ok(Some(_)) => expected += 1,
+            Ok(None) | Err(StoreError::NotFound) => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    if next_unsampled_height != expected {
+        issues.push(IntegrityIssue::SamplingWatermark {
+            recorded: next_unsampled_height,
+            expected,
+        });
+    }
+
+    Ok(IntegrityReport {
+        head_height,
+        last_consistent_height,
+        next_unsampled_height,
+        issues,
+    })
+}
+
+/// Reads headers in `start..=end`, at once if possible, or one by one to find the missing ones.
+async fn read_batch<S>(
+    store: &S,
+    start: u64,
+    end: u64,
+) -> Result<Vec<(u64, Option<ExtendedHeader>)>>
+where
+    S: Store + ?Sized,
+{
+    if let Ok(headers) = store.get_range(start..=end).await {
+        if headers.len() as u64 == end - start + 1 {
+            return Ok((start..=end).zip(headers.into_iter().map(Some)).collect());
+        }
+    }
+
+    let mut headers = Vec::with_capacity((end - start + 1) as usize);
+
+    for height in start..=end {
+        match store.get_by_height(height).await {
+            Ok(header) => headers.push((height, Some(header))),
+            Err(
+                StoreError::NotFound
+                | StoreError::LostHeight(_)
+                | StoreError::LostHash(_)
+                | StoreError::CelestiaTypes(_)
+                | StoreError::StoredDataError(_),
+            ) => headers.push((height, None)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(headers)
+}
@@ -0,0 +1,121 @@
+//! Schema versioning shared by the persistent [`Store`] backends.
+//!
+//! Every backend records the version of the schema it wrote the data with. When the store
+//! is opened, the upgrade steps newer than the recorded version are run in order, and then
+//! the current version is recorded. Databases written by a newer schema are refused.
+//!
+//! [`Store`]: crate::store::Store
+
+use tracing::info;
+
+use crate::store::{Result, StoreError};
+
+/// A single upgrade step, bringing the schema from `version - 1` to `version`.
+pub(crate) struct Migration<F> {
+    /// Version of the schema after running this step.
+    pub(crate) version: u64,
+    /// Short description of the change, used for logging.
+    pub(crate) description: &'static str,
+    /// The upgrade itself.
+    pub(crate) run: F,
+}
+
+/// Returns the migrations that need to run to upgrade the schema from `found`
+/// to `current` version, in the order they must be run.
+///
+/// `migrations` must be sorted by version, with no gaps, ending with the `current` version.
+pub(crate) fn pending<F>(
+    migrations: &[Migration<F>],
+    found: u64,
+    current: u64,
+) -> Result<&[Migration<F>]> {
+    debug_assert!(migrations
+        .iter()
+        .zip(migrations.iter().skip(1))
+        .all(|(prev, next)| prev.version + 1 == next.version));
+    debug_assert!(migrations.last().map_or(true, |m| m.version == current));
+
+    if found > current {
+        return Err(StoreError::OpenFailed(format!(
+            "Incompatible database schema; found {found}, expected {current} or older."
+        )));
+    }
+
+    let first_pending = migrations.partition_point(|m| m.version <= found);
+    let pending = &migrations[first_pending..];
+
+    // There must be a step for every version between the found and the current one
+    let oldest_upgradable = pending.first().map_or(current, |m| m.version - 1);
+
+    if oldest_upgradable != found {
+        return Err(StoreError::OpenFailed(format!(
+            "No migration path from database schema {found} to {current}."
+        )));
+    }
+
+    for migration in pending {
+        info!(
+            "Migrating store schema to version {}: {}",
+            migration.version, migration.description
+        );
+    }
+
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::async_test as test;
+
+    fn migrations() -> Vec<Migration<()>> {
+        (2..=4)
+            .map(|version| Migration {
+                version,
+                description: "test",
+                run: (),
+            })
+            .collect()
+    }
+
+    #[test]
+    async fn runs_all_newer_steps() {
+        let migrations = migrations();
+
+        let versions = |found| {
+            pending(&migrations, found, 4)
+                .unwrap()
+                .iter()
+                .map(|m| m.version)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(versions(1), [2, 3, 4]);
+        assert_eq!(versions(3), [4]);
+        assert!(versions(4).is_empty());
+    }
+
+    #[test]
+    async fn refuses_newer_schema() {
+        let migrations = migrations();
+
+        assert!(matches!(
+            pending(&migrations, 5, 4),
+            Err(StoreError::OpenFailed(_))
+        ));
+    }
+
+    #[test]
+    async fn refuses_schema_without_migration_path() {
+        let migrations = migrations();
+
+        assert!(matches!(
+            pending(&migrations, 0, 4),
+            Err(StoreError::OpenFailed(_))
+        ));
+        assert!(matches!(
+            pending(&[] as &[Migration<()>], 1, 2),
+            Err(StoreError::OpenFailed(_))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use std::ops::RangeBounds;
 use std::pin::pin;
 use std::sync::Arc;
 use std::{convert::Infallible, path::Path};
@@ -5,7 +6,7 @@ use std::{convert::Infallible, path::Path};
 use async_trait::async_trait;
 use celestia_tendermint_proto::Protobuf;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
 use redb::{
     CommitError, Database, ReadTransaction, ReadableTable, StorageError, Table, TableDefinition,
@@ -15,10 +16,18 @@ use tokio::sync::Notify;
 use tokio::task::spawn_blocking;
 use tracing::{debug, info};
 
-use crate::store::{Result, SamplingMetadata, Store, StoreError};
+use crate::store::migrations::{self, Migration};
+use crate::store::{
+    check_continuity, to_headers_range, ForkEvidence, Result, SamplingMetadata, Store, StoreError,
+};
 
 const SCHEMA_VERSION: u64 = 1;
 
+type MigrationFn = fn(&WriteTransaction) -> Result<()>;
+
+/// Upgrade steps from the previous versions of the schema.
+const MIGRATIONS: [Migration<MigrationFn>; 0] = [];
+
 const HEAD_HEIGHT_KEY: &[u8] = b"KEY.HEAD_HEIGHT";
 const NEXT_UNSAMPLED_HEIGHT_KEY: &[u8] = b"KEY.UNSAMPLED_HEIGHT";
 
@@ -26,6 +35,8 @@ const HEIGHTS_TABLE: TableDefinition<'static, &[u8], u64> = TableDefinition::new
 const HEADERS_TABLE: TableDefinition<'static, u64, &[u8]> = TableDefinition::new("STORE.HEADERS");
 const SAMPLING_METADATA_TABLE: TableDefinition<'static, u64, &[u8]> =
     TableDefinition::new("STORE.SAMPLING_METADATA");
+const FORK_EVIDENCE_TABLE: TableDefinition<'static, u64, &[u8]> =
+    TableDefinition::new("STORE.FORK_EVIDENCE");
 const SCHEMA_VERSION_TABLE: TableDefinition<'static, (), u64> =
     TableDefinition::new("STORE.SCHEMA_VERSION");
 
@@ -78,19 +89,15 @@ impl RedbStore {
                 let mut schema_version_table = tx.open_table(SCHEMA_VERSION_TABLE)?;
                 let schema_version = schema_version_table.get(())?.map(|guard| guard.value());
 
-                match schema_version {
-                    Some(schema_version) => {
-                        // TODO: When we schema we should migrate from older versions to newer ones.
-                        if schema_version != SCHEMA_VERSION {
-                            let e = format!("Incompatible database schema; found {schema_version}, expected {SCHEMA_VERSION}.");
-                            return Err(StoreError::OpenFailed(e));
-                        }
-                    }
-                    None => {
-                        schema_version_table.insert((), SCHEMA_VERSION)?;
+                // A new database is created with the current schema
+                if let Some(found) = schema_version {
+                    for migration in migrations::pending(&MIGRATIONS, found, SCHEMA_VERSION)? {
+                        (migration.run)(tx)?;
                     }
                 }
 
+                schema_version_table.insert((), SCHEMA_VERSION)?;
+
                 let mut heights_table = tx.open_table(HEIGHTS_TABLE)?;
 
                 if heights_table.get(HEAD_HEIGHT_KEY)?.is_none() {
@@ -101,6 +108,9 @@ impl RedbStore {
                     heights_table.insert(NEXT_UNSAMPLED_HEIGHT_KEY, 1)?;
                 }
 
+                // Make sure the table exists for the read transactions
+                tx.open_table(FORK_EVIDENCE_TABLE)?;
+
                 Ok(())
             })
             .await
@@ -237,43 +247,82 @@ impl RedbStore {
         .unwrap_or(false)
     }
 
+    async fn get_range(&self, range: impl RangeBounds<u64>) -> Result<Vec<ExtendedHeader>> {
+        let head_height = self.head_height().await?;
+        let range = to_headers_range(range, head_height)?;
+
+        self.read_tx(move |tx| {
+            let headers_table = tx.open_table(HEADERS_TABLE)?;
+            let (start, end) = range.into_inner();
+            let mut headers = Vec::with_capacity((end + 1).saturating_sub(start) as usize);
+            let mut expected_height = start;
+
+            for entry in headers_table.range(start..=end)? {
+                let (height, serialized) = entry?;
+
+                if height.value() != expected_height {
+                    return Err(StoreError::LostHeight(expected_height));
+                }
+
+                let header = ExtendedHeader::decode(serialized.value())
+                    .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+
+                headers.push(header);
+                expected_height += 1;
+            }
+
+            if expected_height <= end {
+                return Err(StoreError::LostHeight(expected_height));
+            }
+
+            Ok(headers)
+        })
+        .await
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.append_unchecked(vec![header]).await
+    }
+
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        if headers.is_empty() {
+            return Ok(());
+        }
+
         self.write_tx(move |tx| {
             let mut heights_table = tx.open_table(HEIGHTS_TABLE)?;
             let mut headers_table = tx.open_table(HEADERS_TABLE)?;
 
-            let hash = header.hash();
-            let height = header.height().value();
             let head_height = get_height(&heights_table, HEAD_HEIGHT_KEY)?;
 
-            // A light check before checking the whole map
-            if head_height > 0 && height <= head_height {
-                return Err(StoreError::HeightExists(height));
-            }
+            // Light checks before checking the whole map
+            check_continuity(head_height, &headers)?;
 
-            // Check if it's continuous before checking the whole map.
-            if head_height + 1 != height {
-                return Err(StoreError::NonContinuousAppend(head_height, height));
-            }
+            for header in &headers {
+                let hash = header.hash();
+                let height = header.height().value();
 
-            // make sure Result is Infallible and unwrap it later
-            let serialized_header: Result<_, Infallible> = header.encode_vec();
-            let serialized_header = serialized_header.unwrap();
-
-            if headers_table
-                .insert(height, &serialized_header[..])?
-                .is_some()
-            {
-                return Err(StoreError::HeightExists(height));
-            }
+                // make sure Result is Infallible and unwrap it later
+                let serialized_header: Result<_, Infallible> = header.encode_vec();
+                let serialized_header = serialized_header.unwrap();
+
+                if headers_table
+                    .insert(height, &serialized_header[..])?
+                    .is_some()
+                {
+                    return Err(StoreError::HeightExists(height));
+                }
 
-            if heights_table.insert(hash.as_bytes(), height)?.is_some() {
-                return Err(StoreError::HashExists(hash));
+                if heights_table.insert(hash.as_bytes(), height)?.is_some() {
+                    return Err(StoreError::HashExists(hash));
+                }
+
+                debug!("Inserted header {hash} with height {height}");
             }
 
-            heights_table.insert(HEAD_HEIGHT_KEY, height)?;
+            let new_head_height = headers.last().expect("headers not empty").height().value();
+            heights_table.insert(HEAD_HEIGHT_KEY, new_head_height)?;
 
-            debug!("Inserted header {hash} with height {height}");
             Ok(())
         })
         .await?;
@@ -287,7 +336,7 @@ impl RedbStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
         self.write_tx(move |tx| {
             let mut heights_table = tx.open_table(HEIGHTS_TABLE)?;
@@ -303,23 +352,8 @@ impl RedbStore {
             let previous = get_sampling_metadata(&sampling_metadata_table, height)?;
             let new_inserted = previous.is_none();
 
-            let entry = match previous {
-                Some(mut previous) => {
-                    previous.accepted = accepted;
-
-                    for cid in &cids {
-                        if !previous.cids_sampled.contains(cid) {
-                            previous.cids_sampled.push(cid.to_owned());
-                        }
-                    }
-
-                    previous
-                }
-                None => SamplingMetadata {
-                    accepted,
-                    cids_sampled: cids.clone(),
-                },
-            };
+            let mut entry = previous.unwrap_or_default();
+            entry.update(accepted, samples);
 
             // make sure Result is Infallible and unwrap it later
             let serialized: Result<_, Infallible> = entry.encode_vec();
@@ -337,6 +371,66 @@ impl RedbStore {
         .await
     }
 
+    async fn truncate(&self, height: u64) -> Result<()> {
+        self.write_tx(move |tx| {
+            let mut heights_table = tx.open_table(HEIGHTS_TABLE)?;
+            let mut headers_table = tx.open_table(HEADERS_TABLE)?;
+            let mut sampling_metadata_table = tx.open_table(SAMPLING_METADATA_TABLE)?;
+
+            let head_height = get_height(&heights_table, HEAD_HEIGHT_KEY)?;
+            let height = height.min(head_height);
+
+            headers_table.retain_in(height + 1.., |_, _| false)?;
+            sampling_metadata_table.retain_in(height + 1.., |_, _| false)?;
+            heights_table.retain(|key, value| {
+                key == HEAD_HEIGHT_KEY || key == NEXT_UNSAMPLED_HEIGHT_KEY || value <= height
+            })?;
+
+            heights_table.insert(HEAD_HEIGHT_KEY, height)?;
+            heights_table.insert(NEXT_UNSAMPLED_HEIGHT_KEY, 1)?;
+            update_sampling_height(&mut heights_table, &mut sampling_metadata_table)?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        self.write_tx(move |tx| {
+            let mut fork_evidence_table = tx.open_table(FORK_EVIDENCE_TABLE)?;
+
+            let next_id = match fork_evidence_table.last()? {
+                Some((id, _)) => id.value() + 1,
+                None => 0,
+            };
+
+            // make sure Result is Infallible and unwrap it later
+            let serialized: Result<_, Infallible> = evidence.encode_vec();
+            let serialized = serialized.unwrap();
+
+            fork_evidence_table.insert(next_id, &serialized[..])?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        self.read_tx(|tx| {
+            let fork_evidence_table = tx.open_table(FORK_EVIDENCE_TABLE)?;
+
+            fork_evidence_table
+                .iter()?
+                .map(|entry| {
+                    let (_, value) = entry?;
+                    ForkEvidence::decode(value.value())
+                        .map_err(|e| StoreError::StoredDataError(e.to_string()))
+                })
+                .collect()
+        })
+        .await
+    }
+
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         self.read_tx(move |tx| {
             let heights_table = tx.open_table(HEIGHTS_TABLE)?;
@@ -397,10 +491,25 @@ impl Store for RedbStore {
         self.contains_height(height).await
     }
 
+    async fn get_range<R>(&self, range: R) -> Result<Vec<ExtendedHeader>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.get_range(range).await
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         self.append_single_unchecked(header).await
     }
 
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        self.append_unchecked(headers).await
+    }
+
+    async fn truncate(&self, height: u64) -> Result<()> {
+        self.truncate(height).await
+    }
+
     async fn next_unsampled_height(&self) -> Result<u64> {
         self.get_next_unsampled_height().await
     }
@@ -409,14 +518,23 @@ impl Store for RedbStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
-        self.update_sampling_metadata(height, accepted, cids).await
+        self.update_sampling_metadata(height, accepted, samples)
+            .await
     }
 
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         self.get_sampling_metadata(height).await
     }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        self.insert_fork_evidence(evidence).await
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        self.get_fork_evidence().await
+    }
 }
 
 #[inline]
@@ -608,6 +726,28 @@ pub mod tests {
         assert_eq!(store1.head_height().await.unwrap(), 16);
     }
 
+    #[tokio::test]
+    async fn refuses_newer_schema() {
+        let db_dir = TempDir::with_prefix("lumina.store.test").unwrap();
+        let db = db_dir.path().join("db");
+
+        let store = create_store(Some(&db)).await;
+        store
+            .write_tx(|tx| {
+                let mut schema_version_table = tx.open_table(SCHEMA_VERSION_TABLE)?;
+                schema_version_table.insert((), SCHEMA_VERSION + 1)?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        drop(store);
+
+        assert!(matches!(
+            RedbStore::open(&db).await,
+            Err(StoreError::OpenFailed(_))
+        ));
+    }
+
     pub async fn create_store(path: Option<&Path>) -> RedbStore {
         match path {
             Some(path) => RedbStore::open(path).await.unwrap(),
@@ -1,12 +1,12 @@
 use std::convert::Infallible;
-use std::ops::Deref;
+use std::ops::{Deref, RangeBounds};
 use std::pin::pin;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use celestia_tendermint_proto::Protobuf;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader};
 use cid::Cid;
 use sled::transaction::{abort, ConflictableTransactionError, TransactionError, TransactionalTree};
 use sled::{Db, Error as SledError, Transactional, Tree};
@@ -14,13 +14,30 @@ use tokio::sync::Notify;
 use tokio::task::spawn_blocking;
 use tracing::{debug, info};
 
-use crate::store::{Result, SamplingMetadata, Store, StoreError};
+use crate::store::migrations::{self, Migration};
+use crate::store::{
+    check_continuity, to_headers_range, ForkEvidence, Result, SamplingMetadata, Store, StoreError,
+};
 
+/// Version of the schema written by this store.
+const SCHEMA_VERSION: u64 = 2;
+
+type MigrationFn = fn(&Db) -> Result<()>;
+
+/// Upgrade steps from the previous versions of the schema.
+const MIGRATIONS: [Migration<MigrationFn>; 1] = [Migration {
+    version: 2,
+    description: "add sampling metadata",
+    run: migrate_to_v2,
+}];
+
+const SCHEMA_VERSION_KEY: &[u8] = b"KEY.SCHEMA_VERSION";
 const HEAD_HEIGHT_KEY: &[u8] = b"KEY.HEAD_HEIGHT";
 const NEXT_UNSAMPLED_HEIGHT_KEY: &[u8] = b"KEY.UNSAMPLED_HEIGHT";
 const HASH_TREE_ID: &[u8] = b"HASH";
 const HEIGHT_TO_HASH_TREE_ID: &[u8] = b"HEIGHT";
 const HEIGHT_TO_METADATA_TREE_ID: &[u8] = b"METADATA";
+const FORK_EVIDENCE_TREE_ID: &[u8] = b"FORK_EVIDENCE";
 
 /// A [`Store`] implementation based on a [`sled`] database.
 #[derive(Debug)]
@@ -38,6 +55,8 @@ struct Inner {
     height_to_hash: Tree,
     /// sub-tree which maps header height to its metadata
     sampling_metadata: Tree,
+    /// sub-tree which maps an insertion id to the fork evidence
+    fork_evidence: Tree,
     /// Notify when a new header is added
     header_added_notifier: Notify,
 }
@@ -46,33 +65,29 @@ impl SledStore {
     /// Create or open a persistent store.
     pub async fn new(db: Db) -> Result<Self> {
         spawn_blocking(move || {
+            migrate(&db)?;
+
             let headers = db.open_tree(HASH_TREE_ID)?;
             let height_to_hash = db.open_tree(HEIGHT_TO_HASH_TREE_ID)?;
             let sampling_metadata = db.open_tree(HEIGHT_TO_METADATA_TREE_ID)?;
+            let fork_evidence = db.open_tree(FORK_EVIDENCE_TREE_ID)?;
 
-            if db
-                .compare_and_swap(
-                    NEXT_UNSAMPLED_HEIGHT_KEY,
-                    None as Option<&[u8]>,
-                    Some(&height_to_key(1)),
-                )?
-                .is_ok()
-            {
-                debug!("initialised sampling height");
-            }
-
-            Ok::<_, SledError>(Self {
+            Ok::<_, StoreError>(Self {
                 inner: Arc::new(Inner {
                     db,
                     headers,
                     height_to_hash,
                     sampling_metadata,
+                    fork_evidence,
                     header_added_notifier: Notify::new(),
                 }),
             })
         })
         .await?
-        .map_err(|e| StoreError::OpenFailed(e.to_string()))
+        .map_err(|e| match e {
+            e @ StoreError::OpenFailed(_) => e,
+            e => StoreError::OpenFailed(e.to_string()),
+        })
     }
 
     async fn head_height(&self) -> Result<u64> {
@@ -149,46 +164,104 @@ impl SledStore {
         .unwrap_or(false)
     }
 
+    async fn get_range(&self, range: impl RangeBounds<u64>) -> Result<Vec<ExtendedHeader>> {
+        let head_height = self.head_height().await?;
+        let range = to_headers_range(range, head_height)?;
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let (start, end) = range.into_inner();
+            let mut headers = Vec::with_capacity((end + 1).saturating_sub(start) as usize);
+            let mut expected_height = start;
+
+            for entry in inner
+                .height_to_hash
+                .range(height_to_key(start)..=height_to_key(end))
+            {
+                let (height_key, hash) = entry?;
+                let height = key_to_height(&height_key)?;
+
+                if height != expected_height {
+                    return Err(StoreError::LostHeight(expected_height));
+                }
+
+                let hash = Hash::Sha256(hash.as_ref().try_into().map_err(|_| {
+                    StoreError::StoredDataError(format!("invalid hash at {height}"))
+                })?);
+
+                let header = match read_header_by_db_key(&inner.headers, hash.as_bytes()) {
+                    Err(StoreError::NotFound) => return Err(StoreError::LostHash(hash)),
+                    res => res?,
+                };
+
+                headers.push(header);
+                expected_height += 1;
+            }
+
+            if expected_height <= end {
+                return Err(StoreError::LostHeight(expected_height));
+            }
+
+            Ok(headers)
+        })
+        .await?
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let hash = header.hash();
-        let height = header.height().value();
+        self.append_unchecked(vec![header]).await
+    }
+
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let Some(head) = headers.last() else {
+            return Ok(());
+        };
+        let head_hash = head.hash();
+        let head_height = head.height().value();
         let inner = self.inner.clone();
 
         spawn_blocking(move || {
-            // Do actual inserts as a transaction, failing if keys already exist
+            let serialized_headers: Vec<_> = headers
+                .iter()
+                .map(|header| {
+                    // make sure Result is Infallible, we unwrap it later
+                    let serialized: std::result::Result<_, Infallible> = header.encode_vec();
+                    (
+                        header.hash(),
+                        height_to_key(header.height().value()),
+                        serialized.unwrap(),
+                    )
+                })
+                .collect();
+
+            // Do actual inserts as a single transaction, failing if any of the keys already exist
             (inner.db.deref(), &inner.headers, &inner.height_to_hash).transaction(
-                move |(db, headers, height_to_hash)| {
+                move |(db, headers_tree, height_to_hash)| {
                     let head_height =
                         transactional_read_height_by_db_key(db, HEAD_HEIGHT_KEY).unwrap_or(0);
 
-                    // A light check before checking the whole map
-                    if head_height > 0 && height <= head_height {
-                        return abort(StoreError::HeightExists(height));
-                    }
+                    // Light checks before checking the whole map
+                    check_continuity(head_height, &headers)?;
+
+                    for (hash, height_key, serialized_header) in &serialized_headers {
+                        if height_to_hash
+                            .insert(height_key, hash.as_bytes())?
+                            .is_some()
+                        {
+                            return abort(StoreError::HeightExists(u64::from_be_bytes(
+                                *height_key,
+                            )));
+                        }
 
-                    // Check if it's continuous before checking the whole map.
-                    if head_height + 1 != height {
-                        return abort(StoreError::NonContinuousAppend(head_height, height));
+                        if headers_tree
+                            .insert(hash.as_bytes(), serialized_header.as_slice())?
+                            .is_some()
+                        {
+                            return abort(StoreError::HashExists(*hash));
+                        }
                     }
 
-                    let height_key = height_to_key(height);
-                    if height_to_hash
-                        .insert(&height_key, hash.as_bytes())?
-                        .is_some()
-                    {
-                        return abort(StoreError::HeightExists(height));
-                    }
-
-                    db.insert(HEAD_HEIGHT_KEY, &height_key)?;
-
-                    // make sure Result is Infallible, we unwrap it later
-                    let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
-
-                    if headers
-                        .insert(hash.as_bytes(), serialized_header.unwrap())?
-                        .is_some()
-                    {
-                        return abort(StoreError::HashExists(hash));
+                    if let Some((_, height_key, _)) = serialized_headers.last() {
+                        db.insert(HEAD_HEIGHT_KEY, height_key)?;
                     }
 
                     Ok(())
@@ -199,7 +272,7 @@ impl SledStore {
 
         self.inner.header_added_notifier.notify_waiters();
 
-        debug!("Inserting header {hash} with height {height}");
+        debug!("Inserted headers up to {head_hash} with height {head_height}");
         Ok(())
     }
 
@@ -207,7 +280,7 @@ impl SledStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
         let inner = self.inner.clone();
 
@@ -233,23 +306,8 @@ impl SledStore {
                     };
                     let new_inserted = previous.is_none();
 
-                    let entry = match previous {
-                        Some(mut previous) => {
-                            previous.accepted = accepted;
-
-                            for cid in &cids {
-                                if !previous.cids_sampled.contains(cid) {
-                                    previous.cids_sampled.push(cid.to_owned());
-                                }
-                            }
-
-                            previous
-                        }
-                        None => SamplingMetadata {
-                            accepted,
-                            cids_sampled: cids.clone(),
-                        },
-                    };
+                    let mut entry = previous.unwrap_or_default();
+                    entry.update(accepted, samples.clone());
 
                     let serialized: Result<_, Infallible> = entry.encode_vec();
                     sampling_metadata.insert(&metadata_key, serialized.unwrap())?;
@@ -295,6 +353,93 @@ impl SledStore {
         .await??)
     }
 
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // ids are monotonic, so the evidence is iterated in the order of insertion
+            let id = inner.db.generate_id()?;
+            let serialized: Result<_, Infallible> = evidence.encode_vec();
+
+            inner
+                .fork_evidence
+                .insert(height_to_key(id), serialized.unwrap())?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            inner
+                .fork_evidence
+                .iter()
+                .values()
+                .map(|value| {
+                    ForkEvidence::decode(value?.as_ref())
+                        .map_err(|e| StoreError::StoredDataError(e.to_string()))
+                })
+                .collect()
+        })
+        .await?
+    }
+
+    async fn truncate(&self, height: u64) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let head_height = read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY).unwrap_or(0);
+            let height = height.min(head_height);
+
+            // Transactions can't iterate the trees, so the keys are collected beforehand
+            let removed_heights = inner
+                .height_to_hash
+                .range(height_to_key(height + 1)..)
+                .collect::<Result<Vec<_>, _>>()?;
+            let removed_metadata = inner
+                .sampling_metadata
+                .range(height_to_key(height + 1)..)
+                .keys()
+                .collect::<Result<Vec<_>, _>>()?;
+
+            (
+                inner.db.deref(),
+                &inner.headers,
+                &inner.height_to_hash,
+                &inner.sampling_metadata,
+            )
+                .transaction(
+                    move |(db, headers, height_to_hash, sampling_metadata)| {
+                        for (height_key, hash) in &removed_heights {
+                            height_to_hash.remove(height_key)?;
+                            headers.remove(hash)?;
+                        }
+
+                        for metadata_key in &removed_metadata {
+                            sampling_metadata.remove(metadata_key)?;
+                        }
+
+                        if height > 0 {
+                            db.insert(HEAD_HEIGHT_KEY, &height_to_key(height))?;
+                        } else {
+                            db.remove(HEAD_HEIGHT_KEY)?;
+                        }
+
+                        db.insert(NEXT_UNSAMPLED_HEIGHT_KEY, &height_to_key(1))?;
+                        transactional_update_sampling_height(db, sampling_metadata)?;
+
+                        Ok(())
+                    },
+                )
+        })
+        .await??;
+
+        Ok(())
+    }
+
     /// Flush the store's state to the filesystem.
     pub async fn flush_to_storage(&self) -> Result<()> {
         self.inner.db.flush_async().await?;
@@ -376,10 +521,25 @@ impl Store for SledStore {
         self.contains_height(height).await
     }
 
+    async fn get_range<R>(&self, range: R) -> Result<Vec<ExtendedHeader>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.get_range(range).await
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         self.append_single_unchecked(header).await
     }
 
+    async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        self.append_unchecked(headers).await
+    }
+
+    async fn truncate(&self, height: u64) -> Result<()> {
+        self.truncate(height).await
+    }
+
     async fn next_unsampled_height(&self) -> Result<u64> {
         self.get_next_unsampled_height().await
     }
@@ -388,14 +548,60 @@ impl Store for SledStore {
         &self,
         height: u64,
         accepted: bool,
-        cids: Vec<Cid>,
+        samples: Vec<(Cid, AxisType)>,
     ) -> Result<u64> {
-        self.update_sampling_metadata(height, accepted, cids).await
+        self.update_sampling_metadata(height, accepted, samples)
+            .await
     }
 
     async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
         self.get_sampling_metadata(height).await
     }
+
+    async fn insert_fork_evidence(&self, evidence: ForkEvidence) -> Result<()> {
+        self.insert_fork_evidence(evidence).await
+    }
+
+    async fn get_fork_evidence(&self) -> Result<Vec<ForkEvidence>> {
+        self.get_fork_evidence().await
+    }
+}
+
+/// Upgrades the schema of the database to [`SCHEMA_VERSION`], recording it.
+fn migrate(db: &Db) -> Result<()> {
+    let found = match db.get(SCHEMA_VERSION_KEY)? {
+        Some(version) => key_to_height(&version)?,
+        // Databases created before the schema was versioned
+        None if db.contains_key(NEXT_UNSAMPLED_HEIGHT_KEY)? => 2,
+        None => 1,
+    };
+
+    for migration in migrations::pending(&MIGRATIONS, found, SCHEMA_VERSION)? {
+        (migration.run)(db)?;
+        db.insert(SCHEMA_VERSION_KEY, &migration.version.to_be_bytes())?;
+    }
+
+    if found == SCHEMA_VERSION && !db.contains_key(SCHEMA_VERSION_KEY)? {
+        db.insert(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Version 2 adds the sampling metadata, with all the existing headers left unsampled.
+fn migrate_to_v2(db: &Db) -> Result<()> {
+    if db
+        .compare_and_swap(
+            NEXT_UNSAMPLED_HEIGHT_KEY,
+            None as Option<&[u8]>,
+            Some(&height_to_key(1)),
+        )?
+        .is_ok()
+    {
+        debug!("initialised sampling height");
+    }
+
+    Ok(())
 }
 
 #[inline]
@@ -492,6 +698,13 @@ fn transactional_read_sampling_metadata_by_db_key(
         .map_err(|e| StoreError::StoredDataError(e.to_string()))?)
 }
 
+#[inline]
+fn key_to_height(key: &[u8]) -> Result<u64> {
+    key.try_into()
+        .map(u64::from_be_bytes)
+        .map_err(|_| StoreError::StoredDataError("invalid height key".to_string()))
+}
+
 #[inline]
 fn height_to_key(height: u64) -> [u8; 8] {
     // sled recommends BigEndian representation for ints since it preserves expected int order
@@ -504,6 +717,7 @@ pub mod tests {
     use std::path::Path;
 
     use super::*;
+    use crate::store::IntegrityIssue;
     use celestia_types::test_utils::ExtendedHeaderGenerator;
     use tempfile::TempDir;
 
@@ -676,6 +890,97 @@ pub mod tests {
             let sampling_data = store.get_sampling_metadata(1).await.unwrap().unwrap();
             assert!(sampling_data.accepted);
         }
+
+        #[tokio::test]
+        async fn records_schema_version() {
+            let mut gen = ExtendedHeaderGenerator::new();
+            let v1_path = init_store(gen.next_many(5)).await;
+
+            let store = create_store(Some(&v1_path)).await;
+            let version = store.inner.db.get(SCHEMA_VERSION_KEY).unwrap().unwrap();
+            assert_eq!(key_to_height(&version).unwrap(), SCHEMA_VERSION);
+            drop(store);
+            sleep(Duration::from_millis(10)).await;
+
+            // reopening doesn't run the migrations again
+            let store = create_store(Some(&v1_path)).await;
+            store
+                .update_sampling_metadata(1, true, vec![])
+                .await
+                .unwrap();
+            drop(store);
+            sleep(Duration::from_millis(10)).await;
+
+            let store = create_store(Some(&v1_path)).await;
+            assert_eq!(store.next_unsampled_height().await.unwrap(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn check_integrity_and_repair() {
+        let (store, _) = gen_filled_store(20, None).await;
+        let lost = store.get_by_height(15).await.unwrap();
+
+        // simulate a header lost in a crash
+        store.inner.headers.remove(lost.hash().as_bytes()).unwrap();
+
+        let report = store.check_integrity().await.unwrap();
+        assert_eq!(report.head_height, 20);
+        assert_eq!(report.last_consistent_height, 14);
+        assert_eq!(report.issues, [IntegrityIssue::MissingHeader(15)]);
+
+        store.truncate(report.last_consistent_height).await.unwrap();
+
+        let report = store.check_integrity().await.unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(store.head_height().await.unwrap(), 14);
+    }
+
+    #[tokio::test]
+    async fn check_integrity_detects_sampling_watermark() {
+        let (store, _) = gen_filled_store(10, None).await;
+        store
+            .inner
+            .db
+            .insert(NEXT_UNSAMPLED_HEIGHT_KEY, &height_to_key(5))
+            .unwrap();
+
+        let report = store.check_integrity().await.unwrap();
+        assert_eq!(
+            report.issues,
+            [IntegrityIssue::SamplingWatermark {
+                recorded: 5,
+                expected: 1
+            }]
+        );
+        assert_eq!(report.last_consistent_height, 10);
+
+        store.truncate(10).await.unwrap();
+        assert_eq!(store.next_unsampled_height().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_newer_schema() {
+        let db_dir = TempDir::with_prefix("lumina.store.test").unwrap();
+        let store = create_store(Some(db_dir.path())).await;
+        store
+            .inner
+            .db
+            .insert(SCHEMA_VERSION_KEY, &(SCHEMA_VERSION + 1).to_be_bytes())
+            .unwrap();
+        store.flush_to_storage().await.unwrap();
+        drop(store);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        let path = db_dir.path().to_owned();
+        let db = spawn_blocking(move || sled::Config::default().path(path).open().unwrap())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            SledStore::new(db).await,
+            Err(StoreError::OpenFailed(_))
+        ));
     }
 
     pub async fn create_store(path: Option<&Path>) -> SledStore {
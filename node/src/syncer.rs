@@ -1,38 +1,62 @@
 //! Component responsible for synchronizing block headers announced in the Celestia network.
 //!
 //! It starts by asking the trusted peers for their current head headers and picks
-//! the latest header returned by at least two of them as the initial synchronization target
-//! called `subjective_head`.
+//! the latest header returned by at least a quorum of them (two by default) as the initial
+//! synchronization target called `subjective_head`.
+//!
+//! The target is verified against the latest stored header, skipping the headers between
+//! them if the validator set allows it, and bisecting the range otherwise. If the stored header
+//! is older than the trusting period, the synchronization stops until a new trusted checkpoint
+//! is provided.
 //!
 //! Then it starts synchronizing from the genesis header up to the target requesting headers
 //! on the `header-ex` p2p protocol. In the meantime, it constantly checks for the latest
 //! headers announced on the `header-sub` p2p protocol to keep the `subjective_head` as close
-//! to the `network_head` as possible.
+//! to the `network_head` as possible. If no new header is announced for a while, the
+//! synchronization is considered stalled and the head is polled from the trusted peers instead.
 
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoffBuilder;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::{ExtendedHeader, VerificationError, VerificationOptions};
 use futures::FutureExt;
+use instant::{Instant, SystemTime};
 use serde::Serialize;
 use tokio::select;
 use tokio::sync::{mpsc, oneshot, watch};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info, info_span, instrument, warn, Instrument};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 
 use crate::executor::{sleep, spawn, spawn_cancellable, Interval};
 use crate::p2p::{P2p, P2pError};
+use crate::peer_tracker::HeaderExThroughput;
 use crate::store::{Store, StoreError};
 use crate::utils::OneshotSenderExt;
 
 type Result<T, E = SyncerError> = std::result::Result<T, E>;
 
-const MAX_HEADERS_IN_BATCH: u64 = 512;
+/// Amount of headers in a batch used until the throughput of the peers is known.
+const DEFAULT_HEADERS_IN_BATCH: u64 = 512;
+const MIN_HEADERS_IN_BATCH: u64 = 64;
+const MAX_HEADERS_IN_BATCH: u64 = 4096;
+/// Expected duration of fetching a single batch, used for choosing its size.
+const TARGET_BATCH_DURATION: Duration = Duration::from_secs(10);
 const TRY_INIT_BACKOFF_MAX_INTERVAL: Duration = Duration::from_secs(60);
+/// Weight of the newest batch in the moving average of the synchronization rate.
+const SYNC_RATE_SMOOTHING: f64 = 0.3;
+/// Lowest stall timeout, shorter ones are raised to it.
+const MIN_STALL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Default number of trusted peers that need to agree on the `subjective_head`.
+pub const DEFAULT_HEAD_QUORUM: usize = 2;
+
+/// Default time without a `subjective_head` update after which the synchronization is stalled.
+pub const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// Representation of all the errors that can occur when interacting with the [`Syncer`].
 #[derive(Debug, thiserror::Error)]
@@ -49,6 +73,12 @@ pub enum SyncerError {
     #[error(transparent)]
     Celestia(#[from] celestia_types::Error),
 
+    /// The stored head is older than the trusting period and the network head can't be verified from it.
+    #[error(
+        "Trusted head {0} is outside of the trusting period, a new trusted checkpoint is required"
+    )]
+    TrustedHeadExpired(u64),
+
     /// The worker has died.
     #[error("Worker died")]
     WorkerDied,
@@ -86,6 +116,21 @@ where
     pub p2p: Arc<P2p>,
     /// Headers storage.
     pub store: Arc<S>,
+    /// Number of trusted peers that need to agree on the initial `subjective_head`.
+    ///
+    /// If no head is reported by that many peers, the highest reported one is used.
+    pub head_quorum: usize,
+    /// Options used to verify the `subjective_head` against the stored head.
+    ///
+    /// The trusting period is checked only if it's set in the options, which is opt-in.
+    /// If the stored head is outside of it, the [`Syncer`] stops with the
+    /// [`SyncingStatus::TrustedHeadExpired`] status.
+    pub verification_options: VerificationOptions,
+    /// Time without a `subjective_head` update after which the synchronization is stalled
+    /// and the head is polled from the trusted peers.
+    ///
+    /// Timeouts shorter than 100ms are raised to it.
+    pub stall_timeout: Duration,
 }
 
 #[derive(Debug)]
@@ -102,6 +147,35 @@ pub struct SyncingInfo {
     pub local_head: u64,
     /// Syncing target. The latest height seen in the network that was successfully verified.
     pub subjective_head: u64,
+    /// Current state of the synchronization.
+    pub status: SyncingStatus,
+    /// Unix time in milliseconds of the last `subjective_head` update.
+    pub last_head_update: Option<u64>,
+    /// Ranges of the headers in the store, in ascending order.
+    pub stored_headers: Vec<RangeInclusive<u64>>,
+    /// Range of the headers currently fetched on the `header-ex` protocol.
+    pub ongoing_batch: Option<RangeInclusive<u64>>,
+    /// Average number of headers synchronized per second, measured over the recent batches.
+    pub headers_per_second: Option<f64>,
+    /// Estimated time until the headers up to the `subjective_head` are synchronized.
+    pub eta: Option<Duration>,
+}
+
+/// State of the synchronization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncingStatus {
+    /// The `subjective_head` wasn't received from the trusted peers yet.
+    Initializing,
+    /// The headers up to the `subjective_head` are being synchronized.
+    Syncing,
+    /// All the headers up to the `subjective_head` are synchronized.
+    Synced,
+    /// The `subjective_head` wasn't updated for a while, it is polled from the trusted peers.
+    Stalled,
+    /// The stored head is outside of the trusting period, so the `subjective_head` can't be
+    /// verified from it. Synchronization doesn't continue until a new trusted header is stored.
+    TrustedHeadExpired,
 }
 
 impl<S> Syncer<S>
@@ -173,7 +247,14 @@ where
     store: Arc<S>,
     header_sub_watcher: watch::Receiver<Option<ExtendedHeader>>,
     genesis_hash: Option<Hash>,
+    head_quorum: usize,
+    verification_options: VerificationOptions,
+    stall_timeout: Duration,
     subjective_head_height: Option<u64>,
+    trusted_head_expired: bool,
+    headers_per_second: Option<f64>,
+    last_head_update: Option<SystemTime>,
+    ongoing_head_poll: Option<oneshot::Receiver<()>>,
     headers_tx: mpsc::Sender<Result<Vec<ExtendedHeader>, P2pError>>,
     headers_rx: mpsc::Receiver<Result<Vec<ExtendedHeader>, P2pError>>,
     ongoing_batch: Option<Ongoing>,
@@ -182,6 +263,7 @@ where
 struct Ongoing {
     start: u64,
     end: u64,
+    started_at: Instant,
     cancellation_token: CancellationToken,
 }
 
@@ -204,7 +286,14 @@ where
             store: args.store,
             header_sub_watcher,
             genesis_hash: args.genesis_hash,
+            head_quorum: args.head_quorum,
+            verification_options: args.verification_options,
+            stall_timeout: args.stall_timeout.max(MIN_STALL_TIMEOUT),
             subjective_head_height: None,
+            trusted_head_expired: false,
+            headers_per_second: None,
+            last_head_update: None,
+            ongoing_head_poll: None,
             headers_tx,
             headers_rx,
             ongoing_batch: None,
@@ -247,11 +336,19 @@ where
                 _ = report_interval.tick() => {
                     self.report().await;
                 }
-                Ok(network_head_height) = &mut try_init_result => {
-                    info!("Setting initial subjective head to {network_head_height}");
-                    self.subjective_head_height = Some(network_head_height);
-                    break;
-                }
+                Ok(res) = &mut try_init_result => match res {
+                    Ok(network_head_height) => {
+                        info!("Setting initial subjective head to {network_head_height}");
+                        self.set_subjective_head_height(network_head_height);
+                        break;
+                    }
+                    // Retrying won't help, keep reporting the status until stopped
+                    Err(e) => {
+                        error!("Initialization of subjective head failed: {e}.");
+                        self.trusted_head_expired = true;
+                        self.report().await;
+                    }
+                },
                 Some(cmd) = self.cmd_rx.recv() => {
                     self.on_cmd(cmd).await;
                 }
@@ -265,6 +362,7 @@ where
         debug!("Entering connected_event_loop");
 
         let mut report_interval = Interval::new(Duration::from_secs(60)).await;
+        let mut stall_check_interval = Interval::new(self.stall_timeout / 2).await;
         let mut peer_tracker_info_watcher = self.p2p.peer_tracker_info_watcher();
 
         // Check if connection status changed before creating the watcher
@@ -290,6 +388,12 @@ where
                 _ = report_interval.tick() => {
                     self.report().await;
                 }
+                _ = stall_check_interval.tick() => {
+                    self.poll_head_if_stalled();
+                }
+                _ = poll_head_result(&mut self.ongoing_head_poll) => {
+                    self.ongoing_head_poll = None;
+                }
                 _ = self.header_sub_watcher.changed() => {
                     self.on_header_sub_message().await;
                     self.fetch_next_batch().await;
@@ -311,35 +415,164 @@ where
             );
             ongoing.cancellation_token.cancel();
         }
+
+        // Dropping the receiver cancels the poll
+        self.ongoing_head_poll = None;
     }
 
     async fn syncing_info(&self) -> SyncingInfo {
+        let local_head = self.store.head_height().await.unwrap_or(0);
+
+        let status = match self.subjective_head_height {
+            None if self.trusted_head_expired => SyncingStatus::TrustedHeadExpired,
+            None => SyncingStatus::Initializing,
+            Some(_) if self.is_stalled() => SyncingStatus::Stalled,
+            Some(subjective_head) if local_head < subjective_head => SyncingStatus::Syncing,
+            Some(_) => SyncingStatus::Synced,
+        };
+
+        let last_head_update = self.last_head_update.map(|time| {
+            let since_epoch = time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            since_epoch.as_millis() as u64
+        });
+
+        let stored_headers = self
+            .store
+            .get_stored_header_ranges()
+            .await
+            .unwrap_or_default();
+
+        let ongoing_batch = self
+            .ongoing_batch
+            .as_ref()
+            .map(|ongoing| ongoing.start..=ongoing.end);
+
+        let eta = self.subjective_head_height.and_then(|subjective_head| {
+            let remaining = subjective_head.saturating_sub(local_head);
+
+            match self.headers_per_second {
+                _ if remaining == 0 => Some(Duration::ZERO),
+                Some(headers_per_second) if headers_per_second > 0.0 => Some(
+                    Duration::from_secs_f64(remaining as f64 / headers_per_second),
+                ),
+                _ => None,
+            }
+        });
+
         SyncingInfo {
-            local_head: self.store.head_height().await.unwrap_or(0),
+            local_head,
             subjective_head: self.subjective_head_height.unwrap_or(0),
+            status,
+            last_head_update,
+            stored_headers,
+            ongoing_batch,
+            headers_per_second: self.headers_per_second,
+            eta,
         }
     }
 
+    /// Includes the batch of `amount` headers, fetched and stored within `elapsed`, in
+    /// the moving average of the synchronization rate.
+    fn record_batch_rate(&mut self, amount: u64, elapsed: Duration) {
+        let elapsed = elapsed.max(Duration::from_millis(1));
+        let headers_per_second = amount as f64 / elapsed.as_secs_f64();
+
+        self.headers_per_second = Some(match self.headers_per_second {
+            Some(prev) => {
+                SYNC_RATE_SMOOTHING * headers_per_second + (1.0 - SYNC_RATE_SMOOTHING) * prev
+            }
+            None => headers_per_second,
+        });
+    }
+
+    fn set_subjective_head_height(&mut self, height: u64) {
+        self.subjective_head_height = Some(height);
+        self.last_head_update = Some(SystemTime::now());
+    }
+
+    fn is_stalled(&self) -> bool {
+        self.last_head_update.is_some_and(|time| {
+            time.elapsed()
+                .is_ok_and(|elapsed| elapsed >= self.stall_timeout)
+        })
+    }
+
+    /// Requests the head from the trusted peers, if `header-sub` didn't deliver any
+    /// new header for a while.
+    ///
+    /// A newer head is verified and passed to the `header-sub`, which updates the
+    /// `subjective_head` the same way as a newly announced header.
+    fn poll_head_if_stalled(&mut self) {
+        if !self.is_stalled() || self.ongoing_head_poll.is_some() {
+            return;
+        }
+
+        let Some(subjective_head) = self.header_sub_watcher.borrow().to_owned() else {
+            return;
+        };
+
+        warn!(
+            "No new head for {:?}, polling it from the trusted peers",
+            self.stall_timeout
+        );
+
+        let p2p = self.p2p.clone();
+        let head_quorum = self.head_quorum;
+        let verification_options = self.verification_options.clone();
+        let (mut tx, rx) = oneshot::channel();
+
+        spawn(async move {
+            let fut = poll_head(&p2p, subjective_head, head_quorum, &verification_options);
+
+            select! {
+                _ = tx.closed() => {}
+                res = fut => {
+                    if let Err(e) = res {
+                        warn!("Polling head failed: {e}");
+                    }
+                    let _ = tx.send(());
+                }
+            }
+        });
+
+        self.ongoing_head_poll = Some(rx);
+    }
+
     #[instrument(skip_all)]
     async fn report(&mut self) {
         let SyncingInfo {
             local_head,
             subjective_head,
+            status,
+            ongoing_batch,
+            headers_per_second,
+            eta,
+            ..
         } = self.syncing_info().await;
 
-        let ongoing_batch = self
-            .ongoing_batch
-            .as_ref()
-            .map(|ongoing| format!("[{}, {}]", ongoing.start, ongoing.end))
+        let ongoing_batch = ongoing_batch
+            .map(|range| format!("[{}, {}]", range.start(), range.end()))
             .unwrap_or_else(|| "None".to_string());
-
-        info!("syncing: {local_head}/{subjective_head}, ongoing batch: {ongoing_batch}",);
+        let rate = headers_per_second
+            .map(|rate| format!("{rate:.1} headers/s"))
+            .unwrap_or_else(|| "unknown rate".to_string());
+        let eta = eta
+            .map(|eta| format!("{}s", eta.as_secs()))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        info!(
+            "syncing ({status:?}): {local_head}/{subjective_head}, ongoing batch: {ongoing_batch}, {rate}, eta: {eta}",
+        );
     }
 
-    fn spawn_try_init(&self) -> oneshot::Receiver<u64> {
+    fn spawn_try_init(&self) -> oneshot::Receiver<Result<u64>> {
         let p2p = self.p2p.clone();
         let store = self.store.clone();
         let genesis_hash = self.genesis_hash;
+        let head_quorum = self.head_quorum;
+        let verification_options = self.verification_options.clone();
         let (tx, rx) = oneshot::channel();
 
         let fut = async move {
@@ -349,9 +582,22 @@ where
                 .build();
 
             loop {
-                match try_init(&p2p, &*store, genesis_hash).await {
+                match try_init(
+                    &p2p,
+                    &*store,
+                    genesis_hash,
+                    head_quorum,
+                    &verification_options,
+                )
+                .await
+                {
                     Ok(network_height) => {
-                        tx.maybe_send(network_height);
+                        tx.maybe_send(Ok(network_height));
+                        break;
+                    }
+                    // Retrying won't help, the store needs a newer trusted header
+                    Err(e @ SyncerError::TrustedHeadExpired(_)) => {
+                        tx.maybe_send(Err(e));
                         break;
                     }
                     Err(e) => {
@@ -359,7 +605,7 @@ where
                             .next_backoff()
                             .expect("backoff never stops retrying");
 
-                        warn!("Intialization of subjective head failed: {e}. Trying again in {sleep_dur:?}.");
+                        warn!("Initialization of subjective head failed: {e}. Trying again in {sleep_dur:?}.");
                         sleep(sleep_dur).await;
                     }
                 }
@@ -411,7 +657,12 @@ where
             }
         }
 
-        self.subjective_head_height = Some(new_head_height);
+        if self
+            .subjective_head_height
+            .map_or(true, |height| new_head_height > height)
+        {
+            self.set_subjective_head_height(new_head_height);
+        }
     }
 
     #[instrument(skip_all)]
@@ -437,7 +688,7 @@ where
 
         let amount = subjective_head_height
             .saturating_sub(local_head.height().value())
-            .min(MAX_HEADERS_IN_BATCH);
+            .min(batch_size(self.p2p.header_ex_throughput()));
 
         if amount == 0 {
             // Nothing to schedule
@@ -457,6 +708,7 @@ where
         self.ongoing_batch = Some(Ongoing {
             start,
             end,
+            started_at: Instant::now(),
             cancellation_token: cancellation_token.clone(),
         });
         info!("Fetching batch {start} until {end}");
@@ -488,15 +740,75 @@ where
             }
         };
 
+        let amount = headers.len() as u64;
+
         // Headers are already verified by `get_verified_headers_range`,
         // so `append_unchecked` is used for optimization.
         if let Err(e) = self.store.append_unchecked(headers).await {
             warn!("Failed to store batch {start} until {end}: {e}");
+            return;
+        }
+
+        self.record_batch_rate(amount, ongoing.started_at.elapsed());
+    }
+}
+
+/// Returns the amount of headers to fetch in a single batch for the observed `throughput`.
+fn batch_size(throughput: Option<HeaderExThroughput>) -> u64 {
+    let Some(throughput) = throughput else {
+        return DEFAULT_HEADERS_IN_BATCH;
+    };
+
+    let headers_per_second = throughput.headers_per_second * throughput.num_peers as f64;
+    let amount = (headers_per_second * TARGET_BATCH_DURATION.as_secs_f64()) as u64;
+
+    amount.clamp(MIN_HEADERS_IN_BATCH, MAX_HEADERS_IN_BATCH)
+}
+
+/// Awaits the end of the ongoing head poll, never resolving if there is none.
+async fn poll_head_result(ongoing_head_poll: &mut Option<oneshot::Receiver<()>>) {
+    match ongoing_head_poll {
+        Some(rx) => {
+            let _ = rx.await;
         }
+        None => futures::future::pending().await,
+    }
+}
+
+/// Requests the head from the trusted peers and passes it to the `header-sub`,
+/// if it's newer than the `subjective_head`.
+async fn poll_head(
+    p2p: &P2p,
+    subjective_head: ExtendedHeader,
+    head_quorum: usize,
+    verification_options: &VerificationOptions,
+) -> Result<()> {
+    let head = p2p
+        .get_head_by_quorum(head_quorum)
+        .await?
+        .into_head(head_quorum);
+
+    if head.height() <= subjective_head.height() {
+        debug!("Polled head {} is not newer", head.height());
+        return Ok(());
     }
+
+    p2p.verify_by_bisection(&subjective_head, &head, verification_options)
+        .await?;
+
+    info!("Polled new head {} from the trusted peers", head.height());
+    p2p.init_header_sub(head).await?;
+
+    Ok(())
 }
 
-async fn try_init<S>(p2p: &P2p, store: &S, genesis_hash: Option<Hash>) -> Result<u64>
+async fn try_init<S>(
+    p2p: &P2p,
+    store: &S,
+    genesis_hash: Option<Hash>,
+    head_quorum: usize,
+    verification_options: &VerificationOptions,
+) -> Result<u64>
 where
     S: Store,
 {
@@ -516,9 +828,27 @@ where
         store.append_single_unchecked(genesis).await?;
     }
 
-    let network_head = p2p.get_head_header().await?;
+    let network_head = p2p
+        .get_head_by_quorum(head_quorum)
+        .await?
+        .into_head(head_quorum);
     let network_head_height = network_head.height().value();
 
+    // Verify the network head from our own head in a few steps, before backfilling
+    // the headers between them.
+    let local_head = store.get_head().await?;
+
+    if local_head.height().value() < network_head_height {
+        p2p.verify_by_bisection(&local_head, &network_head, verification_options)
+            .await
+            .map_err(|e| match e {
+                P2pError::HeaderVerification(celestia_types::Error::Verification(
+                    VerificationError::TrustedHeaderExpired(height),
+                )) => SyncerError::TrustedHeadExpired(height),
+                e => e.into(),
+            })?;
+    }
+
     p2p.init_header_sub(network_head).await?;
 
     Ok(network_head_height)
@@ -527,6 +857,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::p2p::HeadQuorumResponse;
     use crate::store::InMemoryStore;
     use crate::test_utils::{async_test, gen_filled_store, MockP2pHandle};
     use celestia_types::test_utils::ExtendedHeaderGenerator;
@@ -541,6 +872,9 @@ mod tests {
             genesis_hash: None,
             p2p: Arc::new(mock),
             store: Arc::new(InMemoryStore::new()),
+            head_quorum: DEFAULT_HEAD_QUORUM,
+            verification_options: VerificationOptions::default(),
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
         })
         .unwrap();
 
@@ -557,10 +891,11 @@ mod tests {
         respond_to.send(Ok(vec![genesis.clone()])).unwrap();
 
         // After genesis header it asks for the current HEAD
-        let (height, amount, respond_to) = handle.expect_header_request_for_height_cmd().await;
-        assert_eq!(height, 0);
-        assert_eq!(amount, 1);
-        respond_to.send(Ok(vec![genesis.clone()])).unwrap();
+        let (quorum, respond_to) = handle.expect_head_request_cmd().await;
+        assert_eq!(quorum, DEFAULT_HEAD_QUORUM);
+        respond_to
+            .send(Ok(head_quorum_response(genesis.clone())))
+            .unwrap();
 
         // Now Syncer initializes HeaderSub with the latest HEAD
         let head_from_syncer = handle.expect_init_header_sub().await;
@@ -602,6 +937,7 @@ mod tests {
             .map_err(|_| "headers [2, 26]")
             .unwrap();
         assert_syncing(&syncer, &store, 26, 26).await;
+        assert_eq!(syncer.info().await.unwrap().status, SyncingStatus::Synced);
 
         // Syncer is fulling synced and awaiting for events
         p2p_mock.expect_no_cmd().await;
@@ -618,6 +954,7 @@ mod tests {
         let header_28_30 = gen.next_many(3);
         p2p_mock.announce_new_head(header_28_30[2].clone());
         assert_syncing(&syncer, &store, 27, 30).await;
+        assert_eq!(syncer.info().await.unwrap().status, SyncingStatus::Syncing);
 
         // New HEAD is not adjacent to store, so Syncer requests a range
         let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
@@ -689,6 +1026,45 @@ mod tests {
         p2p_mock.expect_no_cmd().await;
     }
 
+    #[async_test]
+    async fn start_with_expired_store_head() {
+        let (p2p, mut p2p_mock) = P2p::mocked();
+        let (store, mut gen) = gen_filled_store(25);
+        let store = Arc::new(store);
+
+        let genesis = store.get_by_height(1).await.unwrap();
+        let network_head = gen.next_many(520).pop().unwrap();
+
+        let syncer = Syncer::start(SyncerArgs {
+            genesis_hash: Some(genesis.hash()),
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            head_quorum: DEFAULT_HEAD_QUORUM,
+            verification_options: VerificationOptions {
+                trusting_period: Some(Duration::ZERO),
+                ..Default::default()
+            },
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
+        })
+        .unwrap();
+
+        p2p_mock.announce_trusted_peer_connected();
+
+        let (_, respond_to) = p2p_mock.expect_head_request_cmd().await;
+        respond_to
+            .send(Ok(head_quorum_response(network_head)))
+            .unwrap();
+
+        // Network head can't be trusted, so Syncer doesn't initialize HeaderSub
+        // nor retries the initialization
+        p2p_mock.expect_no_cmd().await;
+        assert_syncing(&syncer, &store, 25, 0).await;
+        assert_eq!(
+            syncer.info().await.unwrap().status,
+            SyncingStatus::TrustedHeadExpired
+        );
+    }
+
     #[async_test]
     async fn start_with_filled_store() {
         let (p2p, mut p2p_mock) = P2p::mocked();
@@ -703,16 +1079,20 @@ mod tests {
             genesis_hash: Some(genesis.hash()),
             p2p: Arc::new(p2p),
             store: store.clone(),
+            head_quorum: DEFAULT_HEAD_QUORUM,
+            verification_options: VerificationOptions::default(),
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
         })
         .unwrap();
 
         p2p_mock.announce_trusted_peer_connected();
 
         // Store already has genesis, so Syncer asks only for current HEAD
-        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
-        assert_eq!(height, 0);
-        assert_eq!(amount, 1);
-        respond_to.send(Ok(vec![network_head.clone()])).unwrap();
+        let (quorum, respond_to) = p2p_mock.expect_head_request_cmd().await;
+        assert_eq!(quorum, DEFAULT_HEAD_QUORUM);
+        respond_to
+            .send(Ok(head_quorum_response(network_head.clone())))
+            .unwrap();
 
         // Now Syncer initializes HeaderSub with the latest HEAD
         let head_from_syncer = p2p_mock.expect_init_header_sub().await;
@@ -793,11 +1173,12 @@ mod tests {
         p2p_mock.announce_trusted_peer_connected();
 
         // Syncer is now back to `connecting_event_loop`, so we expect a request for HEAD
-        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
-        assert_eq!(height, 0);
-        assert_eq!(amount, 1);
+        let (quorum, respond_to) = p2p_mock.expect_head_request_cmd().await;
+        assert_eq!(quorum, DEFAULT_HEAD_QUORUM);
         // Now HEAD is height 27
-        respond_to.send(Ok(vec![headers_2_27[25].clone()])).unwrap();
+        respond_to
+            .send(Ok(head_quorum_response(headers_2_27[25].clone())))
+            .unwrap();
 
         // Syncer initializes HeaderSub with the latest HEAD
         let head_from_syncer = p2p_mock.expect_init_header_sub().await;
@@ -818,6 +1199,104 @@ mod tests {
         p2p_mock.expect_no_cmd().await;
     }
 
+    #[async_test]
+    async fn syncing_info_reports_progress() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (syncer, store, mut p2p_mock) =
+            initialized_syncer(genesis, headers_2_26[24].clone()).await;
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (2, 25));
+
+        // Rate isn't known before the first batch is stored
+        let info = syncer.info().await.unwrap();
+        assert_eq!(info.stored_headers, vec![1..=1]);
+        assert_eq!(info.ongoing_batch, Some(2..=26));
+        assert_eq!(info.headers_per_second, None);
+        assert_eq!(info.eta, None);
+
+        respond_to
+            .send(Ok(headers_2_26))
+            .map_err(|_| "headers [2, 26]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 26, 26).await;
+
+        let info = syncer.info().await.unwrap();
+        assert_eq!(info.stored_headers, vec![1..=26]);
+        assert_eq!(info.ongoing_batch, None);
+        assert!(info.headers_per_second.unwrap() > 0.0);
+        assert_eq!(info.eta, Some(Duration::ZERO));
+
+        // New HEAD was received by HeaderSub, rate is used for the estimation
+        let header_27_30 = gen.next_many(4);
+        p2p_mock.announce_new_head(header_27_30[3].clone());
+        assert_syncing(&syncer, &store, 26, 30).await;
+
+        let info = syncer.info().await.unwrap();
+        assert_eq!(info.ongoing_batch, Some(27..=30));
+        assert!(info.eta.unwrap() > Duration::ZERO);
+    }
+
+    #[async_test]
+    async fn polls_head_when_stalled() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (syncer, store, mut p2p_mock) = initialized_syncer_with_stall_timeout(
+            genesis,
+            headers_2_26[24].clone(),
+            Duration::from_millis(400),
+        )
+        .await;
+        // Mocked P2p doesn't update HeaderSub on initialization
+        p2p_mock.announce_new_head(headers_2_26[24].clone());
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (2, 25));
+        respond_to
+            .send(Ok(headers_2_26))
+            .map_err(|_| "headers [2, 26]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 26, 26).await;
+
+        let info = syncer.info().await.unwrap();
+        assert_eq!(info.status, SyncingStatus::Synced);
+        assert!(info.last_head_update.is_some());
+
+        // HeaderSub doesn't deliver any new head
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(syncer.info().await.unwrap().status, SyncingStatus::Stalled);
+
+        // Syncer polls the head from the trusted peers
+        let header_27_30 = gen.next_many(4);
+        let (quorum, respond_to) = p2p_mock.expect_head_request_cmd().await;
+        assert_eq!(quorum, DEFAULT_HEAD_QUORUM);
+        respond_to
+            .send(Ok(head_quorum_response(header_27_30[3].clone())))
+            .unwrap();
+
+        // Verified head is passed to HeaderSub
+        let new_head = p2p_mock.expect_init_header_sub().await;
+        assert_eq!(new_head, header_27_30[3]);
+        p2p_mock.announce_new_head(new_head);
+        assert_syncing(&syncer, &store, 26, 30).await;
+        assert_eq!(syncer.info().await.unwrap().status, SyncingStatus::Syncing);
+
+        // Syncing continues as usual
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (27, 4));
+        respond_to
+            .send(Ok(header_27_30))
+            .map_err(|_| "headers [27, 30]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 30, 30).await;
+        assert_eq!(syncer.info().await.unwrap().status, SyncingStatus::Synced);
+    }
+
     async fn assert_syncing(
         syncer: &Syncer<InMemoryStore>,
         store: &InMemoryStore,
@@ -839,6 +1318,14 @@ mod tests {
     async fn initialized_syncer(
         genesis: ExtendedHeader,
         head: ExtendedHeader,
+    ) -> (Syncer<InMemoryStore>, Arc<InMemoryStore>, MockP2pHandle) {
+        initialized_syncer_with_stall_timeout(genesis, head, DEFAULT_STALL_TIMEOUT).await
+    }
+
+    async fn initialized_syncer_with_stall_timeout(
+        genesis: ExtendedHeader,
+        head: ExtendedHeader,
+        stall_timeout: Duration,
     ) -> (Syncer<InMemoryStore>, Arc<InMemoryStore>, MockP2pHandle) {
         let (mock, mut handle) = P2p::mocked();
         let store = Arc::new(InMemoryStore::new());
@@ -847,6 +1334,9 @@ mod tests {
             genesis_hash: Some(genesis.hash()),
             p2p: Arc::new(mock),
             store: store.clone(),
+            head_quorum: DEFAULT_HEAD_QUORUM,
+            verification_options: VerificationOptions::default(),
+            stall_timeout,
         })
         .unwrap();
 
@@ -862,10 +1352,11 @@ mod tests {
         respond_to.send(Ok(vec![genesis])).unwrap();
 
         // After genesis header it asks for the current HEAD
-        let (height, amount, respond_to) = handle.expect_header_request_for_height_cmd().await;
-        assert_eq!(height, 0);
-        assert_eq!(amount, 1);
-        respond_to.send(Ok(vec![head.clone()])).unwrap();
+        let (quorum, respond_to) = handle.expect_head_request_cmd().await;
+        assert_eq!(quorum, DEFAULT_HEAD_QUORUM);
+        respond_to
+            .send(Ok(head_quorum_response(head.clone())))
+            .unwrap();
 
         // Now Syncer initializes HeaderSub with the latest HEAD
         let head_from_syncer = handle.expect_init_header_sub().await;
@@ -874,6 +1365,32 @@ mod tests {
         (syncer, store, handle)
     }
 
+    #[test]
+    fn batch_size_follows_throughput() {
+        let throughput = |num_peers, headers_per_second| HeaderExThroughput {
+            num_peers,
+            headers_per_second,
+            latency: Duration::from_millis(500),
+        };
+
+        assert_eq!(batch_size(None), DEFAULT_HEADERS_IN_BATCH);
+        assert_eq!(batch_size(Some(throughput(4, 25.0))), 1000);
+        assert_eq!(batch_size(Some(throughput(1, 1.0))), MIN_HEADERS_IN_BATCH);
+        assert_eq!(
+            batch_size(Some(throughput(16, 1000.0))),
+            MAX_HEADERS_IN_BATCH
+        );
+    }
+
+    fn head_quorum_response(head: ExtendedHeader) -> HeadQuorumResponse {
+        HeadQuorumResponse {
+            head,
+            quorum_reached: true,
+            agreeing_peers: Vec::new(),
+            disagreements: Vec::new(),
+        }
+    }
+
     async fn handle_session_batch(
         p2p_mock: &mut MockP2pHandle,
         remaining_headers: &mut Vec<ExtendedHeader>,
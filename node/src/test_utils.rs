@@ -5,18 +5,21 @@ use std::time::Duration;
 use celestia_proto::p2p::pb::{header_request::Data, HeaderRequest};
 use celestia_types::hash::Hash;
 use celestia_types::test_utils::ExtendedHeaderGenerator;
-use celestia_types::ExtendedHeader;
+use celestia_types::{AxisType, ExtendedHeader, VerificationOptions};
 use cid::Cid;
 use libp2p::identity::{self, Keypair};
 use tokio::sync::{mpsc, watch};
 
 use crate::{
     blockstore::InMemoryBlockstore,
+    daser::DEFAULT_MAX_SAMPLES_NEEDED,
     executor::timeout,
+    network::ProtocolVersions,
     node::NodeConfig,
-    p2p::{P2pCmd, P2pError},
+    p2p::{shwap::RequiredProofAxes, HeadQuorumResponse, P2pCmd, P2pError},
     peer_tracker::PeerTrackerInfo,
     store::InMemoryStore,
+    syncer::{DEFAULT_HEAD_QUORUM, DEFAULT_STALL_TIMEOUT},
     utils::OneshotResultSender,
 };
 
@@ -50,9 +53,14 @@ pub fn test_node_config() -> NodeConfig<InMemoryBlockstore, InMemoryStore> {
     NodeConfig {
         network_id: "private".to_string(),
         genesis_hash: None,
+        p2p_protocol_versions: ProtocolVersions::default(),
         p2p_local_keypair: node_keypair,
         p2p_bootnodes: vec![],
         p2p_listen_on: vec![],
+        das_max_samples_per_block: DEFAULT_MAX_SAMPLES_NEEDED,
+        syncer_head_quorum: DEFAULT_HEAD_QUORUM,
+        syncer_verification_options: VerificationOptions::default(),
+        syncer_stall_timeout: DEFAULT_STALL_TIMEOUT,
         blockstore: InMemoryBlockstore::new(),
         store: InMemoryStore::new(),
     }
@@ -85,6 +93,7 @@ pub struct MockP2pHandle {
     pub(crate) cmd_rx: mpsc::Receiver<P2pCmd>,
     pub(crate) header_sub_tx: watch::Sender<Option<ExtendedHeader>>,
     pub(crate) peer_tracker_tx: watch::Sender<PeerTrackerInfo>,
+    pub(crate) required_proof_axes: RequiredProofAxes,
 }
 
 impl MockP2pHandle {
@@ -156,6 +165,19 @@ impl MockP2pHandle {
         }
     }
 
+    /// Assert that a head request was sent to the [`P2p`] worker and obtain the requested quorum
+    /// and a response channel.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_head_request_cmd(
+        &mut self,
+    ) -> (usize, OneshotResultSender<HeadQuorumResponse, P2pError>) {
+        match self.expect_cmd().await {
+            P2pCmd::HeaderExHeadRequest { quorum, respond_to } => (quorum, respond_to),
+            cmd => panic!("Expecting HeaderExHeadRequest, but received: {cmd:?}"),
+        }
+    }
+
     /// Assert that a header request for height was sent to the [`P2p`] worker and obtain a response channel.
     ///
     /// [`P2p`]: crate::p2p::P2p
@@ -207,4 +229,9 @@ impl MockP2pHandle {
             cmd => panic!("Expecting GetShwapCid, but received: {cmd:?}"),
         }
     }
+
+    /// Returns the axis along which the sample of `cid` is required to be proven, if any.
+    pub fn required_proof_axis(&self, cid: &Cid) -> Option<AxisType> {
+        self.required_proof_axes.get(cid)
+    }
 }
@@ -40,6 +40,7 @@ static CUSTOM_TYPE_ATTRIBUTES: &[(&str, &str)] = &[
     (".share.p2p.shwap.Row", SERIALIZED),
     (".share.p2p.shwap.Sample", SERIALIZED),
     (".share.p2p.shwap.Data", SERIALIZED),
+    (".lumina.shwap.Column", SERIALIZED),
 ];
 
 #[rustfmt::skip]
@@ -90,12 +91,15 @@ fn main() -> Result<()> {
                 "vendor/share/eds/byzantine/pb/share.proto",
                 "vendor/cosmos/base/v1beta1/coin.proto",
                 "vendor/cosmos/base/abci/v1beta1/abci.proto",
+                "vendor/cosmos/bank/v1beta1/tx.proto",
                 "vendor/cosmos/crypto/multisig/v1beta1/multisig.proto",
+                "vendor/cosmos/crypto/secp256k1/keys.proto",
                 "vendor/cosmos/staking/v1beta1/query.proto",
                 "vendor/cosmos/tx/v1beta1/tx.proto",
                 "vendor/go-header/p2p/pb/header_request.proto",
+                "lumina/shwap/column.proto",
             ],
-            &["vendor", "vendor/nmt"],
+            &["vendor", "vendor/nmt", "."],
         )?;
 
     Ok(())
@@ -20,4 +20,12 @@ pub enum Error {
     /// Error propagated from the [`jsonrpsee`].
     #[error(transparent)]
     JsonRpc(#[from] jsonrpsee::core::Error),
+
+    /// Error propagated from the [`celestia_types`].
+    #[error(transparent)]
+    Celestia(#[from] celestia_types::Error),
+
+    /// Node didn't return any of the requested headers.
+    #[error("Missing headers in range {0}..{1}")]
+    MissingHeaders(u64, u64),
 }
@@ -5,26 +5,33 @@ mod blob;
 pub mod client;
 mod error;
 mod header;
+pub mod node;
 #[cfg(feature = "p2p")]
 mod p2p;
 mod share;
 mod state;
+#[cfg(not(target_arch = "wasm32"))]
+mod stream;
 
 pub use crate::blob::BlobClient;
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::client::Client;
 pub use crate::error::{Error, Result};
 pub use crate::header::HeaderClient;
+pub use crate::node::NodeClient;
 #[cfg(feature = "p2p")]
 #[cfg_attr(docs_rs, doc(cfg(feature = "p2p")))]
 pub use crate::p2p::P2PClient;
 pub use crate::share::ShareClient;
 pub use crate::state::StateClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::stream::{BlobStream, HeaderStream};
 
 /// Re-exports of all the RPC traits.
 pub mod prelude {
     pub use crate::BlobClient;
     pub use crate::HeaderClient;
+    pub use crate::NodeClient;
     #[cfg(feature = "p2p")]
     pub use crate::P2PClient;
     pub use crate::ShareClient;
@@ -0,0 +1,65 @@
+//! Types and methods of the celestia node's `node` namespace.
+
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// Permission level of the authentication token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthLevel {
+    /// Access to the public methods only.
+    Public,
+    /// Access to the methods that only read the node's state.
+    Read,
+    /// Access to the methods that can modify the node's state, e.g. submit blobs.
+    Write,
+    /// Access to all the methods, including administrative ones.
+    Admin,
+}
+
+/// Type of the celestia node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum NodeType {
+    /// Light node, which samples the blocks to verify their availability.
+    Light = 1,
+    /// Full node, which stores the blocks in their entirety.
+    Full = 2,
+    /// Bridge node, which bridges the consensus and data availability networks.
+    Bridge = 3,
+}
+
+/// Information about the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeInfo {
+    /// Type of the node.
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    /// Version of the node's API.
+    pub api_version: String,
+}
+
+#[rpc(client)]
+pub trait Node {
+    /// AuthNew signs and returns a new token with the given permissions.
+    #[method(name = "node.AuthNew")]
+    async fn node_auth_new(&self, perms: &[AuthLevel]) -> Result<String, Error>;
+
+    /// AuthVerify returns the permissions assigned to the given token.
+    #[method(name = "node.AuthVerify")]
+    async fn node_auth_verify(&self, token: &str) -> Result<Vec<AuthLevel>, Error>;
+
+    /// Info returns administrative information about the node.
+    #[method(name = "node.Info")]
+    async fn node_info(&self) -> Result<NodeInfo, Error>;
+
+    // This method does not report errors due to a workaround to a go-jsonrpc bug, see https://github.com/eigerco/celestia-node-rs/issues/53
+    /// LogLevelSet sets the given component log level to the given level.
+    #[method(name = "node.LogLevelSet")]
+    async fn node_log_level_set(&self, name: &str, level: &str);
+
+    /// Ready returns true once the node's RPC is ready to accept requests.
+    #[method(name = "node.Ready")]
+    async fn node_ready(&self) -> Result<bool, Error>;
+}
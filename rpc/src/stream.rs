@@ -0,0 +1,387 @@
+//! Typed streams built on top of the celestia node subscriptions.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use celestia_types::nmt::Namespace;
+use celestia_types::{Blob, ExtendedHeader};
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use jsonrpsee::core::client::Subscription;
+use jsonrpsee::core::Error as JrpcError;
+use tracing::{debug, warn};
+
+use crate::{BlobClient, Client, Error, HeaderClient, Result};
+
+/// Maximum amount of headers requested at once when filling a gap.
+const MAX_HEADERS_IN_RANGE_REQUEST: u64 = 64;
+/// Delay before the first reconnection attempt.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+/// Maximum delay between reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// Error message returned by the node if there are no blobs in requested namespaces.
+const BLOB_NOT_FOUND_MSG: &str = "blob: not found";
+
+/// A gapless stream of validated and verified [`ExtendedHeader`]s.
+///
+/// The stream subscribes to the `header.Subscribe` of the celestia node. Every
+/// received header is validated and verified against the previous one. If the
+/// node skips some heights or the connection is dropped, the stream reconnects
+/// and fetches the missing headers with `header.GetRangeByHeight`, so that
+/// consumers always receive consecutive heights.
+///
+/// An error is yielded if a header fails validation or verification. The stream
+/// then continues from the last verified header.
+///
+/// # Example
+///
+/// ```no_run
+/// use celestia_rpc::HeaderStream;
+/// use futures::StreamExt;
+///
+/// # async fn follow_headers() -> celestia_rpc::Result<()> {
+/// let mut headers = HeaderStream::new("ws://localhost:26658", None).await?;
+///
+/// while let Some(header) = headers.next().await {
+///     println!("New header: {}", header?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct HeaderStream {
+    inner: BoxStream<'static, Result<ExtendedHeader>>,
+}
+
+impl HeaderStream {
+    /// Subscribe to the headers of the node at `conn_str`.
+    ///
+    /// The first received header is only validated and it becomes the trusted
+    /// header for all the following ones. Only `ws` protocol supports subscriptions.
+    pub async fn new(conn_str: &str, auth_token: Option<&str>) -> Result<Self> {
+        let follower = HeaderFollower::new(conn_str, auth_token, None).await?;
+        Ok(HeaderStream::from_follower(follower))
+    }
+
+    /// Subscribe to the headers of the node at `conn_str`, starting right after `trusted`.
+    ///
+    /// All the headers between `trusted` and the head of the subscription are
+    /// fetched and verified before the new ones are yielded.
+    pub async fn new_from(
+        conn_str: &str,
+        auth_token: Option<&str>,
+        trusted: ExtendedHeader,
+    ) -> Result<Self> {
+        let follower = HeaderFollower::new(conn_str, auth_token, Some(trusted)).await?;
+        Ok(HeaderStream::from_follower(follower))
+    }
+
+    fn from_follower(follower: HeaderFollower) -> Self {
+        let inner = stream::unfold(follower, |mut follower| async move {
+            let header = follower.next().await;
+            Some((header, follower))
+        })
+        .boxed();
+
+        HeaderStream { inner }
+    }
+}
+
+impl Stream for HeaderStream {
+    type Item = Result<ExtendedHeader>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for HeaderStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderStream").finish_non_exhaustive()
+    }
+}
+
+/// A stream of all the [`Blob`]s submitted to the given namespaces, height by height.
+///
+/// The stream follows the verified headers of a [`HeaderStream`] and, for every
+/// height, fetches the blobs with `blob.GetAll`. Heights without any blobs in the
+/// namespaces are yielded with an empty list, so consumers can track the progress
+/// of the chain.
+///
+/// If fetching blobs fails, the error is yielded and the same height is retried
+/// when polled again.
+///
+/// # Example
+///
+/// ```no_run
+/// use celestia_rpc::BlobStream;
+/// use celestia_types::nmt::Namespace;
+/// use futures::StreamExt;
+///
+/// # async fn follow_rollup() -> celestia_rpc::Result<()> {
+/// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
+/// let mut blobs = BlobStream::new("ws://localhost:26658", None, &[namespace]).await?;
+///
+/// while let Some(res) = blobs.next().await {
+///     let (height, blobs) = res?;
+///     println!("Got {} blobs at height {height}", blobs.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct BlobStream {
+    inner: BoxStream<'static, Result<(u64, Vec<Blob>)>>,
+}
+
+impl BlobStream {
+    /// Subscribe to the blobs in `namespaces` from the node at `conn_str`.
+    ///
+    /// See [`HeaderStream::new`] for the details of how the headers are followed.
+    pub async fn new(
+        conn_str: &str,
+        auth_token: Option<&str>,
+        namespaces: &[Namespace],
+    ) -> Result<Self> {
+        let headers = HeaderFollower::new(conn_str, auth_token, None).await?;
+        Ok(BlobStream::from_follower(headers, namespaces))
+    }
+
+    /// Subscribe to the blobs in `namespaces` from the node at `conn_str`, starting right after `trusted`.
+    ///
+    /// See [`HeaderStream::new_from`] for the details of how the headers are followed.
+    pub async fn new_from(
+        conn_str: &str,
+        auth_token: Option<&str>,
+        namespaces: &[Namespace],
+        trusted: ExtendedHeader,
+    ) -> Result<Self> {
+        let headers = HeaderFollower::new(conn_str, auth_token, Some(trusted)).await?;
+        Ok(BlobStream::from_follower(headers, namespaces))
+    }
+
+    fn from_follower(headers: HeaderFollower, namespaces: &[Namespace]) -> Self {
+        let follower = BlobFollower {
+            headers,
+            namespaces: namespaces.to_vec(),
+            pending_height: None,
+        };
+
+        let inner = stream::unfold(follower, |mut follower| async move {
+            let blobs = follower.next().await;
+            Some((blobs, follower))
+        })
+        .boxed();
+
+        BlobStream { inner }
+    }
+}
+
+impl Stream for BlobStream {
+    type Item = Result<(u64, Vec<Blob>)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for BlobStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlobStream").finish_non_exhaustive()
+    }
+}
+
+/// State of a [`BlobStream`].
+struct BlobFollower {
+    headers: HeaderFollower,
+    namespaces: Vec<Namespace>,
+    /// Height of a verified header whose blobs weren't fetched yet.
+    pending_height: Option<u64>,
+}
+
+impl BlobFollower {
+    async fn next(&mut self) -> Result<(u64, Vec<Blob>)> {
+        let height = match self.pending_height {
+            Some(height) => height,
+            None => {
+                let header = self.headers.next().await?;
+                let height = header.height().value();
+                self.pending_height = Some(height);
+                height
+            }
+        };
+
+        let client = self.headers.connected_client().await;
+
+        let blobs = match client.blob_get_all(height, &self.namespaces).await {
+            Ok(blobs) => blobs,
+            Err(JrpcError::Call(e)) if e.message().contains(BLOB_NOT_FOUND_MSG) => Vec::new(),
+            Err(e) => {
+                if !matches!(e, JrpcError::Call(_)) {
+                    self.headers.disconnect();
+                }
+                return Err(e.into());
+            }
+        };
+
+        self.pending_height = None;
+
+        Ok((height, blobs))
+    }
+}
+
+/// State of a reconnecting subscription yielding consecutive verified headers.
+struct HeaderFollower {
+    conn_str: String,
+    auth_token: Option<String>,
+    client: Client,
+    subscription: Option<Subscription<ExtendedHeader>>,
+    last_verified: Option<ExtendedHeader>,
+    pending: VecDeque<ExtendedHeader>,
+}
+
+impl HeaderFollower {
+    async fn new(
+        conn_str: &str,
+        auth_token: Option<&str>,
+        trusted: Option<ExtendedHeader>,
+    ) -> Result<Self> {
+        let client = Client::new(conn_str, auth_token).await?;
+        let subscription = client.header_subscribe().await?;
+
+        Ok(HeaderFollower {
+            conn_str: conn_str.to_owned(),
+            auth_token: auth_token.map(ToOwned::to_owned),
+            client,
+            subscription: Some(subscription),
+            last_verified: trusted,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Get the client, reconnecting first if the connection was dropped.
+    async fn connected_client(&mut self) -> &Client {
+        if self.subscription.is_none() {
+            self.reconnect().await;
+        }
+
+        &self.client
+    }
+
+    /// Drop the current connection, so that the next read reconnects.
+    fn disconnect(&mut self) {
+        self.subscription = None;
+    }
+
+    /// Get the next consecutive verified header.
+    async fn next(&mut self) -> Result<ExtendedHeader> {
+        loop {
+            if let Some(header) = self.pending.pop_front() {
+                return Ok(header);
+            }
+
+            let header = self.next_from_subscription().await;
+            self.process(header).await?;
+        }
+    }
+
+    async fn next_from_subscription(&mut self) -> ExtendedHeader {
+        loop {
+            let Some(subscription) = self.subscription.as_mut() else {
+                self.reconnect().await;
+                continue;
+            };
+
+            match subscription.next().await {
+                Some(Ok(header)) => return header,
+                Some(Err(e)) => warn!("Header subscription failed: {e}"),
+                None => warn!("Header subscription closed"),
+            }
+
+            self.subscription = None;
+        }
+    }
+
+    async fn reconnect(&mut self) {
+        let mut delay = MIN_RECONNECT_DELAY;
+
+        loop {
+            match self.try_reconnect().await {
+                Ok(()) => return,
+                Err(e) => warn!("Reconnecting to {} failed: {e}", self.conn_str),
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    async fn try_reconnect(&mut self) -> Result<()> {
+        debug!("Reconnecting to {}", self.conn_str);
+
+        let client = Client::new(&self.conn_str, self.auth_token.as_deref()).await?;
+        let subscription = client.header_subscribe().await?;
+
+        self.client = client;
+        self.subscription = Some(subscription);
+
+        Ok(())
+    }
+
+    /// Verify the header, filling the gap between it and the last verified one.
+    async fn process(&mut self, header: ExtendedHeader) -> Result<()> {
+        header.validate()?;
+
+        let Some(mut last) = self.last_verified.clone() else {
+            self.push_verified(header);
+            return Ok(());
+        };
+
+        if header.height() <= last.height() {
+            // already yielded, e.g. received again after reconnecting
+            return Ok(());
+        }
+
+        let height = header.height().value();
+
+        while last.height().value() + 1 < height {
+            let to = height.min(last.height().value() + 1 + MAX_HEADERS_IN_RANGE_REQUEST);
+            debug!("Filling gap from {} to {to}", last.height());
+
+            let headers = match self.client.header_get_range_by_height(&last, to).await {
+                Ok(headers) => headers,
+                Err(e) => {
+                    // the gap is filled again when the next header arrives
+                    self.disconnect();
+                    return Err(e.into());
+                }
+            };
+
+            for header in &headers {
+                header.validate()?;
+            }
+            last.verify_adjacent_range(&headers)?;
+
+            let Some(new_last) = headers.last().cloned() else {
+                self.disconnect();
+                return Err(Error::MissingHeaders(last.height().value() + 1, to));
+            };
+
+            last = new_last;
+            for header in headers {
+                self.push_verified(header);
+            }
+        }
+
+        last.verify(&header)?;
+        self.push_verified(header);
+
+        Ok(())
+    }
+
+    fn push_verified(&mut self, header: ExtendedHeader) {
+        self.last_verified = Some(header.clone());
+        self.pending.push_back(header);
+    }
+}
@@ -3,11 +3,15 @@
 use std::time::Duration;
 
 use celestia_rpc::prelude::*;
+use celestia_rpc::BlobStream;
 use celestia_types::{Blob, Commitment};
+use futures::StreamExt;
 
 pub mod utils;
 
-use crate::utils::client::{blob_submit, new_test_client, AuthLevel};
+use crate::utils::client::{
+    blob_submit, new_test_client, test_client_url, token_from_env, AuthLevel,
+};
 use crate::utils::{random_bytes, random_bytes_array, random_ns};
 
 #[tokio::test]
@@ -163,3 +167,32 @@ async fn blob_get_get_proof_wrong_commitment() {
         .await
         .unwrap_err();
 }
+
+#[tokio::test]
+async fn blob_stream() {
+    let client = new_test_client(AuthLevel::Write).await.unwrap();
+    let namespace = random_ns();
+    let data = random_bytes(5);
+    let blob = Blob::new(namespace, data).unwrap();
+
+    let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
+    let trusted = client
+        .header_get_by_height(submitted_height - 2)
+        .await
+        .unwrap();
+
+    let url = test_client_url();
+    let token = token_from_env(AuthLevel::Read).unwrap();
+    let mut blobs = BlobStream::new_from(&url, token.as_deref(), &[namespace], trusted)
+        .await
+        .unwrap();
+
+    // height without blobs in the namespace is reported as empty
+    let (height, received_blobs) = blobs.next().await.unwrap().unwrap();
+    assert_eq!(height, submitted_height - 1);
+    assert!(received_blobs.is_empty());
+
+    let (height, received_blobs) = blobs.next().await.unwrap().unwrap();
+    assert_eq!(height, submitted_height);
+    assert_eq!(received_blobs, vec![blob]);
+}
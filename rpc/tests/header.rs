@@ -1,10 +1,12 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 use celestia_rpc::prelude::*;
+use celestia_rpc::HeaderStream;
+use futures::StreamExt;
 
 pub mod utils;
 
-use crate::utils::client::{new_test_client, AuthLevel};
+use crate::utils::client::{new_test_client, test_client_url, token_from_env, AuthLevel};
 
 #[tokio::test]
 async fn local_head() {
@@ -102,6 +104,37 @@ async fn subscribe() {
     header1.verify(&header2).unwrap();
 }
 
+#[tokio::test]
+async fn header_stream() {
+    let url = test_client_url();
+    let token = token_from_env(AuthLevel::Read).unwrap();
+    let mut headers = HeaderStream::new(&url, token.as_deref()).await.unwrap();
+
+    let header1 = headers.next().await.unwrap().unwrap();
+    let header2 = headers.next().await.unwrap().unwrap();
+
+    assert_eq!(header1.height().increment(), header2.height());
+}
+
+#[tokio::test]
+async fn header_stream_fills_gap() {
+    let client = new_test_client(AuthLevel::Read).await.unwrap();
+    let genesis_header = client.header_get_by_height(1).await.unwrap();
+
+    let url = test_client_url();
+    let token = token_from_env(AuthLevel::Read).unwrap();
+    let mut headers = HeaderStream::new_from(&url, token.as_deref(), genesis_header.clone())
+        .await
+        .unwrap();
+
+    let mut trusted = genesis_header;
+    for _ in 0..3 {
+        let header = headers.next().await.unwrap().unwrap();
+        trusted.verify_adjacent_range(&[header.clone()]).unwrap();
+        trusted = header;
+    }
+}
+
 #[tokio::test]
 async fn sync_state() {
     let client = new_test_client(AuthLevel::Read).await.unwrap();
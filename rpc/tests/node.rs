@@ -0,0 +1,73 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use celestia_rpc::node::NodeType;
+use celestia_rpc::prelude::*;
+
+pub mod utils;
+
+use crate::utils::client::{new_test_client, new_test_client_with_token, AuthLevel};
+
+#[tokio::test]
+async fn info() {
+    let client = new_test_client(AuthLevel::Admin).await.unwrap();
+
+    let info = client.node_info().await.unwrap();
+
+    assert_eq!(info.node_type, NodeType::Bridge);
+    assert!(!info.api_version.is_empty());
+}
+
+#[tokio::test]
+async fn ready() {
+    let client = new_test_client(AuthLevel::Read).await.unwrap();
+
+    assert!(client.node_ready().await.unwrap());
+}
+
+#[tokio::test]
+async fn log_level_set() {
+    let client = new_test_client(AuthLevel::Admin).await.unwrap();
+
+    client.node_log_level_set("header", "debug").await.unwrap();
+    client.node_log_level_set("header", "info").await.unwrap();
+}
+
+#[tokio::test]
+async fn auth_new_and_verify() {
+    let client = new_test_client(AuthLevel::Admin).await.unwrap();
+
+    for perms in [
+        vec![AuthLevel::Public],
+        vec![AuthLevel::Public, AuthLevel::Read],
+        vec![
+            AuthLevel::Public,
+            AuthLevel::Read,
+            AuthLevel::Write,
+            AuthLevel::Admin,
+        ],
+    ] {
+        let token = client.node_auth_new(&perms).await.unwrap();
+        let verified = client.node_auth_verify(&token).await.unwrap();
+
+        assert_eq!(verified, perms);
+    }
+}
+
+#[tokio::test]
+async fn auth_new_token_usable() {
+    let admin_client = new_test_client(AuthLevel::Admin).await.unwrap();
+
+    let token = admin_client
+        .node_auth_new(&[AuthLevel::Public, AuthLevel::Read])
+        .await
+        .unwrap();
+
+    let read_client = new_test_client_with_token(&token).await.unwrap();
+
+    read_client.header_local_head().await.unwrap();
+    // admin methods are rejected with read-only token
+    read_client
+        .node_auth_new(&[AuthLevel::Admin])
+        .await
+        .unwrap_err();
+}
@@ -2,6 +2,7 @@ use std::env;
 use std::sync::OnceLock;
 
 use anyhow::Result;
+pub use celestia_rpc::node::AuthLevel;
 use celestia_rpc::prelude::*;
 use celestia_rpc::Client;
 use celestia_types::{blob::GasPrice, Blob};
@@ -16,15 +17,7 @@ async fn write_lock() -> MutexGuard<'static, ()> {
     LOCK.get_or_init(|| Mutex::new(())).lock().await
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum AuthLevel {
-    Public,
-    Read,
-    Write,
-    Admin,
-}
-
-fn token_from_env(auth_level: AuthLevel) -> Result<Option<String>> {
+pub fn token_from_env(auth_level: AuthLevel) -> Result<Option<String>> {
     match auth_level {
         AuthLevel::Public => Ok(None),
         AuthLevel::Read => Ok(Some(env::var("CELESTIA_NODE_AUTH_TOKEN_READ")?)),
@@ -40,9 +33,23 @@ fn env_or(var_name: &str, or_value: &str) -> String {
 pub async fn new_test_client(auth_level: AuthLevel) -> Result<Client> {
     let _ = dotenvy::dotenv();
     let token = token_from_env(auth_level)?;
-    let url = env_or("CELESTIA_RPC_URL", CELESTIA_RPC_URL);
+    new_client(token.as_deref()).await
+}
+
+pub async fn new_test_client_with_token(token: &str) -> Result<Client> {
+    let _ = dotenvy::dotenv();
+    new_client(Some(token)).await
+}
+
+pub fn test_client_url() -> String {
+    let _ = dotenvy::dotenv();
+    env_or("CELESTIA_RPC_URL", CELESTIA_RPC_URL)
+}
+
+async fn new_client(token: Option<&str>) -> Result<Client> {
+    let url = test_client_url();
 
-    let client = Client::new(&url, token.as_deref()).await?;
+    let client = Client::new(&url, token).await?;
 
     // minimum 2 blocks
     client.header_wait_for_height(2).await?;
@@ -0,0 +1,144 @@
+//! Logic shared by the [`Row`] and [`Column`], which differ only in the axis
+//! of the [`ExtendedDataSquare`] they span.
+//!
+//! [`Row`]: crate::row::Row
+//! [`Column`]: crate::column::Column
+//! [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+
+use blockstore::block::CidError;
+use bytes::{Buf, BufMut, BytesMut};
+use cid::CidGeneric;
+use multihash::Multihash;
+use nmt_rs::NamespaceMerkleHasher;
+
+use crate::consts::appconsts::SHARE_SIZE;
+use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt, NS_SIZE};
+use crate::rsmt2d::{is_ods_square, AxisType};
+use crate::{DataAvailabilityHeader, Error, Result};
+
+/// Number of bytes needed to represent the id of a row or a column in `multihash`.
+pub(crate) const AXIS_ID_SIZE: usize = 10;
+
+/// Encode the id of a row or a column.
+pub(crate) fn encode_id(block_height: u64, index: u16, bytes: &mut BytesMut) {
+    bytes.reserve(AXIS_ID_SIZE);
+    bytes.put_u64(block_height);
+    bytes.put_u16(index);
+}
+
+/// Decode the id of a row or a column, returning its block height and index.
+pub(crate) fn decode_id(mut buffer: &[u8]) -> Result<(u64, u16), CidError> {
+    if buffer.len() != AXIS_ID_SIZE {
+        return Err(CidError::InvalidMultihashLength(buffer.len()));
+    }
+
+    let block_height = buffer.get_u64();
+    let index = buffer.get_u16();
+
+    if block_height == 0 {
+        return Err(CidError::InvalidCid("Zero block height".to_string()));
+    }
+
+    Ok((block_height, index))
+}
+
+/// Decode the id of a row or a column from the `Cid` with the expected codec and multihash code.
+pub(crate) fn id_from_cid<const S: usize>(
+    cid: &CidGeneric<S>,
+    codec: u64,
+    multihash_code: u64,
+) -> Result<(u64, u16), CidError> {
+    if cid.codec() != codec {
+        return Err(CidError::InvalidCidCodec(cid.codec()));
+    }
+
+    let hash = cid.hash();
+
+    let size = hash.size() as usize;
+    if size != AXIS_ID_SIZE {
+        return Err(CidError::InvalidMultihashLength(size));
+    }
+
+    if hash.code() != multihash_code {
+        return Err(CidError::InvalidMultihashCode(hash.code(), multihash_code));
+    }
+
+    decode_id(hash.digest())
+}
+
+/// Create the `Cid` of a row or a column id with the given codec and multihash code.
+pub(crate) fn id_to_cid(
+    block_height: u64,
+    index: u16,
+    codec: u64,
+    multihash_code: u64,
+) -> CidGeneric<AXIS_ID_SIZE> {
+    let mut bytes = BytesMut::with_capacity(AXIS_ID_SIZE);
+    encode_id(block_height, index, &mut bytes);
+    // length is correct, so unwrap is safe
+    let mh = Multihash::wrap(multihash_code, &bytes[..]).unwrap();
+
+    CidGeneric::new_v1(codec, mh)
+}
+
+/// Verify the shares of a row or a column against its root from the DAH.
+pub(crate) fn verify(
+    axis: AxisType,
+    index: u16,
+    shares: &[Vec<u8>],
+    dah: &DataAvailabilityHeader,
+) -> Result<()> {
+    let square_width = u16::try_from(shares.len()).map_err(|_| Error::EdsInvalidDimentions)?;
+
+    let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+
+    for i in 0..square_width {
+        let share = &shares[usize::from(i)];
+
+        let (row, col) = match axis {
+            AxisType::Row => (index, i),
+            AxisType::Col => (i, index),
+        };
+
+        let ns = if is_ods_square(row, col, square_width) {
+            Namespace::from_raw(&share[..NS_SIZE])?
+        } else {
+            Namespace::PARITY_SHARE
+        };
+
+        tree.push_leaf(share, *ns).map_err(Error::Nmt)?;
+    }
+
+    let Some(root) = dah.root(axis, index) else {
+        return Err(match axis {
+            AxisType::Row => Error::EdsIndexOutOfRange(index, 0),
+            AxisType::Col => Error::EdsIndexOutOfRange(0, index),
+        });
+    };
+
+    if tree.root().hash() != root.hash() {
+        return Err(Error::RootMismatch);
+    }
+
+    Ok(())
+}
+
+/// Recover all the shares of a row or a column from its data half.
+pub(crate) fn extend_half(mut shares: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let data_shares = shares.len();
+
+    shares.resize(shares.len() * 2, vec![0; SHARE_SIZE]);
+
+    leopard_codec::encode(&mut shares, data_shares)?;
+
+    Ok(shares)
+}
+
+/// Take the data half of a row or a column.
+///
+/// Parity shares aren't transmitted over shwap, just data shares.
+pub(crate) fn data_half(mut shares: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let square_width = shares.len();
+    shares.truncate(square_width / 2);
+    shares
+}
@@ -5,8 +5,10 @@ use celestia_tendermint_proto::Protobuf;
 use serde::{Deserialize, Serialize};
 
 mod commitment;
+mod msg_pay_for_blobs;
 
 pub use self::commitment::Commitment;
+pub use self::msg_pay_for_blobs::MsgPayForBlobs;
 use crate::consts::appconsts;
 use crate::nmt::Namespace;
 use crate::{bail_validation, Error, Result, Share};
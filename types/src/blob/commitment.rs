@@ -45,7 +45,7 @@ use crate::{InfoByte, Share};
 ///
 /// [`Blob`]: crate::Blob
 /// [`Share`]: crate::share::Share
-/// [`MsgPayForBlobs`]: celestia_proto::celestia::blob::v1::MsgPayForBlobs
+/// [`MsgPayForBlobs`]: crate::blob::MsgPayForBlobs
 /// [`merkle hash`]: celestia_tendermint::merkle::simple_hash_from_byte_vectors
 /// [`Nmt`]: crate::nmt::Nmt
 /// [`ExtendedDataSquare`]: crate::ExtendedDataSquare
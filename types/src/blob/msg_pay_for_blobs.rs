@@ -0,0 +1,157 @@
+use celestia_proto::celestia::blob::v1::MsgPayForBlobs as RawMsgPayForBlobs;
+use celestia_tendermint_proto::Protobuf;
+use prost::Message;
+use prost_types::Any;
+
+use crate::nmt::Namespace;
+use crate::state::AccAddress;
+use crate::{bail_validation, validation_error, Blob, Commitment, Error, Result};
+
+/// A message paying for the inclusion of one or more [`Blob`]s in the block.
+///
+/// The message carries only the [`Commitment`]s of the blobs, while the blobs
+/// themselves are attached to the transaction in the [`BlobTx`] envelope.
+///
+/// [`BlobTx`]: crate::state::BlobTx
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgPayForBlobs {
+    /// Address of the account paying for the blobs.
+    pub signer: AccAddress,
+    /// Namespaces the blobs are associated with.
+    pub namespaces: Vec<Namespace>,
+    /// Sizes of the blobs in bytes.
+    pub blob_sizes: Vec<u32>,
+    /// Commitments of the blobs.
+    pub share_commitments: Vec<Commitment>,
+    /// Versions of the share format of the blobs.
+    pub share_versions: Vec<u32>,
+}
+
+impl MsgPayForBlobs {
+    /// The type url of the message.
+    pub const TYPE_URL: &'static str = "/celestia.blob.v1.MsgPayForBlobs";
+
+    /// Create a message paying for the given blobs.
+    ///
+    /// The [`Commitment`]s are computed from the blobs' data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `blobs` is empty, any of the
+    /// blobs is bigger than [`u32::MAX`] or if computing the commitment fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::blob::MsgPayForBlobs;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::Blob;
+    /// # let signer = "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3".parse().unwrap();
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
+    /// let blob = Blob::new(namespace, b"some data to store on blockchain".to_vec())
+    ///     .expect("Failed to create a blob");
+    ///
+    /// let msg = MsgPayForBlobs::new(&[blob.clone()], signer).unwrap();
+    ///
+    /// assert_eq!(msg.share_commitments, vec![blob.commitment]);
+    /// ```
+    pub fn new(blobs: &[Blob], signer: AccAddress) -> Result<Self> {
+        if blobs.is_empty() {
+            bail_validation!("no blobs provided")
+        }
+
+        let mut namespaces = Vec::with_capacity(blobs.len());
+        let mut blob_sizes = Vec::with_capacity(blobs.len());
+        let mut share_commitments = Vec::with_capacity(blobs.len());
+        let mut share_versions = Vec::with_capacity(blobs.len());
+
+        for blob in blobs {
+            let size =
+                u32::try_from(blob.data.len()).map_err(|_| Error::BlobTooLarge(blob.data.len()))?;
+            let commitment = Commitment::from_blob(blob.namespace, blob.share_version, &blob.data)?;
+
+            namespaces.push(blob.namespace);
+            blob_sizes.push(size);
+            share_commitments.push(commitment);
+            share_versions.push(u32::from(blob.share_version));
+        }
+
+        Ok(MsgPayForBlobs {
+            signer,
+            namespaces,
+            blob_sizes,
+            share_commitments,
+            share_versions,
+        })
+    }
+}
+
+impl Protobuf<RawMsgPayForBlobs> for MsgPayForBlobs {}
+
+impl TryFrom<RawMsgPayForBlobs> for MsgPayForBlobs {
+    type Error = Error;
+
+    fn try_from(value: RawMsgPayForBlobs) -> Result<Self, Self::Error> {
+        let signer = value.signer.parse()?;
+        let namespaces = value
+            .namespaces
+            .iter()
+            .map(|ns| Namespace::from_raw(ns))
+            .collect::<Result<_>>()?;
+        let share_commitments = value
+            .share_commitments
+            .iter()
+            .map(|commitment| {
+                commitment
+                    .as_slice()
+                    .try_into()
+                    .map(Commitment)
+                    .map_err(|_| {
+                        validation_error!(
+                            "commitment is not a size of a sha256: {}",
+                            commitment.len()
+                        )
+                        .into()
+                    })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(MsgPayForBlobs {
+            signer,
+            namespaces,
+            blob_sizes: value.blob_sizes,
+            share_commitments,
+            share_versions: value.share_versions,
+        })
+    }
+}
+
+impl From<MsgPayForBlobs> for RawMsgPayForBlobs {
+    fn from(value: MsgPayForBlobs) -> Self {
+        RawMsgPayForBlobs {
+            signer: value.signer.to_string(),
+            namespaces: value
+                .namespaces
+                .iter()
+                .map(|ns| ns.as_bytes().to_vec())
+                .collect(),
+            blob_sizes: value.blob_sizes,
+            share_commitments: value
+                .share_commitments
+                .iter()
+                .map(|commitment| commitment.0.to_vec())
+                .collect(),
+            share_versions: value.share_versions,
+        }
+    }
+}
+
+impl From<MsgPayForBlobs> for Any {
+    fn from(value: MsgPayForBlobs) -> Self {
+        Any {
+            type_url: MsgPayForBlobs::TYPE_URL.to_owned(),
+            value: RawMsgPayForBlobs::from(value).encode_to_vec(),
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Types related to columns
+//!
+//! Column in Celestia is understood as all the [`Share`]s in a particular
+//! column of the [`ExtendedDataSquare`].
+//!
+//! [`Share`]: crate::Share
+//! [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+
+use blockstore::block::CidError;
+use bytes::BytesMut;
+use celestia_proto::lumina::shwap::Column as RawColumn;
+use celestia_tendermint_proto::Protobuf;
+use cid::CidGeneric;
+use serde::{Deserialize, Serialize};
+
+use crate::axis::{self, AXIS_ID_SIZE};
+use crate::rsmt2d::{AxisType, ExtendedDataSquare};
+use crate::{DataAvailabilityHeader, Error, Result};
+
+/// Number of bytes needed to represent [`ColumnId`] in `multihash`.
+pub(crate) const COLUMN_ID_SIZE: usize = AXIS_ID_SIZE;
+/// The code of the [`ColumnId`] hashing algorithm in `multihash`.
+pub const COLUMN_ID_MULTIHASH_CODE: u64 = 0x7831;
+/// The id of codec used for the [`ColumnId`] in `Cid`s.
+pub const COLUMN_ID_CODEC: u64 = 0x7830;
+
+/// Represents particular column in a specific Data Square,
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ColumnId {
+    block_height: u64,
+    index: u16,
+}
+
+/// Column together with the data
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "RawColumn", into = "RawColumn")]
+pub struct Column {
+    /// Location of the column in the EDS and associated block height
+    pub id: ColumnId,
+    /// Shares contained in the column
+    pub shares: Vec<Vec<u8>>,
+}
+
+impl Column {
+    /// Create Column with the given index from EDS
+    pub fn new(index: u16, eds: &ExtendedDataSquare, block_height: u64) -> Result<Self> {
+        let id = ColumnId::new(index, block_height)?;
+        let shares = eds.column(index)?;
+
+        Ok(Column { id, shares })
+    }
+
+    /// verify the column against roots from DAH
+    pub fn verify(&self, dah: &DataAvailabilityHeader) -> Result<()> {
+        axis::verify(AxisType::Col, self.id.index, &self.shares, dah)
+    }
+}
+
+impl Protobuf<RawColumn> for Column {}
+
+impl TryFrom<RawColumn> for Column {
+    type Error = Error;
+
+    fn try_from(column: RawColumn) -> Result<Column, Self::Error> {
+        let id = ColumnId::decode(&column.column_id)?;
+        let shares = axis::extend_half(column.column_half)?;
+
+        Ok(Column { id, shares })
+    }
+}
+
+impl From<Column> for RawColumn {
+    fn from(column: Column) -> RawColumn {
+        let mut column_id_bytes = BytesMut::new();
+        column.id.encode(&mut column_id_bytes);
+
+        RawColumn {
+            column_id: column_id_bytes.to_vec(),
+            column_half: axis::data_half(column.shares),
+        }
+    }
+}
+
+impl ColumnId {
+    /// Create a new [`ColumnId`] for the particular block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the block height is invalid.
+    pub fn new(index: u16, block_height: u64) -> Result<Self> {
+        if block_height == 0 {
+            return Err(Error::ZeroBlockHeight);
+        }
+
+        Ok(Self {
+            index,
+            block_height,
+        })
+    }
+
+    /// A height of the block which contains the data.
+    pub fn block_height(&self) -> u64 {
+        self.block_height
+    }
+
+    /// An index of the column in the [`ExtendedDataSquare`].
+    ///
+    /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub(crate) fn encode(&self, bytes: &mut BytesMut) {
+        axis::encode_id(self.block_height, self.index, bytes);
+    }
+
+    pub(crate) fn decode(buffer: &[u8]) -> Result<Self, CidError> {
+        let (block_height, index) = axis::decode_id(buffer)?;
+
+        Ok(Self {
+            block_height,
+            index,
+        })
+    }
+}
+
+impl<const S: usize> TryFrom<CidGeneric<S>> for ColumnId {
+    type Error = CidError;
+
+    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        let (block_height, index) =
+            axis::id_from_cid(&cid, COLUMN_ID_CODEC, COLUMN_ID_MULTIHASH_CODE)?;
+
+        Ok(Self {
+            block_height,
+            index,
+        })
+    }
+}
+
+impl From<ColumnId> for CidGeneric<COLUMN_ID_SIZE> {
+    fn from(column: ColumnId) -> Self {
+        axis::id_to_cid(
+            column.block_height,
+            column.index,
+            COLUMN_ID_CODEC,
+            COLUMN_ID_MULTIHASH_CODE,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::appconsts::SHARE_SIZE;
+    use crate::test_utils::generate_eds;
+    use multihash::Multihash;
+
+    #[test]
+    fn round_trip_test() {
+        let column_id = ColumnId::new(5, 100).unwrap();
+        let cid = CidGeneric::from(column_id);
+
+        let multihash = cid.hash();
+        assert_eq!(multihash.code(), COLUMN_ID_MULTIHASH_CODE);
+        assert_eq!(multihash.size(), COLUMN_ID_SIZE as u8);
+
+        let deserialized_column_id = ColumnId::try_from(cid).unwrap();
+        assert_eq!(column_id, deserialized_column_id);
+    }
+
+    #[test]
+    fn index_calculation() {
+        let height = 100;
+        let shares = vec![vec![0; SHARE_SIZE]; 8 * 8];
+        let eds = ExtendedDataSquare::new(shares, "codec".to_string()).unwrap();
+
+        Column::new(1, &eds, height).unwrap();
+        Column::new(7, &eds, height).unwrap();
+        let column_err = Column::new(8, &eds, height).unwrap_err();
+        assert!(matches!(column_err, Error::EdsIndexOutOfRange(0, 8)));
+        let column_err = Column::new(100, &eds, height).unwrap_err();
+        assert!(matches!(column_err, Error::EdsIndexOutOfRange(0, 100)));
+    }
+
+    #[test]
+    fn from_buffer() {
+        let bytes = [
+            0x01, // CIDv1
+            0xB0, 0xF0, 0x01, // CID codec = 7830
+            0xB1, 0xF0, 0x01, // multihash code = 7831
+            0x0A, // len = COLUMN_ID_SIZE = 10
+            0, 0, 0, 0, 0, 0, 0, 64, // block height = 64
+            0, 7, // column index = 7
+        ];
+
+        let cid = CidGeneric::<COLUMN_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
+        assert_eq!(cid.codec(), COLUMN_ID_CODEC);
+        let mh = cid.hash();
+        assert_eq!(mh.code(), COLUMN_ID_MULTIHASH_CODE);
+        assert_eq!(mh.size(), COLUMN_ID_SIZE as u8);
+        let column_id = ColumnId::try_from(cid).unwrap();
+        assert_eq!(column_id.index, 7);
+        assert_eq!(column_id.block_height, 64);
+    }
+
+    #[test]
+    fn multihash_invalid_code() {
+        let multihash = Multihash::<COLUMN_ID_SIZE>::wrap(999, &[0; COLUMN_ID_SIZE]).unwrap();
+        let cid = CidGeneric::<COLUMN_ID_SIZE>::new_v1(COLUMN_ID_CODEC, multihash);
+        let column_err = ColumnId::try_from(cid).unwrap_err();
+        assert_eq!(
+            column_err,
+            CidError::InvalidMultihashCode(999, COLUMN_ID_MULTIHASH_CODE)
+        );
+    }
+
+    #[test]
+    fn cid_invalid_codec() {
+        let multihash =
+            Multihash::<COLUMN_ID_SIZE>::wrap(COLUMN_ID_MULTIHASH_CODE, &[0; COLUMN_ID_SIZE])
+                .unwrap();
+        let cid = CidGeneric::<COLUMN_ID_SIZE>::new_v1(1234, multihash);
+        let column_err = ColumnId::try_from(cid).unwrap_err();
+        assert_eq!(column_err, CidError::InvalidCidCodec(1234));
+    }
+
+    #[test]
+    fn test_validate() {
+        for _ in 0..10 {
+            let eds = generate_eds(2 << (rand::random::<usize>() % 8));
+            let dah = DataAvailabilityHeader::from_eds(&eds);
+
+            let index = rand::random::<u16>() % eds.square_width();
+
+            let column = Column::new(index, &eds, 1).unwrap();
+
+            let encoded = column.encode_vec().unwrap();
+            let decoded = Column::decode(encoded.as_ref()).unwrap();
+
+            decoded.verify(&dah).unwrap();
+        }
+    }
+
+    #[test]
+    fn verify_against_wrong_root() {
+        let eds = generate_eds(8);
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+
+        let mut column = Column::new(2, &eds, 1).unwrap();
+        column.id.index = 3;
+
+        assert!(matches!(column.verify(&dah), Err(Error::RootMismatch)));
+    }
+}
@@ -86,6 +86,14 @@ pub mod appconsts {
 
         /// The maximum value a share version can be.
         pub const MAX_SHARE_VERSION: u8 = 127;
+
+        /// The denomination of the native token used for fees and staking.
+        pub const BOND_DENOM: &str = "utia";
+
+        /// The type id of the [`BlobTx`] envelope.
+        ///
+        /// [`BlobTx`]: crate::state::BlobTx
+        pub const BLOB_TX_TYPE_ID: &str = "BLOB";
     }
 }
 
@@ -8,7 +8,7 @@ use crate::consts::data_availability_header::{
     MAX_EXTENDED_SQUARE_WIDTH, MIN_EXTENDED_SQUARE_WIDTH,
 };
 use crate::hash::Hash;
-use crate::nmt::{NamespacedHash, NamespacedHashExt};
+use crate::nmt::{Namespace, NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher};
 use crate::rsmt2d::AxisType;
 use crate::{bail_validation, Error, ExtendedDataSquare, Result, ValidateBasic, ValidationError};
 
@@ -139,6 +139,18 @@ impl DataAvailabilityHeader {
         self.column_roots.get(column).cloned()
     }
 
+    /// Get the indexes of the rows which roots' namespace ranges contain the `namespace`.
+    ///
+    /// Only those rows may include the shares of the `namespace`, its absence in all the
+    /// other rows is proven by their roots.
+    pub fn row_indexes_for_namespace(&self, namespace: Namespace) -> Vec<u16> {
+        (0..self.square_width())
+            .filter(|&row| {
+                self.row_roots[usize::from(row)].contains::<NamespacedSha2Hasher>(*namespace)
+            })
+            .collect()
+    }
+
     /// Compute the combined hash of all rows and columns.
     ///
     /// This is the data commitment for the block.
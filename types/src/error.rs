@@ -85,6 +85,10 @@ pub enum Error {
     )]
     ShareSequenceLenExceeded(usize),
 
+    /// Blob is too large to be paid for.
+    #[error("Blob size must fit into u32, got {0} bytes")]
+    BlobTooLarge(usize),
+
     /// Invalid namespace in version 0.
     #[error("Invalid namespace v0")]
     InvalidNamespaceV0,
@@ -172,6 +176,14 @@ pub enum Error {
     #[error("Invalid dimensions of EDS")]
     EdsInvalidDimentions,
 
+    /// Namespaced data doesn't cover exactly the rows that can contain the namespace.
+    #[error("Namespaced data doesn't match the rows of the namespace")]
+    NamespacedDataMismatch,
+
+    /// Namespace has shares in the row, while its absence was expected.
+    #[error("Namespace is present in row {0}")]
+    NamespacePresent(u16),
+
     /// Zero block height.
     #[error("Invalid zero block height")]
     ZeroBlockHeight,
@@ -204,6 +216,10 @@ pub enum VerificationError {
     #[error("Not enought voting power (got {0}, needed {1})")]
     NotEnoughVotingPower(u64, u64),
 
+    /// Trusted header is older than the trusting period.
+    #[error("Trusted header {0} is outside of the trusting period, a new trusted checkpoint is required")]
+    TrustedHeaderExpired(u64),
+
     /// Other errors that can happen during verification.
     #[error("{0}")]
     Other(String),
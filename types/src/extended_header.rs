@@ -1,5 +1,4 @@
 use std::fmt::{Display, Formatter};
-#[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
 use std::time::Duration;
 
 use celestia_proto::header::pb::ExtendedHeader as RawExtendedHeader;
@@ -10,10 +9,13 @@ use celestia_tendermint::{validator, Hash, Time};
 use celestia_tendermint_proto::Protobuf;
 use serde::{Deserialize, Serialize};
 
-use crate::trust_level::DEFAULT_TRUST_LEVEL;
+use crate::trust_level::{TrustLevelRatio, DEFAULT_TRUST_LEVEL};
 use crate::validator_set::ValidatorSetExt;
+#[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
+use crate::verification_error;
 use crate::{
     bail_validation, bail_verification, DataAvailabilityHeader, Error, Result, ValidateBasic,
+    VerificationError,
 };
 
 /// Information about a tendermint validator.
@@ -21,8 +23,38 @@ pub type Validator = validator::Info;
 /// A collection of the tendermint validators.
 pub type ValidatorSet = validator::Set;
 
-#[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
-const VERIFY_CLOCK_DRIFT: Duration = Duration::from_secs(10);
+/// A default maximum clock drift allowed for the untrusted headers.
+pub const DEFAULT_MAX_CLOCK_DRIFT: Duration = Duration::from_secs(10);
+
+/// A default trusting period used by the Celestia nodes.
+pub const DEFAULT_TRUSTING_PERIOD: Duration = Duration::from_secs(336 * 60 * 60);
+
+/// Parameters of the [`ExtendedHeader`] verification.
+///
+/// The default options match the behaviour of [`ExtendedHeader::verify`], which
+/// doesn't limit for how long a trusted header stays trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationOptions {
+    /// The fraction of the trusted validators' voting power that needs to sign
+    /// a non-adjacent untrusted header.
+    pub trust_level: TrustLevelRatio,
+    /// For how long since its creation a header can be used as a trusted one.
+    ///
+    /// `None` means that the trusted header never expires.
+    pub trusting_period: Option<Duration>,
+    /// How far in the future the time of an untrusted header can be.
+    pub max_clock_drift: Duration,
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        VerificationOptions {
+            trust_level: DEFAULT_TRUST_LEVEL,
+            trusting_period: None,
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+        }
+    }
+}
 
 /// Block header together with the relevant Data Availability metadata.
 ///
@@ -193,6 +225,22 @@ impl ExtendedHeader {
     /// fail if the validator set commiting those blocks was changed. If that is the case,
     /// consider verifying the untrusted header with a more recent or even previous header.
     pub fn verify(&self, untrusted: &ExtendedHeader) -> Result<()> {
+        self.verify_with_options(untrusted, &VerificationOptions::default())
+    }
+
+    /// Verify an untrusted header using the given [`VerificationOptions`].
+    ///
+    /// # Errors
+    ///
+    /// If validation fails, this function will return an error with a reason of failure.
+    ///
+    /// If `self` is older than the trusting period, [`VerificationError::TrustedHeaderExpired`]
+    /// is returned and a newer trusted header needs to be obtained to continue verification.
+    pub fn verify_with_options(
+        &self,
+        untrusted: &ExtendedHeader,
+        options: &VerificationOptions,
+    ) -> Result<()> {
         if untrusted.height() <= self.height() {
             bail_verification!(
                 "untrusted header height({}) <= current trusted header({})",
@@ -220,18 +268,42 @@ impl ExtendedHeader {
         #[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
         {
             let now = Time::now();
-            let valid_until = now.checked_add(VERIFY_CLOCK_DRIFT).unwrap();
+
+            if let Some(trusting_period) = options.trusting_period {
+                let expires_at = self
+                    .time()
+                    .checked_add(trusting_period)
+                    .ok_or_else(|| verification_error!("trusting period overflow"))?;
+
+                if !now.before(expires_at) {
+                    return Err(
+                        VerificationError::TrustedHeaderExpired(self.height().value()).into(),
+                    );
+                }
+            }
+
+            let valid_until = now
+                .checked_add(options.max_clock_drift)
+                .ok_or_else(|| verification_error!("max clock drift overflow"))?;
 
             if !untrusted.time().before(valid_until) {
                 bail_verification!(
                     "new untrusted header has a time from the future {} (now: {}, clock_drift: {:?})",
                     untrusted.time(),
                     now,
-                    VERIFY_CLOCK_DRIFT
+                    options.max_clock_drift
                 );
             }
         }
 
+        // Without a clock the expiry can't be checked, refuse instead of trusting the header forever
+        #[cfg(all(target_arch = "wasm32", not(feature = "wasm-bindgen")))]
+        if options.trusting_period.is_some() {
+            bail_verification!(
+                "trusting period can't be checked without the `wasm-bindgen` feature"
+            );
+        }
+
         // Optimization: If we are verifying an adjacent header we can avoid
         // `verify_commit_light_trusting` because we can just check the hash
         // of next validators and last header.
@@ -258,7 +330,7 @@ impl ExtendedHeader {
         self.validator_set.verify_commit_light_trusting(
             self.chain_id(),
             &untrusted.commit,
-            DEFAULT_TRUST_LEVEL,
+            options.trust_level.clone(),
         )?;
 
         Ok(())
@@ -294,6 +366,17 @@ impl ExtendedHeader {
     /// assert!(genesis_header.verify_range(&next_headers).is_ok());
     /// ```
     pub fn verify_range(&self, untrusted: &[ExtendedHeader]) -> Result<()> {
+        self.verify_range_with_options(untrusted, &VerificationOptions::default())
+    }
+
+    /// Verify a chain of adjacent untrusted headers using the given [`VerificationOptions`].
+    ///
+    /// See [`ExtendedHeader::verify_range`] for details.
+    pub fn verify_range_with_options(
+        &self,
+        untrusted: &[ExtendedHeader],
+        options: &VerificationOptions,
+    ) -> Result<()> {
         let mut trusted = self;
 
         for (i, untrusted) in untrusted.iter().enumerate() {
@@ -308,7 +391,7 @@ impl ExtendedHeader {
                 );
             }
 
-            trusted.verify(untrusted)?;
+            trusted.verify_with_options(untrusted, options)?;
             trusted = untrusted;
         }
 
@@ -351,6 +434,18 @@ impl ExtendedHeader {
     /// genesis_header.verify_adjacent_range(&next_headers).unwrap();
     /// ```
     pub fn verify_adjacent_range(&self, untrusted: &[ExtendedHeader]) -> Result<()> {
+        self.verify_adjacent_range_with_options(untrusted, &VerificationOptions::default())
+    }
+
+    /// Verify a chain of adjacent untrusted headers using the given [`VerificationOptions`]
+    /// and make sure they are adjacent to `self`.
+    ///
+    /// See [`ExtendedHeader::verify_adjacent_range`] for details.
+    pub fn verify_adjacent_range_with_options(
+        &self,
+        untrusted: &[ExtendedHeader],
+        options: &VerificationOptions,
+    ) -> Result<()> {
         if untrusted.is_empty() {
             return Ok(());
         }
@@ -364,7 +459,7 @@ impl ExtendedHeader {
             );
         }
 
-        self.verify_range(untrusted)
+        self.verify_range_with_options(untrusted, options)
     }
 }
 
@@ -581,6 +676,67 @@ mod tests {
         eh_block_1.verify(&eh_block_27).unwrap_err();
     }
 
+    #[test]
+    fn verify_with_custom_clock_drift() {
+        let eh_block_1 = sample_eh_chain_1_block_1();
+        let mut eh_block_27 = sample_eh_chain_1_block_27();
+
+        eh_block_27.header.time = Time::now().checked_add(Duration::from_secs(60)).unwrap();
+        let options = VerificationOptions {
+            max_clock_drift: Duration::from_secs(120),
+            ..Default::default()
+        };
+
+        eh_block_1
+            .verify_with_options(&eh_block_27, &options)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_expired_trusted_header() {
+        let eh_block_1 = sample_eh_chain_1_block_1();
+        let eh_block_27 = sample_eh_chain_1_block_27();
+
+        let options = VerificationOptions {
+            trusting_period: Some(DEFAULT_TRUSTING_PERIOD),
+            ..Default::default()
+        };
+
+        let err = eh_block_1
+            .verify_with_options(&eh_block_27, &options)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Verification(VerificationError::TrustedHeaderExpired(1))
+        ));
+
+        let options = VerificationOptions {
+            trusting_period: Some(Duration::from_secs(100 * 365 * 24 * 60 * 60)),
+            ..Default::default()
+        };
+        eh_block_1
+            .verify_with_options(&eh_block_27, &options)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_with_unreachable_trust_level() {
+        let eh_block_1 = sample_eh_chain_1_block_1();
+        let eh_block_27 = sample_eh_chain_1_block_27();
+
+        let options = VerificationOptions {
+            trust_level: TrustLevelRatio::new(1, 1),
+            ..Default::default()
+        };
+        let err = eh_block_1
+            .verify_with_options(&eh_block_27, &options)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Verification(VerificationError::NotEnoughVotingPower(..))
+        ));
+    }
+
     #[test]
     fn verify_range() {
         let eh_chain = sample_eh_chain_3_block_1_to_256();
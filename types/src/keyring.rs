@@ -0,0 +1,353 @@
+//! Management of the secp256k1 keys used by the accounts in Celestia network.
+//!
+//! Keys are derived from the [BIP39] mnemonic phrases using the [BIP32] derivation
+//! along the Cosmos HD path, so the same mnemonic yields the same addresses as
+//! in `celestia-appd` or `cel-key`.
+//!
+//! [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+//! [BIP32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bip32::{DerivationPath, XPrv};
+use bip39::{Language, Mnemonic};
+use celestia_tendermint::account::Id;
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+
+use crate::state::{AccAddress, ValAddress};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file_keyring;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::file_keyring::FileKeyring;
+
+/// The HD path of the first key of the first account in Cosmos SDK chains.
+pub const COSMOS_HD_PATH: &str = "m/44'/118'/0'/0/0";
+
+/// Alias for a `Result` with the error type [`KeyringError`].
+pub type Result<T, E = KeyringError> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when interacting with the [`Keyring`].
+#[derive(Debug, thiserror::Error)]
+pub enum KeyringError {
+    /// Invalid mnemonic phrase.
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(#[from] bip39::Error),
+
+    /// Invalid derivation path or the derivation failed.
+    #[error("Key derivation failed: {0}")]
+    Derivation(#[from] bip32::Error),
+
+    /// Bytes are not a valid secp256k1 private key.
+    #[error("Invalid secp256k1 private key")]
+    InvalidKey,
+
+    /// Name of the key contains unsupported characters.
+    #[error("Invalid key name: '{0}'")]
+    InvalidKeyName(String),
+
+    /// Key with the given name already exists in the keyring.
+    #[error("Key '{0}' already exists in keyring")]
+    KeyExists(String),
+
+    /// Key with the given name not found in the keyring.
+    #[error("Key '{0}' not found in keyring")]
+    KeyNotFound(String),
+
+    /// Stored key couldn't be decrypted, either due to a wrong passphrase or corrupted data.
+    #[error("Failed to decrypt key '{0}', wrong passphrase or corrupted data")]
+    Decryption(String),
+
+    /// Stored key is in an unknown format.
+    #[error("Stored key '{0}' in unsupported format")]
+    UnsupportedFormat(String),
+
+    /// An error propagated from the IO operation.
+    #[error("Received IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A secp256k1 private key of an account.
+#[derive(Clone)]
+pub struct Key {
+    signing_key: SigningKey,
+}
+
+impl Key {
+    /// Generate a new random 24 words mnemonic phrase in English.
+    pub fn generate_mnemonic() -> String {
+        Mnemonic::generate_in_with(&mut OsRng, Language::English, 24)
+            .expect("24 is a valid word count")
+            .to_string()
+    }
+
+    /// Derive a key from the mnemonic phrase along the [`COSMOS_HD_PATH`].
+    ///
+    /// `passphrase` is the optional BIP39 passphrase, use an empty string if not set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::keyring::Key;
+    ///
+    /// let mnemonic = Key::generate_mnemonic();
+    /// let key = Key::from_mnemonic(&mnemonic, "").unwrap();
+    ///
+    /// println!("Account address: {}", key.acc_address());
+    /// ```
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Result<Self> {
+        Key::from_mnemonic_with_path(mnemonic, passphrase, COSMOS_HD_PATH)
+    }
+
+    /// Derive a key from the mnemonic phrase along the given HD path.
+    pub fn from_mnemonic_with_path(mnemonic: &str, passphrase: &str, path: &str) -> Result<Self> {
+        let mnemonic = Mnemonic::parse_in_normalized(Language::English, mnemonic)?;
+        let path: DerivationPath = path.parse()?;
+        let seed = mnemonic.to_seed(passphrase);
+        let xprv = XPrv::derive_from_path(seed, &path)?;
+
+        Ok(Key {
+            signing_key: xprv.private_key().clone(),
+        })
+    }
+
+    /// Create a key from the raw 32 bytes of the secp256k1 scalar.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 32 {
+            return Err(KeyringError::InvalidKey);
+        }
+
+        let signing_key = SigningKey::from_slice(bytes).map_err(|_| KeyringError::InvalidKey)?;
+        Ok(Key { signing_key })
+    }
+
+    /// Get the raw 32 bytes of the secp256k1 scalar.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.signing_key.to_bytes().into()
+    }
+
+    /// Get the signing key.
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+
+    /// Get the public key.
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    /// Get the address of the account owning the key.
+    pub fn acc_address(&self) -> AccAddress {
+        AccAddress::new(self.id())
+    }
+
+    /// Get the address of the validator operated by the key.
+    pub fn val_address(&self) -> ValAddress {
+        ValAddress::new(self.id())
+    }
+
+    /// Sign the bytes.
+    ///
+    /// The message is hashed with sha256 and the signature is normalized to
+    /// the lower S form, as required by the Cosmos SDK.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.signing_key.sign(msg)
+    }
+
+    fn id(&self) -> Id {
+        Id::from(*self.verifying_key())
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("address", &self.acc_address())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An interface for the storage of the named [`Key`]s.
+pub trait Keyring {
+    /// Insert the key under the given name.
+    ///
+    /// Fails with [`KeyringError::KeyExists`] if the name is already taken.
+    fn add(&mut self, name: &str, key: &Key) -> Result<()>;
+
+    /// Get the key with the given name.
+    fn get(&self, name: &str) -> Result<Key>;
+
+    /// Remove the key with the given name.
+    fn delete(&mut self, name: &str) -> Result<()>;
+
+    /// List the names of all the keys, in alphabetical order.
+    fn list(&self) -> Result<Vec<String>>;
+}
+
+/// A non-persistent keyring, keeping the keys in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryKeyring {
+    keys: BTreeMap<String, Key>,
+}
+
+impl InMemoryKeyring {
+    /// Create an empty keyring.
+    pub fn new() -> Self {
+        InMemoryKeyring::default()
+    }
+}
+
+impl Keyring for InMemoryKeyring {
+    fn add(&mut self, name: &str, key: &Key) -> Result<()> {
+        validate_key_name(name)?;
+
+        if self.keys.contains_key(name) {
+            return Err(KeyringError::KeyExists(name.to_owned()));
+        }
+
+        self.keys.insert(name.to_owned(), key.clone());
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Key> {
+        self.keys
+            .get(name)
+            .cloned()
+            .ok_or_else(|| KeyringError::KeyNotFound(name.to_owned()))
+    }
+
+    fn delete(&mut self, name: &str) -> Result<()> {
+        self.keys
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| KeyringError::KeyNotFound(name.to_owned()))
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        Ok(self.keys.keys().cloned().collect())
+    }
+}
+
+/// Allow only the names that are safe to use as the file names.
+fn validate_key_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(KeyringError::InvalidKeyName(name.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AddressTrait;
+    use bech32::FromBase32;
+    use k256::ecdsa::signature::Verifier;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn derive_cosmos_address() {
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+
+        // address derived by the cosmos sdk from the same mnemonic
+        let (_, data, _) = bech32::decode("cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4").unwrap();
+        let expected = Vec::<u8>::from_base32(&data).unwrap();
+
+        assert_eq!(key.acc_address().as_bytes(), expected);
+        assert_eq!(key.val_address().as_bytes(), expected);
+        assert!(key.acc_address().to_string().starts_with("celestia1"));
+        assert!(key
+            .val_address()
+            .to_string()
+            .starts_with("celestiavaloper1"));
+    }
+
+    #[test]
+    fn derive_with_path_and_passphrase() {
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+        let same = Key::from_mnemonic_with_path(MNEMONIC, "", COSMOS_HD_PATH).unwrap();
+        let other_index = Key::from_mnemonic_with_path(MNEMONIC, "", "m/44'/118'/0'/0/1").unwrap();
+        let with_passphrase = Key::from_mnemonic(MNEMONIC, "secret").unwrap();
+
+        assert_eq!(key.to_bytes(), same.to_bytes());
+        assert_ne!(key.to_bytes(), other_index.to_bytes());
+        assert_ne!(key.to_bytes(), with_passphrase.to_bytes());
+    }
+
+    #[test]
+    fn invalid_mnemonic() {
+        Key::from_mnemonic("abandon abandon", "").unwrap_err();
+        Key::from_mnemonic_with_path(MNEMONIC, "", "m/44'/invalid").unwrap_err();
+    }
+
+    #[test]
+    fn generated_mnemonic_is_valid() {
+        let mnemonic = Key::generate_mnemonic();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+        Key::from_mnemonic(&mnemonic, "").unwrap();
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+        let decoded = Key::from_bytes(&key.to_bytes()).unwrap();
+
+        assert_eq!(key.acc_address(), decoded.acc_address());
+
+        Key::from_bytes(&[0; 32]).unwrap_err();
+        Key::from_bytes(&[1; 31]).unwrap_err();
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+        let signature = key.sign(b"message");
+
+        key.verifying_key().verify(b"message", &signature).unwrap();
+        key.verifying_key()
+            .verify(b"other", &signature)
+            .unwrap_err();
+        assert!(signature.normalize_s().is_none());
+    }
+
+    #[test]
+    fn in_memory_keyring() {
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+        let mut keyring = InMemoryKeyring::new();
+
+        keyring.add("b", &key).unwrap();
+        keyring.add("a", &key).unwrap();
+        assert!(matches!(
+            keyring.add("a", &key).unwrap_err(),
+            KeyringError::KeyExists(_)
+        ));
+        assert!(matches!(
+            keyring.add("../a", &key).unwrap_err(),
+            KeyringError::InvalidKeyName(_)
+        ));
+
+        assert_eq!(keyring.list().unwrap(), vec!["a", "b"]);
+        assert_eq!(keyring.get("a").unwrap().acc_address(), key.acc_address());
+
+        keyring.delete("a").unwrap();
+        assert!(matches!(
+            keyring.get("a").unwrap_err(),
+            KeyringError::KeyNotFound(_)
+        ));
+        assert_eq!(keyring.list().unwrap(), vec!["b"]);
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+use scrypt::Params;
+
+use crate::keyring::{validate_key_name, Key, Keyring, KeyringError, Result};
+
+/// Version of the format of the key files.
+const FORMAT_VERSION: u8 = 1;
+/// Extension of the key files.
+const KEY_FILE_EXTENSION: &str = "key";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Length of the header: version, scrypt's log n, salt and nonce.
+const HEADER_LEN: usize = 2 + SALT_LEN + NONCE_LEN;
+/// Scrypt cost, as recommended for the interactive logins.
+#[cfg(not(test))]
+const SCRYPT_LOG_N: u8 = 15;
+#[cfg(test)]
+const SCRYPT_LOG_N: u8 = 4;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// A persistent keyring, keeping every key encrypted in a separate file.
+///
+/// Keys are encrypted with ChaCha20-Poly1305, using a key derived from the
+/// passphrase with scrypt. The name of the key is authenticated together
+/// with its content, so renamed files are rejected.
+///
+/// # Example
+///
+/// ```no_run
+/// use celestia_types::keyring::{FileKeyring, Key, Keyring};
+///
+/// let mut keyring = FileKeyring::new("/tmp/keyring", "passphrase").unwrap();
+///
+/// let mnemonic = Key::generate_mnemonic();
+/// keyring.add("validator", &Key::from_mnemonic(&mnemonic, "").unwrap()).unwrap();
+///
+/// let key = keyring.get("validator").unwrap();
+/// println!("{}", key.acc_address());
+/// ```
+pub struct FileKeyring {
+    dir: PathBuf,
+    passphrase: String,
+}
+
+impl fmt::Debug for FileKeyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileKeyring")
+            .field("dir", &self.dir)
+            .field("passphrase", &"<redacted>")
+            .finish()
+    }
+}
+
+impl FileKeyring {
+    /// Open the keyring in the given directory, creating it if needed.
+    ///
+    /// All the keys in the keyring are encrypted with the same `passphrase`.
+    pub fn new(dir: impl AsRef<Path>, passphrase: impl Into<String>) -> Result<Self> {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+
+        Ok(FileKeyring {
+            dir,
+            passphrase: passphrase.into(),
+        })
+    }
+
+    /// Get the directory of the keyring.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn key_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{KEY_FILE_EXTENSION}"))
+    }
+
+    fn cipher(&self, log_n: u8, salt: &[u8]) -> Result<ChaCha20Poly1305, ()> {
+        let params = Params::new(log_n, SCRYPT_R, SCRYPT_P, 32).map_err(|_| ())?;
+        let mut key = [0; 32];
+        scrypt::scrypt(self.passphrase.as_bytes(), salt, &params, &mut key).map_err(|_| ())?;
+
+        ChaCha20Poly1305::new_from_slice(&key).map_err(|_| ())
+    }
+
+    fn encrypt(&self, name: &str, key: &Key) -> Vec<u8> {
+        let mut header = [0; HEADER_LEN];
+        header[0] = FORMAT_VERSION;
+        header[1] = SCRYPT_LOG_N;
+        rand::thread_rng().fill_bytes(&mut header[2..]);
+
+        let (salt, nonce) = header[2..].split_at(SALT_LEN);
+        let cipher = self
+            .cipher(SCRYPT_LOG_N, salt)
+            .expect("constant scrypt params are valid");
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: &key.to_bytes(),
+                    aad: &associated_data(&header, name),
+                },
+            )
+            .expect("encrypting in memory buffer can't fail");
+
+        let mut data = header.to_vec();
+        data.extend_from_slice(&ciphertext);
+        data
+    }
+
+    fn decrypt(&self, name: &str, data: &[u8]) -> Result<Key> {
+        // Bound the scrypt cost taken from the file, a tampered one could exhaust the resources
+        if data.len() < HEADER_LEN || data[0] != FORMAT_VERSION || data[1] > SCRYPT_LOG_N {
+            return Err(KeyringError::UnsupportedFormat(name.to_owned()));
+        }
+
+        let (header, ciphertext) = data.split_at(HEADER_LEN);
+        let (salt, nonce) = header[2..].split_at(SALT_LEN);
+
+        let bytes = self
+            .cipher(header[1], salt)
+            .and_then(|cipher| {
+                cipher
+                    .decrypt(
+                        Nonce::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: &associated_data(header, name),
+                        },
+                    )
+                    .map_err(|_| ())
+            })
+            .map_err(|_| KeyringError::Decryption(name.to_owned()))?;
+
+        Key::from_bytes(&bytes)
+    }
+}
+
+impl Keyring for FileKeyring {
+    fn add(&mut self, name: &str, key: &Key) -> Result<()> {
+        validate_key_name(name)?;
+
+        let data = self.encrypt(name, key);
+
+        // `create_new` makes sure existing keys are never overwritten
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.key_path(name));
+
+        let mut file = match file {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                return Err(KeyringError::KeyExists(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        restrict_permissions(&file)?;
+        std::io::Write::write_all(&mut file, &data)?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Key> {
+        validate_key_name(name).map_err(|_| KeyringError::KeyNotFound(name.to_owned()))?;
+
+        match fs::read(self.key_path(name)) {
+            Ok(data) => self.decrypt(name, &data),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(KeyringError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&mut self, name: &str) -> Result<()> {
+        validate_key_name(name).map_err(|_| KeyringError::KeyNotFound(name.to_owned()))?;
+
+        match fs::remove_file(self.key_path(name)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(KeyringError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some(KEY_FILE_EXTENSION) {
+                continue;
+            }
+
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                if validate_key_name(name).is_ok() {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+}
+
+fn associated_data(header: &[u8], name: &str) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(name.as_bytes());
+    aad
+}
+
+#[cfg(unix)]
+fn restrict_permissions(file: &fs::File) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_file: &fs::File) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "celestia-keyring-test-{}",
+            rand::thread_rng().next_u64()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn persists_keys() {
+        let dir = temp_dir();
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+
+        let mut keyring = FileKeyring::new(&dir, "passphrase").unwrap();
+        keyring.add("alice", &key).unwrap();
+        keyring.add("bob", &key).unwrap();
+        drop(keyring);
+
+        let mut keyring = FileKeyring::new(&dir, "passphrase").unwrap();
+        assert_eq!(keyring.list().unwrap(), vec!["alice", "bob"]);
+        assert_eq!(keyring.get("alice").unwrap().to_bytes(), key.to_bytes());
+
+        assert!(matches!(
+            keyring.add("alice", &key).unwrap_err(),
+            KeyringError::KeyExists(_)
+        ));
+
+        keyring.delete("alice").unwrap();
+        assert!(matches!(
+            keyring.get("alice").unwrap_err(),
+            KeyringError::KeyNotFound(_)
+        ));
+        assert!(matches!(
+            keyring.delete("alice").unwrap_err(),
+            KeyringError::KeyNotFound(_)
+        ));
+        assert_eq!(keyring.list().unwrap(), vec!["bob"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keys_are_encrypted() {
+        let dir = temp_dir();
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+
+        let mut keyring = FileKeyring::new(&dir, "passphrase").unwrap();
+        keyring.add("alice", &key).unwrap();
+
+        let data = fs::read(keyring.key_path("alice")).unwrap();
+        assert!(!data
+            .windows(32)
+            .any(|window| window == key.to_bytes().as_slice()));
+
+        let wrong = FileKeyring::new(&dir, "wrong").unwrap();
+        assert!(matches!(
+            wrong.get("alice").unwrap_err(),
+            KeyringError::Decryption(_)
+        ));
+
+        // key content is bound to its name
+        fs::copy(keyring.key_path("alice"), keyring.key_path("mallory")).unwrap();
+        assert!(matches!(
+            keyring.get("mallory").unwrap_err(),
+            KeyringError::Decryption(_)
+        ));
+
+        fs::write(keyring.key_path("corrupted"), [2, 4]).unwrap();
+        assert!(matches!(
+            keyring.get("corrupted").unwrap_err(),
+            KeyringError::UnsupportedFormat(_)
+        ));
+
+        // scrypt cost above the one we use is rejected before deriving anything
+        let mut data = fs::read(keyring.key_path("alice")).unwrap();
+        data[1] = SCRYPT_LOG_N + 1;
+        fs::write(keyring.key_path("expensive"), data).unwrap();
+        assert!(matches!(
+            keyring.get("expensive").unwrap_err(),
+            KeyringError::UnsupportedFormat(_)
+        ));
+
+        assert!(!format!("{wrong:?}").contains("wrong"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_path_traversal() {
+        let dir = temp_dir();
+        let key = Key::from_mnemonic(MNEMONIC, "").unwrap();
+        let mut keyring = FileKeyring::new(dir.join("keys"), "passphrase").unwrap();
+
+        for name in ["../alice", "a/b", "", ".hidden"] {
+            assert!(matches!(
+                keyring.add(name, &key).unwrap_err(),
+                KeyringError::InvalidKeyName(_)
+            ));
+        }
+        assert!(!dir.join("alice.key").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
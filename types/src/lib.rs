@@ -1,15 +1,20 @@
 #![cfg_attr(docs_rs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+mod axis;
 pub mod blob;
 mod block;
 mod byzantine;
+pub mod column;
 pub mod consts;
 mod data_availability_header;
 mod error;
 mod extended_header;
 pub mod fraud_proof;
 pub mod hash;
+#[cfg(feature = "keyring")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "keyring")))]
+pub mod keyring;
 pub mod namespaced_data;
 pub mod nmt;
 #[cfg(feature = "p2p")]
@@ -2,6 +2,7 @@
 //!
 //! Namespaced data in Celestia is understood as all the [`Share`]s within
 //! the same [`Namespace`] in a single row of the [`ExtendedDataSquare`].
+//! [`NamespacedRows`] combine it for all the rows of the block that can contain the namespace.
 //!
 //! [`Share`]: crate::Share
 //! [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
@@ -54,6 +55,8 @@ pub struct NamespacedData {
 impl NamespacedData {
     /// Verifies proof inside `NamespacedData` using a row root from [`DataAvailabilityHeader`]
     ///
+    /// `NamespacedData` without any shares must carry a proof of the namespace absence in the row.
+    ///
     /// #Example
     /// ```no_run
     /// use celestia_types::nmt::Namespace;
@@ -79,7 +82,7 @@ impl NamespacedData {
     ///
     /// [`DataAvailabilityHeader`]: crate::DataAvailabilityHeader
     pub fn verify(&self, dah: &DataAvailabilityHeader) -> Result<()> {
-        if self.shares.is_empty() {
+        if self.shares.is_empty() != self.proof.is_of_absence() {
             return Err(Error::WrongProofType);
         }
 
@@ -95,6 +98,95 @@ impl NamespacedData {
 
 impl Protobuf<RawNamespacedData> for NamespacedData {}
 
+/// All the [`NamespacedData`] of a [`Namespace`] in a block.
+///
+/// It holds the [`NamespacedData`] for each row which root's namespace range contains the
+/// namespace, including the rows proving its absence. The namespace is absent from all the
+/// other rows, which is proven by their roots in the [`DataAvailabilityHeader`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NamespacedRows {
+    /// A namespace of the shares.
+    pub namespace: Namespace,
+    /// A height of the block which contains the shares.
+    pub block_height: u64,
+    /// Namespaced data of the rows, ordered by the row index.
+    pub rows: Vec<NamespacedData>,
+}
+
+impl NamespacedRows {
+    /// Verifies that the rows cover exactly the ones which may contain the namespace, and
+    /// each of them against its root from [`DataAvailabilityHeader`].
+    pub fn verify(&self, dah: &DataAvailabilityHeader) -> Result<()> {
+        let expected_rows = dah.row_indexes_for_namespace(self.namespace);
+
+        if self.rows.len() != expected_rows.len() {
+            return Err(Error::NamespacedDataMismatch);
+        }
+
+        for (row, expected_row) in self.rows.iter().zip(expected_rows) {
+            if row.id.namespace() != self.namespace
+                || row.id.block_height() != self.block_height
+                || row.id.row_index() != expected_row
+            {
+                return Err(Error::NamespacedDataMismatch);
+            }
+
+            row.verify(dah)?;
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the namespace has no shares in the block.
+    ///
+    /// The namespace is absent from the rows which roots' namespace ranges don't contain it,
+    /// and each of the remaining rows must carry a proof of its absence.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::nmt::Namespace;
+    /// # use celestia_types::namespaced_data::NamespacedRows;
+    /// # use celestia_types::ExtendedHeader;
+    /// # fn get_namespaced_rows(namespace: Namespace, height: u64) -> NamespacedRows {
+    /// #    unimplemented!()
+    /// # }
+    /// # fn get_extended_header(height: u64) -> ExtendedHeader {
+    /// #    unimplemented!()
+    /// # }
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let header = get_extended_header(100);
+    /// let rows = get_namespaced_rows(namespace, 100);
+    ///
+    /// if rows.is_absent() {
+    ///     // The block is empty for the rollup
+    ///     rows.verify_absence(&header.dah).unwrap();
+    /// }
+    /// ```
+    pub fn verify_absence(&self, dah: &DataAvailabilityHeader) -> Result<()> {
+        self.verify(dah)?;
+
+        match self.rows.iter().find(|row| !row.shares.is_empty()) {
+            Some(row) => Err(Error::NamespacePresent(row.id.row_index())),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if none of the rows has shares of the namespace.
+    ///
+    /// This doesn't verify the rows, see [`NamespacedRows::verify_absence`].
+    pub fn is_absent(&self) -> bool {
+        self.rows.iter().all(|row| row.shares.is_empty())
+    }
+
+    /// Returns all the shares of the namespace in the block, in order.
+    pub fn shares(&self) -> impl Iterator<Item = &[u8]> {
+        self.rows
+            .iter()
+            .flat_map(|row| row.shares.iter().map(|share| &share[..]))
+    }
+}
+
 impl TryFrom<RawNamespacedData> for NamespacedData {
     type Error = Error;
 
@@ -225,7 +317,7 @@ impl From<NamespacedDataId> for CidGeneric<NAMESPACED_DATA_ID_SIZE> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Share;
+    use crate::{ExtendedDataSquare, Share};
 
     #[test]
     fn round_trip() {
@@ -301,6 +393,139 @@ mod tests {
         assert_eq!(axis_err, CidError::InvalidCidCodec(4321));
     }
 
+    fn shwap_samples() -> (ExtendedDataSquare, DataAvailabilityHeader) {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds = serde_json::from_str(eds_json).unwrap();
+
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah = serde_json::from_str(dah_json).unwrap();
+
+        (eds, dah)
+    }
+
+    fn namespaced_rows(
+        eds: &ExtendedDataSquare,
+        dah: &DataAvailabilityHeader,
+        namespace: Namespace,
+    ) -> NamespacedRows {
+        NamespacedRows {
+            namespace,
+            block_height: 45577,
+            rows: eds.get_namespaced_data(namespace, dah, 45577).unwrap(),
+        }
+    }
+
+    #[test]
+    fn verify_namespaced_rows() {
+        let (eds, dah) = shwap_samples();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        rows.verify(&dah).unwrap();
+        assert_eq!(rows.rows.len(), 2);
+        assert_eq!(rows.shares().count(), 5);
+    }
+
+    #[test]
+    fn verify_namespaced_rows_with_absence() {
+        let (eds, dah) = shwap_samples();
+
+        // Namespace is in the range of the first row, but it has no shares in it
+        let namespace = Namespace::new_v0(&[1, 171]).unwrap();
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        rows.verify(&dah).unwrap();
+        assert_eq!(rows.rows.len(), 1);
+        assert!(rows.rows[0].proof.is_of_absence());
+        assert_eq!(rows.shares().count(), 0);
+
+        // Namespace isn't in the range of any row
+        let namespace = Namespace::new_v0(&[1, 200]).unwrap();
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        rows.verify(&dah).unwrap();
+        assert!(rows.rows.is_empty());
+    }
+
+    #[test]
+    fn verify_namespace_absence() {
+        let (eds, dah) = shwap_samples();
+
+        // Absence proven by the row proof
+        let namespace = Namespace::new_v0(&[1, 171]).unwrap();
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        assert!(rows.is_absent());
+        rows.verify_absence(&dah).unwrap();
+
+        // Absence proven by the row roots alone
+        let namespace = Namespace::new_v0(&[1, 200]).unwrap();
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        assert!(rows.is_absent());
+        rows.verify_absence(&dah).unwrap();
+    }
+
+    #[test]
+    fn verify_absence_of_present_namespace() {
+        let (eds, dah) = shwap_samples();
+        let namespace = Namespace::new_v0(&[1, 170]).unwrap();
+
+        let rows = namespaced_rows(&eds, &dah, namespace);
+        assert!(!rows.is_absent());
+        assert!(matches!(
+            rows.verify_absence(&dah),
+            Err(Error::NamespacePresent(_))
+        ));
+
+        // Hiding the row that contains the namespace doesn't prove its absence
+        let mut rows = rows;
+        rows.rows.clear();
+        assert!(rows.is_absent());
+        assert!(matches!(
+            rows.verify_absence(&dah),
+            Err(Error::NamespacedDataMismatch)
+        ));
+    }
+
+    #[test]
+    fn verify_namespaced_rows_missing_row() {
+        let (eds, dah) = shwap_samples();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let mut rows = namespaced_rows(&eds, &dah, namespace);
+        rows.rows.pop();
+
+        assert!(matches!(
+            rows.verify(&dah),
+            Err(Error::NamespacedDataMismatch)
+        ));
+    }
+
+    #[test]
+    fn verify_namespaced_rows_from_other_block() {
+        let (eds, dah) = shwap_samples();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let mut rows = namespaced_rows(&eds, &dah, namespace);
+        rows.block_height += 1;
+
+        assert!(matches!(
+            rows.verify(&dah),
+            Err(Error::NamespacedDataMismatch)
+        ));
+    }
+
+    #[test]
+    fn verify_absence_with_shares() {
+        let (eds, dah) = shwap_samples();
+        let namespace = Namespace::new_v0(&[1, 171]).unwrap();
+
+        let mut rows = namespaced_rows(&eds, &dah, namespace);
+        rows.rows[0].shares.push(vec![0; 512]);
+
+        assert!(matches!(
+            rows.rows[0].verify(&dah),
+            Err(Error::WrongProofType)
+        ));
+    }
+
     #[test]
     fn decode_data_bytes() {
         let bytes = include_bytes!("../test_data/shwap_samples/namespaced_data.data");
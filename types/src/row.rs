@@ -7,22 +7,18 @@
 //! [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
 
 use blockstore::block::CidError;
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::BytesMut;
 use celestia_proto::share::p2p::shwap::Row as RawRow;
 use celestia_tendermint_proto::Protobuf;
 use cid::CidGeneric;
-use multihash::Multihash;
-use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Serialize};
 
-use crate::consts::appconsts::SHARE_SIZE;
-use crate::nmt::NS_SIZE;
-use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt};
-use crate::rsmt2d::{is_ods_square, ExtendedDataSquare};
+use crate::axis::{self, AXIS_ID_SIZE};
+use crate::rsmt2d::{AxisType, ExtendedDataSquare};
 use crate::{DataAvailabilityHeader, Error, Result};
 
 /// Number of bytes needed to represent [`RowId`] in `multihash`.
-pub(crate) const ROW_ID_SIZE: usize = 10;
+pub(crate) const ROW_ID_SIZE: usize = AXIS_ID_SIZE;
 /// The code of the [`RowId`] hashing algorithm in `multihash`.
 pub const ROW_ID_MULTIHASH_CODE: u64 = 0x7811;
 /// The id of codec used for the [`RowId`] in `Cid`s.
@@ -56,33 +52,7 @@ impl Row {
 
     /// verify the row against roots from DAH
     pub fn verify(&self, dah: &DataAvailabilityHeader) -> Result<()> {
-        let square_width =
-            u16::try_from(self.shares.len()).map_err(|_| Error::EdsInvalidDimentions)?;
-        let row = self.id.index;
-
-        let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
-
-        for col in 0..square_width {
-            let share = &self.shares[usize::from(col)];
-
-            let ns = if is_ods_square(row, col, square_width) {
-                Namespace::from_raw(&share[..NS_SIZE])?
-            } else {
-                Namespace::PARITY_SHARE
-            };
-
-            tree.push_leaf(share, *ns).map_err(Error::Nmt)?;
-        }
-
-        let Some(root) = dah.row_root(row) else {
-            return Err(Error::EdsIndexOutOfRange(row, 0));
-        };
-
-        if tree.root().hash() != root.hash() {
-            return Err(Error::RootMismatch);
-        }
-
-        Ok(())
+        axis::verify(AxisType::Row, self.id.index, &self.shares, dah)
     }
 }
 
@@ -93,12 +63,7 @@ impl TryFrom<RawRow> for Row {
 
     fn try_from(row: RawRow) -> Result<Row, Self::Error> {
         let id = RowId::decode(&row.row_id)?;
-        let mut shares = row.row_half;
-        let data_shares = shares.len();
-
-        shares.resize(shares.len() * 2, vec![0; SHARE_SIZE]);
-
-        leopard_codec::encode(&mut shares, data_shares)?;
+        let shares = axis::extend_half(row.row_half)?;
 
         Ok(Row { id, shares })
     }
@@ -109,14 +74,9 @@ impl From<Row> for RawRow {
         let mut row_id_bytes = BytesMut::new();
         row.id.encode(&mut row_id_bytes);
 
-        // parity shares aren't transmitted over shwap, just data shares
-        let square_width = row.shares.len();
-        let mut row_half = row.shares;
-        row_half.truncate(square_width / 2);
-
         RawRow {
             row_id: row_id_bytes.to_vec(),
-            row_half,
+            row_half: axis::data_half(row.shares),
         }
     }
 }
@@ -151,22 +111,11 @@ impl RowId {
     }
 
     pub(crate) fn encode(&self, bytes: &mut BytesMut) {
-        bytes.reserve(ROW_ID_SIZE);
-        bytes.put_u64(self.block_height);
-        bytes.put_u16(self.index);
+        axis::encode_id(self.block_height, self.index, bytes);
     }
 
-    pub(crate) fn decode(mut buffer: &[u8]) -> Result<Self, CidError> {
-        if buffer.len() != ROW_ID_SIZE {
-            return Err(CidError::InvalidMultihashLength(buffer.len()));
-        }
-
-        let block_height = buffer.get_u64();
-        let index = buffer.get_u16();
-
-        if block_height == 0 {
-            return Err(CidError::InvalidCid("Zero block height".to_string()));
-        }
+    pub(crate) fn decode(buffer: &[u8]) -> Result<Self, CidError> {
+        let (block_height, index) = axis::decode_id(buffer)?;
 
         Ok(Self {
             block_height,
@@ -179,35 +128,23 @@ impl<const S: usize> TryFrom<CidGeneric<S>> for RowId {
     type Error = CidError;
 
     fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
-        let codec = cid.codec();
-        if codec != ROW_ID_CODEC {
-            return Err(CidError::InvalidCidCodec(codec));
-        }
-
-        let hash = cid.hash();
-
-        let size = hash.size() as usize;
-        if size != ROW_ID_SIZE {
-            return Err(CidError::InvalidMultihashLength(size));
-        }
+        let (block_height, index) = axis::id_from_cid(&cid, ROW_ID_CODEC, ROW_ID_MULTIHASH_CODE)?;
 
-        let code = hash.code();
-        if code != ROW_ID_MULTIHASH_CODE {
-            return Err(CidError::InvalidMultihashCode(code, ROW_ID_MULTIHASH_CODE));
-        }
-
-        RowId::decode(hash.digest())
+        Ok(Self {
+            block_height,
+            index,
+        })
     }
 }
 
 impl From<RowId> for CidGeneric<ROW_ID_SIZE> {
     fn from(row: RowId) -> Self {
-        let mut bytes = BytesMut::with_capacity(ROW_ID_SIZE);
-        row.encode(&mut bytes);
-        // length is correct, so unwrap is safe
-        let mh = Multihash::wrap(ROW_ID_MULTIHASH_CODE, &bytes[..]).unwrap();
-
-        CidGeneric::new_v1(ROW_ID_CODEC, mh)
+        axis::id_to_cid(
+            row.block_height,
+            row.index,
+            ROW_ID_CODEC,
+            ROW_ID_MULTIHASH_CODE,
+        )
     }
 }
 
@@ -216,6 +153,7 @@ mod tests {
     use super::*;
     use crate::consts::appconsts::SHARE_SIZE;
     use crate::test_utils::generate_eds;
+    use multihash::Multihash;
 
     #[test]
     fn round_trip_test() {
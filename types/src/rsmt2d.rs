@@ -8,13 +8,13 @@ use crate::consts::data_availability_header::{
     MAX_EXTENDED_SQUARE_WIDTH, MIN_EXTENDED_SQUARE_WIDTH,
 };
 use crate::namespaced_data::{NamespacedData, NamespacedDataId};
-use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt, NmtExt, NS_SIZE};
+use crate::nmt::{Namespace, Nmt, NmtExt, NS_SIZE};
 use crate::{bail_validation, DataAvailabilityHeader, Error, InfoByte, Result};
 
 /// Represents either column or row of the [`ExtendedDataSquare`].
 ///
 /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum AxisType {
     /// A row of the data square.
@@ -311,6 +311,10 @@ impl ExtendedDataSquare {
 
     /// Returns the share of the provided coordinates.
     pub fn share(&self, row: u16, column: u16) -> Result<&[u8]> {
+        if column >= self.square_width {
+            return Err(Error::EdsIndexOutOfRange(row, column));
+        }
+
         let index = usize::from(row) * usize::from(self.square_width) + usize::from(column);
 
         self.data_square
@@ -322,6 +326,10 @@ impl ExtendedDataSquare {
     /// Returns the mutable share of the provided coordinates.
     #[cfg(any(test, feature = "test-utils"))]
     pub(crate) fn share_mut(&mut self, row: u16, column: u16) -> Result<&mut [u8]> {
+        if column >= self.square_width {
+            return Err(Error::EdsIndexOutOfRange(row, column));
+        }
+
         let index = usize::from(row) * usize::from(self.square_width) + usize::from(column);
 
         self.data_square
@@ -403,13 +411,9 @@ impl ExtendedDataSquare {
     ) -> Result<Vec<NamespacedData>> {
         let mut data = Vec::new();
 
-        for row in 0..self.square_width {
-            let Some(row_root) = dah.row_root(row) else {
+        for row in dah.row_indexes_for_namespace(namespace) {
+            if row >= self.square_width {
                 break;
-            };
-
-            if !row_root.contains::<NamespacedSha2Hasher>(*namespace) {
-                continue;
             }
 
             let mut shares = Vec::with_capacity(self.square_width.into());
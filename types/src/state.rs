@@ -2,15 +2,17 @@
 
 mod address;
 mod balance;
+mod bank;
 mod query_delegation;
 mod tx;
 
 pub use self::address::{AccAddress, Address, AddressKind, AddressTrait, ConsAddress, ValAddress};
 pub use self::balance::Balance;
+pub use self::bank::MsgSend;
 pub use self::query_delegation::{
     QueryDelegationResponse, QueryRedelegationsResponse, QueryUnbondingDelegationResponse,
 };
-pub use self::tx::{RawTx, TxResponse};
+pub use self::tx::{BlobTx, Fee, RawTx, SignedTx, TxBuilder, TxResponse};
 
 /// A 256-bit unsigned integer.
 pub type Uint = ruint::aliases::U256;
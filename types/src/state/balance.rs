@@ -23,6 +23,14 @@ impl Balance {
     pub fn validate(&self) -> Result<()> {
         validate_denom(&self.denom)
     }
+
+    /// Convert to the protobuf representation without validating the denomination.
+    pub(crate) fn into_raw_unchecked(self) -> RawCoin {
+        RawCoin {
+            denom: self.denom,
+            amount: self.amount.to_string(),
+        }
+    }
 }
 
 impl Serialize for Balance {
@@ -61,11 +69,7 @@ impl TryFrom<Balance> for RawCoin {
 
     fn try_from(value: Balance) -> Result<Self, Self::Error> {
         value.validate()?;
-
-        Ok(RawCoin {
-            denom: value.denom,
-            amount: value.amount.to_string(),
-        })
+        Ok(value.into_raw_unchecked())
     }
 }
 
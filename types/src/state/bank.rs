@@ -0,0 +1,91 @@
+use celestia_proto::cosmos::bank::v1beta1::MsgSend as RawMsgSend;
+use celestia_tendermint_proto::Protobuf;
+use prost::Message;
+use prost_types::Any;
+
+use crate::state::{AccAddress, Balance};
+use crate::{bail_validation, Error, Result};
+
+/// A message sending coins from one account to another.
+///
+/// Conversions into the raw types don't validate the `amount`, use [`MsgSend::validate`]
+/// for that. [`TxBuilder::sign`] validates the messages it signs.
+///
+/// [`TxBuilder::sign`]: crate::state::TxBuilder::sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgSend {
+    /// Address of the sender.
+    pub from_address: AccAddress,
+    /// Address of the recipient.
+    pub to_address: AccAddress,
+    /// Coins to be sent.
+    pub amount: Vec<Balance>,
+}
+
+impl MsgSend {
+    /// The type url of the message.
+    pub const TYPE_URL: &'static str = "/cosmos.bank.v1beta1.MsgSend";
+
+    /// Validate the sent coins.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no coins are sent, or if any of them
+    /// has an invalid denomination or a zero amount.
+    pub fn validate(&self) -> Result<()> {
+        if self.amount.is_empty() {
+            bail_validation!("no coins sent")
+        }
+
+        for coin in &self.amount {
+            coin.validate()?;
+
+            if coin.amount.is_zero() {
+                bail_validation!("zero amount of {} sent", coin.denom)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Protobuf<RawMsgSend> for MsgSend {}
+
+impl TryFrom<RawMsgSend> for MsgSend {
+    type Error = Error;
+
+    fn try_from(value: RawMsgSend) -> Result<Self, Self::Error> {
+        Ok(MsgSend {
+            from_address: value.from_address.parse()?,
+            to_address: value.to_address.parse()?,
+            amount: value
+                .amount
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<MsgSend> for RawMsgSend {
+    fn from(value: MsgSend) -> Self {
+        RawMsgSend {
+            from_address: value.from_address.to_string(),
+            to_address: value.to_address.to_string(),
+            amount: value
+                .amount
+                .into_iter()
+                .map(Balance::into_raw_unchecked)
+                .collect(),
+        }
+    }
+}
+
+impl From<MsgSend> for Any {
+    fn from(value: MsgSend) -> Self {
+        Any {
+            type_url: MsgSend::TYPE_URL.to_owned(),
+            value: RawMsgSend::from(value).encode_to_vec(),
+        }
+    }
+}
@@ -1,6 +1,25 @@
 use celestia_proto::cosmos::base::abci::v1beta1::TxResponse as RawTxResponse;
+use celestia_proto::cosmos::crypto::secp256k1::PubKey as RawPubKey;
+use celestia_proto::cosmos::tx::signing::v1beta1::SignMode;
+use celestia_proto::cosmos::tx::v1beta1::mode_info::{Single, Sum};
+use celestia_proto::cosmos::tx::v1beta1::{
+    AuthInfo, Fee as RawFee, ModeInfo, SignDoc, SignerInfo, TxBody, TxRaw,
+};
+use celestia_tendermint_proto::v0_34::types::BlobTx as RawBlobTx;
+use celestia_tendermint_proto::Protobuf;
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+use prost::Message;
+use prost_types::Any;
 use serde::{Deserialize, Serialize};
 
+use crate::consts::appconsts;
+use crate::state::{Balance, MsgSend, Uint};
+use crate::{bail_validation, Blob, Result};
+
+/// Type url of the secp256k1 public key.
+const SECP256K1_PUBKEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
 /// Raw transaction data.
 ///
 /// # Note
@@ -15,3 +34,468 @@ pub struct RawTx {
 
 /// Raw transaction response.
 pub type TxResponse = RawTxResponse;
+
+impl RawTx {
+    /// Create a raw transaction from its encoded bytes.
+    pub fn new(data: Vec<u8>) -> Self {
+        RawTx { data }
+    }
+
+    /// Get the encoded bytes of the transaction.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Fee paid for the execution of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fee {
+    /// Coins paid for the execution.
+    pub amount: Vec<Balance>,
+    /// Maximum amount of gas the transaction can consume.
+    pub gas_limit: u64,
+}
+
+impl Fee {
+    /// Create a fee paid in the [`BOND_DENOM`].
+    ///
+    /// [`BOND_DENOM`]: crate::consts::appconsts::BOND_DENOM
+    pub fn new(amount: Uint, gas_limit: u64) -> Self {
+        Fee {
+            amount: vec![Balance {
+                denom: appconsts::BOND_DENOM.to_owned(),
+                amount,
+            }],
+            gas_limit,
+        }
+    }
+}
+
+/// A builder of the Cosmos SDK transactions.
+///
+/// Transactions are signed with a secp256k1 key in the `SIGN_MODE_DIRECT`.
+///
+/// # Example
+///
+/// ```
+/// use celestia_types::blob::MsgPayForBlobs;
+/// use celestia_types::nmt::Namespace;
+/// use celestia_types::state::{BlobTx, Fee, TxBuilder, Uint};
+/// use celestia_types::Blob;
+/// use k256::ecdsa::SigningKey;
+/// # let signer = "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3".parse().unwrap();
+/// # let signing_key = SigningKey::from_slice(&[1; 32]).unwrap();
+///
+/// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
+/// let blob = Blob::new(namespace, b"some data to store on blockchain".to_vec())
+///     .expect("Failed to create a blob");
+/// let msg = MsgPayForBlobs::new(&[blob.clone()], signer).unwrap();
+///
+/// let tx = TxBuilder::new("private", 1, 0)
+///     .message(msg)
+///     .fee(Fee::new(Uint::from(2000), 100_000))
+///     .sign(&signing_key)
+///     .unwrap();
+///
+/// let blob_tx = BlobTx::new(tx, vec![blob]).unwrap();
+/// // bytes to be broadcasted to the consensus node
+/// let tx_bytes = blob_tx.to_vec();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TxBuilder {
+    chain_id: String,
+    account_number: u64,
+    sequence: u64,
+    messages: Vec<Any>,
+    memo: String,
+    timeout_height: u64,
+    fee: Fee,
+}
+
+impl TxBuilder {
+    /// Create a new builder of a transaction for the given chain and signer's account.
+    ///
+    /// `account_number` and `sequence` can be queried from the `auth` module of the consensus node.
+    pub fn new(chain_id: impl Into<String>, account_number: u64, sequence: u64) -> Self {
+        TxBuilder {
+            chain_id: chain_id.into(),
+            account_number,
+            sequence,
+            messages: Vec::new(),
+            memo: String::new(),
+            timeout_height: 0,
+            fee: Fee {
+                amount: Vec::new(),
+                gas_limit: 0,
+            },
+        }
+    }
+
+    /// Add a message to the transaction.
+    pub fn message(mut self, msg: impl Into<Any>) -> Self {
+        self.messages.push(msg.into());
+        self
+    }
+
+    /// Set the memo of the transaction.
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = memo.into();
+        self
+    }
+
+    /// Set the height after which the transaction will not be included in the block.
+    pub fn timeout_height(mut self, height: u64) -> Self {
+        self.timeout_height = height;
+        self
+    }
+
+    /// Set the fee paid for the transaction.
+    pub fn fee(mut self, fee: Fee) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Sign the transaction with the given key.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no messages in the transaction
+    /// or if the fee has an invalid denomination or any of the [`MsgSend`]s is invalid.
+    pub fn sign(self, key: &SigningKey) -> Result<SignedTx> {
+        if self.messages.is_empty() {
+            bail_validation!("transaction has no messages")
+        }
+
+        for msg in &self.messages {
+            // Decoding validates the addresses
+            if msg.type_url == MsgSend::TYPE_URL {
+                MsgSend::decode(&msg.value[..])?.validate()?;
+            }
+        }
+
+        for coin in &self.fee.amount {
+            coin.validate()?;
+        }
+
+        let body = TxBody {
+            messages: self.messages,
+            memo: self.memo,
+            timeout_height: self.timeout_height,
+            extension_options: Vec::new(),
+            non_critical_extension_options: Vec::new(),
+        };
+
+        let public_key = RawPubKey {
+            key: key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        };
+
+        let signer_info = SignerInfo {
+            public_key: Some(Any {
+                type_url: SECP256K1_PUBKEY_TYPE_URL.to_owned(),
+                value: public_key.encode_to_vec(),
+            }),
+            mode_info: Some(ModeInfo {
+                sum: Some(Sum::Single(Single {
+                    mode: SignMode::Direct.into(),
+                })),
+            }),
+            sequence: self.sequence,
+        };
+
+        let auth_info = AuthInfo {
+            signer_infos: vec![signer_info],
+            fee: Some(RawFee {
+                amount: self
+                    .fee
+                    .amount
+                    .into_iter()
+                    .map(Balance::into_raw_unchecked)
+                    .collect(),
+                gas_limit: self.fee.gas_limit,
+                payer: String::new(),
+                granter: String::new(),
+            }),
+            tip: None,
+        };
+
+        let sign_doc = SignDoc {
+            body_bytes: body.encode_to_vec(),
+            auth_info_bytes: auth_info.encode_to_vec(),
+            chain_id: self.chain_id,
+            account_number: self.account_number,
+        };
+
+        let signature: Signature = key.sign(&sign_doc.encode_to_vec());
+
+        Ok(SignedTx {
+            raw: TxRaw {
+                body_bytes: sign_doc.body_bytes,
+                auth_info_bytes: sign_doc.auth_info_bytes,
+                signatures: vec![signature.to_vec()],
+            },
+        })
+    }
+}
+
+/// A signed Cosmos SDK transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTx {
+    raw: TxRaw,
+}
+
+impl SignedTx {
+    /// Get the protobuf representation of the transaction.
+    pub fn as_raw(&self) -> &TxRaw {
+        &self.raw
+    }
+
+    /// Encode the transaction to bytes, ready to be broadcasted.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.raw.encode_to_vec()
+    }
+}
+
+impl From<SignedTx> for RawTx {
+    fn from(value: SignedTx) -> Self {
+        RawTx::new(value.to_vec())
+    }
+}
+
+/// A transaction paying for blobs, wrapped together with the blobs.
+///
+/// This is the format in which transactions with [`MsgPayForBlobs`] are
+/// broadcasted to the consensus nodes.
+///
+/// [`MsgPayForBlobs`]: crate::blob::MsgPayForBlobs
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobTx {
+    tx: SignedTx,
+    blobs: Vec<Blob>,
+}
+
+impl BlobTx {
+    /// Wrap the signed transaction with the blobs it pays for.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `blobs` is empty.
+    pub fn new(tx: SignedTx, blobs: Vec<Blob>) -> Result<Self> {
+        if blobs.is_empty() {
+            bail_validation!("no blobs provided")
+        }
+
+        Ok(BlobTx { tx, blobs })
+    }
+
+    /// Get the signed transaction.
+    pub fn tx(&self) -> &SignedTx {
+        &self.tx
+    }
+
+    /// Get the blobs.
+    pub fn blobs(&self) -> &[Blob] {
+        &self.blobs
+    }
+
+    /// Encode the transaction to bytes, ready to be broadcasted.
+    pub fn to_vec(&self) -> Vec<u8> {
+        RawBlobTx::from(self.clone()).encode_to_vec()
+    }
+}
+
+impl From<BlobTx> for RawBlobTx {
+    fn from(value: BlobTx) -> Self {
+        RawBlobTx {
+            tx: value.tx.to_vec(),
+            blobs: value.blobs.into_iter().map(Into::into).collect(),
+            type_id: appconsts::BLOB_TX_TYPE_ID.to_owned(),
+        }
+    }
+}
+
+impl From<BlobTx> for RawTx {
+    fn from(value: BlobTx) -> Self {
+        RawTx::new(value.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::MsgPayForBlobs;
+    use crate::nmt::Namespace;
+    use crate::state::AccAddress;
+    use celestia_tendermint::account::Id;
+    use k256::ecdsa::signature::Verifier;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_slice(&[7; 32]).unwrap()
+    }
+
+    fn address(key: &SigningKey) -> AccAddress {
+        AccAddress::new(Id::from(*key.verifying_key()))
+    }
+
+    #[test]
+    fn sign_msg_send() {
+        let key = signing_key();
+        let msg = MsgSend {
+            from_address: address(&key),
+            to_address: "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3"
+                .parse()
+                .unwrap(),
+            amount: vec![Balance {
+                denom: "utia".to_owned(),
+                amount: Uint::from(1000),
+            }],
+        };
+
+        let tx = TxBuilder::new("private", 12, 3)
+            .message(msg.clone())
+            .memo("memo")
+            .fee(Fee::new(Uint::from(2000), 100_000))
+            .sign(&key)
+            .unwrap();
+
+        let raw = TxRaw::decode(&tx.to_vec()[..]).unwrap();
+        assert_eq!(raw.signatures.len(), 1);
+
+        let body = TxBody::decode(&raw.body_bytes[..]).unwrap();
+        assert_eq!(body.memo, "memo");
+        assert_eq!(body.messages, vec![Any::from(msg)]);
+
+        let auth_info = AuthInfo::decode(&raw.auth_info_bytes[..]).unwrap();
+        assert_eq!(auth_info.signer_infos[0].sequence, 3);
+        let fee = auth_info.fee.unwrap();
+        assert_eq!(fee.gas_limit, 100_000);
+        assert_eq!(fee.amount[0].amount, "2000");
+        assert_eq!(fee.amount[0].denom, "utia");
+
+        let sign_doc = SignDoc {
+            body_bytes: raw.body_bytes,
+            auth_info_bytes: raw.auth_info_bytes,
+            chain_id: "private".to_owned(),
+            account_number: 12,
+        };
+        let signature = Signature::from_slice(&raw.signatures[0]).unwrap();
+
+        key.verifying_key()
+            .verify(&sign_doc.encode_to_vec(), &signature)
+            .unwrap();
+        // cosmos sdk accepts only signatures with low S
+        assert!(signature.normalize_s().is_none());
+    }
+
+    #[test]
+    fn sign_without_messages() {
+        TxBuilder::new("private", 0, 0)
+            .sign(&signing_key())
+            .unwrap_err();
+    }
+
+    #[test]
+    fn sign_with_invalid_fee_denom() {
+        let key = signing_key();
+        let fee = Fee {
+            amount: vec![Balance {
+                denom: "0tia".to_owned(),
+                amount: Uint::from(1),
+            }],
+            gas_limit: 1,
+        };
+
+        TxBuilder::new("private", 0, 0)
+            .message(MsgSend {
+                from_address: address(&key),
+                to_address: address(&key),
+                amount: vec![Balance {
+                    denom: "utia".to_owned(),
+                    amount: Uint::from(1),
+                }],
+            })
+            .fee(fee)
+            .sign(&key)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn sign_with_invalid_msg_send_denom() {
+        let key = signing_key();
+
+        TxBuilder::new("private", 0, 0)
+            .message(MsgSend {
+                from_address: address(&key),
+                to_address: address(&key),
+                amount: vec![Balance {
+                    denom: "0tia".to_owned(),
+                    amount: Uint::from(1),
+                }],
+            })
+            .fee(Fee::new(Uint::from(2000), 100_000))
+            .sign(&key)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn sign_with_invalid_msg_send_amount() {
+        let key = signing_key();
+        let msg_send = |amount| MsgSend {
+            from_address: address(&key),
+            to_address: address(&key),
+            amount,
+        };
+
+        for amount in [
+            vec![],
+            vec![Balance {
+                denom: "utia".to_owned(),
+                amount: Uint::ZERO,
+            }],
+        ] {
+            TxBuilder::new("private", 0, 0)
+                .message(msg_send(amount))
+                .fee(Fee::new(Uint::from(2000), 100_000))
+                .sign(&key)
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn blob_tx_envelope() {
+        let key = signing_key();
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blobs = vec![
+            Blob::new(namespace, vec![1; 100]).unwrap(),
+            Blob::new(namespace, vec![2; 1000]).unwrap(),
+        ];
+
+        let msg = MsgPayForBlobs::new(&blobs, address(&key)).unwrap();
+        let tx = TxBuilder::new("private", 0, 0)
+            .message(msg)
+            .sign(&key)
+            .unwrap();
+
+        let blob_tx = BlobTx::new(tx.clone(), blobs.clone()).unwrap();
+        let raw = RawBlobTx::decode(&blob_tx.to_vec()[..]).unwrap();
+
+        assert_eq!(raw.type_id, "BLOB");
+        assert_eq!(raw.tx, tx.to_vec());
+
+        let decoded_blobs = raw
+            .blobs
+            .into_iter()
+            .map(Blob::try_from)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(decoded_blobs, blobs);
+
+        BlobTx::new(tx, vec![]).unwrap_err();
+    }
+}
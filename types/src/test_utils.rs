@@ -22,6 +22,7 @@ use crate::{DataAvailabilityHeader, ExtendedDataSquare, ExtendedHeader, Validato
 pub struct ExtendedHeaderGenerator {
     chain_id: chain::Id,
     key: SigningKey,
+    next_key: Option<SigningKey>,
     current_header: Option<ExtendedHeader>,
 }
 
@@ -34,6 +35,7 @@ impl ExtendedHeaderGenerator {
         ExtendedHeaderGenerator {
             chain_id,
             key,
+            next_key: None,
             current_header: None,
         }
     }
@@ -70,7 +72,7 @@ impl ExtendedHeaderGenerator {
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> ExtendedHeader {
         let header = match self.current_header {
-            Some(ref header) => generate_next(1, header, &self.key, None),
+            Some(_) => self.generate_next(1, None),
             None => generate_new(GENESIS_HEIGHT, &self.chain_id, &self.key, None),
         };
 
@@ -92,7 +94,7 @@ impl ExtendedHeaderGenerator {
     #[allow(clippy::should_implement_trait)]
     pub fn next_with_dah(&mut self, dah: DataAvailabilityHeader) -> ExtendedHeader {
         let header = match self.current_header {
-            Some(ref header) => generate_next(1, header, &self.key, Some(dah)),
+            Some(_) => self.generate_next(1, Some(dah)),
             None => generate_new(GENESIS_HEIGHT, &self.chain_id, &self.key, Some(dah)),
         };
 
@@ -128,7 +130,7 @@ impl ExtendedHeaderGenerator {
     ///
     /// This method does not change the state of `ExtendedHeaderGenerator`.
     pub fn next_of(&self, header: &ExtendedHeader) -> ExtendedHeader {
-        generate_next(1, header, &self.key, None)
+        generate_next(1, header, &self.key, None, None)
     }
 
     /// Generates the next header of the provided header with the given [`DataAvailabilityHeader`].
@@ -155,7 +157,7 @@ impl ExtendedHeaderGenerator {
         header: &ExtendedHeader,
         dah: DataAvailabilityHeader,
     ) -> ExtendedHeader {
-        generate_next(1, header, &self.key, Some(dah))
+        generate_next(1, header, &self.key, None, Some(dah))
     }
 
     /// Generates the next amount of headers of the provided header.
@@ -221,13 +223,53 @@ impl ExtendedHeaderGenerator {
         }
 
         let header = match self.current_header {
-            Some(ref header) => generate_next(amount, header, &self.key, None),
+            Some(_) => self.generate_next(amount, None),
             None => generate_new(amount, &self.chain_id, &self.key, None),
         };
 
         self.current_header = Some(header.clone());
     }
 
+    /// Changes the validator set of the chain.
+    ///
+    /// The next generated header is still signed by the current validator set and announces
+    /// the new one, which signs all the headers after it. Headers signed by the different
+    /// validator sets can't be verified against each other unless they are adjacent.
+    ///
+    /// ```
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    ///
+    /// let mut gen = ExtendedHeaderGenerator::new();
+    /// let header1 = gen.next();
+    ///
+    /// gen.change_validator_set();
+    /// let header2 = gen.next();
+    /// let header3 = gen.next();
+    /// let header4 = gen.next();
+    ///
+    /// header1.verify(&header2).unwrap();
+    /// header2.verify(&header3).unwrap();
+    /// header1.verify(&header4).unwrap_err();
+    /// ```
+    pub fn change_validator_set(&mut self) {
+        self.next_key = Some(SigningKey::new(rand::thread_rng()));
+    }
+
+    fn generate_next(
+        &mut self,
+        increment: u64,
+        dah: Option<DataAvailabilityHeader>,
+    ) -> ExtendedHeader {
+        let current = self.current_header.as_ref().expect("no current header");
+        let header = generate_next(increment, current, &self.key, self.next_key.as_ref(), dah);
+
+        if let Some(next_key) = self.next_key.take() {
+            self.key = next_key;
+        }
+
+        header
+    }
+
     /// Create a "forked" generator for "forking" the chain.
     ///
     /// ```
@@ -424,11 +466,19 @@ fn generate_next(
     increment: u64,
     current: &ExtendedHeader,
     signing_key: &SigningKey,
+    next_signing_key: Option<&SigningKey>,
     dah: Option<DataAvailabilityHeader>,
 ) -> ExtendedHeader {
     assert!(increment > 0);
 
-    let validator_address = current.validator_set.validators()[0].address;
+    // Use the validator set announced by the current header
+    let validator_set = if current.header.next_validators_hash == current.validator_set.hash() {
+        current.validator_set.clone()
+    } else {
+        validator_set_of(signing_key)
+    };
+    let next_validator_set = next_signing_key.map(validator_set_of);
+    let validator_address = validator_set.validators()[0].address;
 
     let height = (current.header.height.value() + increment)
         .try_into()
@@ -479,20 +529,51 @@ fn generate_next(
                 signature: None,
             }],
         },
-        validator_set: current.validator_set.clone(),
+        validator_set,
         dah: dah.unwrap_or_else(|| DataAvailabilityHeader::from_eds(&ExtendedDataSquare::empty())),
     };
 
-    hash_and_sign(&mut header, signing_key);
+    let next_validators_hash = next_validator_set
+        .as_ref()
+        .unwrap_or(&header.validator_set)
+        .hash();
+    hash_and_sign_with_next(&mut header, signing_key, next_validators_hash);
     header.validate().expect("invalid header generated");
-    current.verify(&header).expect("invalid header generated");
+
+    if increment == 1 || current.validator_set.hash() == header.validator_set.hash() {
+        current.verify(&header).expect("invalid header generated");
+    }
 
     header
 }
 
+/// Validator set with a single validator of the given key.
+fn validator_set_of(signing_key: &SigningKey) -> ValidatorSet {
+    let pub_key_bytes = signing_key.verification_key().to_bytes();
+    let pub_key = PublicKey::from_raw_ed25519(&pub_key_bytes).unwrap();
+    let validator = celestia_tendermint::validator::Info {
+        address: celestia_tendermint::account::Id::from(pub_key),
+        pub_key,
+        power: 5000_u32.into(),
+        name: None,
+        proposer_priority: 0_i64.into(),
+    };
+
+    ValidatorSet::new(vec![validator.clone()], Some(validator))
+}
+
 fn hash_and_sign(header: &mut ExtendedHeader, signing_key: &SigningKey) {
+    let next_validators_hash = header.validator_set.hash();
+    hash_and_sign_with_next(header, signing_key, next_validators_hash);
+}
+
+fn hash_and_sign_with_next(
+    header: &mut ExtendedHeader,
+    signing_key: &SigningKey,
+    next_validators_hash: Hash,
+) {
     header.header.validators_hash = header.validator_set.hash();
-    header.header.next_validators_hash = header.validator_set.hash();
+    header.header.next_validators_hash = next_validators_hash;
     header.header.data_hash = header.dah.hash();
     header.commit.block_id.hash = header.header.hash();
 
@@ -561,6 +642,23 @@ mod tests {
         headers[5].verify_range(&headers[10..]).unwrap();
     }
 
+    #[test]
+    fn change_validator_set() {
+        let mut gen = ExtendedHeaderGenerator::new();
+
+        let genesis = gen.next();
+        gen.change_validator_set();
+        let headers = gen.next_many(10);
+
+        genesis.verify_adjacent_range(&headers).unwrap();
+        headers[0].verify(&headers[5]).unwrap_err();
+        headers[1].verify(&headers[9]).unwrap();
+        assert_ne!(
+            genesis.validator_set.hash(),
+            headers[1].validator_set.hash()
+        );
+    }
+
     #[test]
     fn generate_and_skip() {
         let mut gen = ExtendedHeaderGenerator::new();